@@ -1,56 +1,133 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    entry_point, to_binary, Addr, Api, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage,
+    Timestamp,
 };
 use secret_toolkit_crypto::hkdf_sha_512;
-use secret_toolkit_permit::{validate, Permit};
+use secret_toolkit_permit::{validate, Permit, RevokedPermits};
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 
 use crate::error::ContractError;
+use crate::eval;
 use crate::msg::{
-    CommunityCardsResponse, ExecuteMsg, InstantiateMsg, LastHandLogResponse, QueryMsg, QueryWithPermit, ResponsePayload, ShowdownPlayer, ShowdownResponse, StartGamePlayer, StartGameResponse
+    AllCommunityCardsResponse, BulkStartGameResponse, CommunityCardsResponse, CompressedResponse, ConfigResponse, DeckCommitmentResponse, DeckVariantResponse, ExecuteMsg, HandChainResponse, HandHistoryResponse, InstantiateMsg, LastHandLogQueryResponse, LastHandLogResponse, MigrateMsg, NextTableIdResponse, PartialSumResponse, PlayerHandResponse, PlayerShare, QueryMsg, QueryWithPermit, ResponsePayload, ShowdownPlayer, ShowdownResponse, ShowdownStatusResponse, ShowdownWithWinnersResponse, StartGameArgs, StartGamePlayer, StartGameResponse, TableIntent, TableStatePlayer, TableStateResponse, TableSummary, TablesResponse, UnknownCardsResponse, VerifyShuffleResponse, VersionResponse, WinnersResponse
 };
 use crate::state::{
-    load_table, save_table, Card, CommunityCards, Config, Deck, Flop, GameState,
-    Player, PokerTable, River, Turn, CONFIG_KEY, COUNTER_KEY, PREFIX_REVOKED_PERMITS,
+    append_hand_link, delete_table, hand_chain, hand_history, index_table_id, last_hand_hash, load_game_result, load_player_limits, load_table, next_table_id, reserve_table_id, save_game_result, save_table, table_id_used,
+    Card, CommunityCards, Config, Deck, DeckVariant, ExecuteMetrics, Flop, GameResult, GameState, Instantiation, Player, PlayerLimits, PokerTable, River, SharingScheme, Stakes, Turn,
+    CONFIG_KEY, CONTRACT_VERSION_KEY, COUNTER_KEY, INSTANTIATION_KEY, LEGACY_CONFIG_KEY, MAX_PLAYERS, METRICS_KEY, MIN_PLAYERS, PLAYER_LIMITS_KEY, PREFIX_REVOKED_PERMITS, TABLE_IDS,
 };
 
-const MIN_PLAYERS: usize = 2;
-const MAX_PLAYERS: usize = 9;
 const COMMUNITY_CARD_PHASES: usize = 3;
 const SECRET_LENGTH: usize = 64;
 const RANDOM_SEED_SIZE: usize = 16;
+const SHUFFLE_MAX_RETRIES: u64 = 16;
+// highest cap a StartGame can ask for, the attempts of each swap are bounded whatever the hash sequence
+const MAX_SHUFFLE_RETRIES: u64 = 64;
 const RESPONSE_KEY: &str = "response";
+const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+const DEFAULT_HISTORY_LIMIT: u32 = 10;
+const MAX_HISTORY_LIMIT: u32 = 30;
+const DEFAULT_TABLES_LIMIT: u32 = 30;
+const MAX_TABLES_LIMIT: u32 = 100;
+const MAX_BULK_START_GAMES: usize = 20;
+
+// A full table is dealt from a single deck: two hole cards per player, one burn per phase and a five cards board.
+const _: () = assert!(required_cards(MAX_PLAYERS, COMMUNITY_CARD_PHASES) <= 52);
+
+/*
+ * Cards needed to deal a hand: two hole cards per player, a burned card before each phase
+ * and the board itself (three cards on the flop, then one per phase).
+ */
+const fn required_cards(player_count: usize, phases: usize) -> usize {
+    player_count * 2 + phases + if phases == 0 { 0 } else { phases + 2 }
+}
+
+pub use helpers::reconstruct_additive;
 
 mod helpers {
     use super::*;
 
-    pub fn generate_random_number(env: &Env, counter: &mut u128) -> StdResult<u64> {
+    /*
+     * The counter makes every draw of a block unique, it must never wrap around or the draws would repeat.
+     * The salt binds the draws to this contract and block, so contracts sharing the block randomness get independent streams.
+     */
+    pub fn generate_random_number(env: &Env, counter: &mut u128) -> Result<u128, ContractError> {
+        let next_counter = counter.checked_add(1).ok_or(ContractError::CounterExhausted {})?;
+        let mut salt = env.contract.address.as_bytes().to_vec();
+        salt.extend_from_slice(&env.block.height.to_le_bytes());
         let secret = hkdf_sha_512(
-            &Some(vec![0u8; SECRET_LENGTH]),
-            &env.block.random.as_ref().unwrap(),
+            &Some(salt),
+            env.block.random.as_ref().ok_or(ContractError::MissingRandomness {})?,
             &counter.to_le_bytes(),
             SECRET_LENGTH,
         )?;
 
-        *counter += 1;
-        Ok(u64::from_le_bytes(secret[..8].try_into().unwrap()))
+        *counter = next_counter;
+        Ok(u128::from_le_bytes(secret[..16].try_into().unwrap()))
+    }
+
+    /*
+     * Source of the draws dealing a hand, the contract draws from the block randomness
+     * while tests can inject a fixed sequence to assert the exact deal.
+     */
+    pub trait Randomness {
+        fn next(&mut self) -> Result<u128, ContractError>;
+    }
+
+    // generate_random_number over the stored counter, which the caller saves back once the hand is dealt
+    pub struct BlockRandomness<'a> {
+        env: &'a Env,
+        pub counter: u128,
+    }
+
+    impl<'a> BlockRandomness<'a> {
+        pub fn new(env: &'a Env, counter: u128) -> Self {
+            BlockRandomness { env, counter }
+        }
+    }
+
+    impl Randomness for BlockRandomness<'_> {
+        fn next(&mut self) -> Result<u128, ContractError> {
+            generate_random_number(self.env, &mut self.counter)
+        }
+    }
+
+    /*
+        The Showdown query looks players up by hand_secret while community queries match the phase secrets,
+        so every secret of a hand must be unique for a secret to only ever unlock what it was generated for.
+        A collision between two random u128 is astronomically rare, but when it happens the secret is simply drawn again.
+     */
+    pub fn generate_distinct_secrets<F>(count: usize, mut next_secret: F) -> Result<Vec<u128>, ContractError>
+    where
+        F: FnMut() -> Result<u128, ContractError>,
+    {
+        let mut secrets: Vec<u128> = Vec::with_capacity(count);
+
+        while secrets.len() < count {
+            let secret = next_secret()?;
+            if !secrets.contains(&secret) {
+                secrets.push(secret);
+            }
+        }
+
+        Ok(secrets)
     }
 
     pub fn additive_secret_sharing(
-        env: &Env,
+        rng: &mut dyn Randomness,
         players: usize,
-        secret: u64,
-        counter: &mut u128,
-    ) -> StdResult<Vec<u64>> {
+        secret: u128,
+    ) -> Result<Vec<u128>, ContractError> {
         let mut shares = Vec::with_capacity(players);
-        let mut sum: u64 = 0;
+        let mut sum: u128 = 0;
 
         for _ in 0..(players - 1) {
-            let share = generate_random_number(env, counter)?;
+            let share = rng.next()?;
             shares.push(share);
             sum = sum.wrapping_add(share);
         }
@@ -59,6 +136,102 @@ mod helpers {
         Ok(shares)
     }
 
+    // inverse of additive_secret_sharing, clients reconstructing the secrets must match this wrapping sum
+    pub fn reconstruct_additive(shares: &[u128]) -> u128 {
+        shares.iter().copied().fold(0, u128::wrapping_add)
+    }
+
+    /*
+     * (t, n) threshold sharing over the prime field of order 2^127 - 1: any threshold of the n shares reconstruct
+     * the secret, fewer reveal nothing about it. Shares are evaluated at the players' share_index, 1 to n.
+     */
+    pub mod shamir {
+        use super::*;
+
+        pub const PRIME: u128 = (1 << 127) - 1;
+
+        fn add_mod(a: u128, b: u128) -> u128 {
+            // both operands are below 2^127, their sum can't overflow
+            let sum = a + b;
+            if sum >= PRIME { sum - PRIME } else { sum }
+        }
+
+        fn sub_mod(a: u128, b: u128) -> u128 {
+            if a >= b { a - b } else { a + PRIME - b }
+        }
+
+        fn mul_mod(a: u128, mut b: u128) -> u128 {
+            let mut a = a % PRIME;
+            let mut product = 0;
+
+            while b > 0 {
+                if b & 1 == 1 {
+                    product = add_mod(product, a);
+                }
+                a = add_mod(a, a);
+                b >>= 1;
+            }
+
+            product
+        }
+
+        // Fermat's little theorem, a must not be a multiple of PRIME
+        fn inv_mod(a: u128) -> u128 {
+            let mut base = a % PRIME;
+            let mut exponent = PRIME - 2;
+            let mut result = 1;
+
+            while exponent > 0 {
+                if exponent & 1 == 1 {
+                    result = mul_mod(result, base);
+                }
+                base = mul_mod(base, base);
+                exponent >>= 1;
+            }
+
+            result
+        }
+
+        // the secret must be a field element, below PRIME
+        pub fn split(
+            rng: &mut dyn Randomness,
+            secret: u128,
+            threshold: usize,
+            share_count: usize,
+        ) -> Result<Vec<u128>, ContractError> {
+            let mut coefficients = vec![secret % PRIME];
+            for _ in 1..threshold {
+                coefficients.push(rng.next()? % PRIME);
+            }
+
+            Ok((1..=share_count as u128)
+                .map(|x| {
+                    coefficients
+                        .iter()
+                        .rev()
+                        .fold(0, |acc, &coefficient| add_mod(mul_mod(acc, x), coefficient))
+                })
+                .collect())
+        }
+
+        /*
+         * Lagrange interpolation at 0 of the (share_index, share) points, their share_index must be distinct and non zero.
+         * With fewer points than the threshold the result is unrelated to the secret.
+         */
+        pub fn reconstruct(points: &[(u128, u128)]) -> u128 {
+            points.iter().enumerate().fold(0, |secret, (i, &(x_i, y_i))| {
+                let (numerator, denominator) = points
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .fold((1, 1), |(numerator, denominator), (_, &(x_j, _))| {
+                        (mul_mod(numerator, x_j), mul_mod(denominator, sub_mod(x_j, x_i)))
+                    });
+                add_mod(secret, mul_mod(y_i, mul_mod(numerator, inv_mod(denominator))))
+            })
+        }
+    }
+
 
     /*
         Shuffle the deck using a seed-based random number generator.
@@ -110,9 +283,9 @@ mod helpers {
         Even so, we implement the rejection sampling method to ensure uniform distribution, 
         it simply works by discarding any random number that falls within the biased range.
          */
-    pub fn shuffle_deck(deck: &mut Deck, seed: u64) {
-        let mut rng = Sha256::new();
+    pub fn shuffle_deck(deck: &mut Deck, seed: u64, max_retries: u64) -> bool {
         let mut deck_len = deck.cards.len();
+        let mut fallback_used = false;
 
         while deck_len > 1 {
             deck_len -= 1;
@@ -121,27 +294,56 @@ mod helpers {
 
             let threshold = (u64::MAX / upper_bound as u64) * upper_bound as u64;
             
-            let random_index;
+            let swap_rng = swap_hasher(seed, deck_len);
+            let mut random_index = None;
             let mut attempt_counter: u64 = 0;
 
-            loop {
-                rng.update(&seed.to_le_bytes());
-                rng.update(&(deck_len as u64).to_le_bytes());
-                rng.update(&attempt_counter.to_le_bytes());
-
-                let hash = rng.finalize_reset();
+            while attempt_counter < max_retries {
+                let hash = swap_rng.clone().chain_update(attempt_counter.to_le_bytes()).finalize();
                 let random_value = u64::from_le_bytes(hash[..8].try_into().unwrap());
                 
                 if random_value < threshold {
-                    random_index = (random_value as usize) % upper_bound;
+                    random_index = Some((random_value as usize) % upper_bound);
                     break;
                 }
 
                 attempt_counter += 1;
             }
 
+            let random_index = random_index.unwrap_or_else(|| {
+                fallback_used = true;
+                fallback_index(&swap_rng, upper_bound)
+            });
+
             deck.cards.swap(deck_len, random_index);
         }
+
+        fallback_used
+    }
+
+    /*
+     * The seed and deck length are absorbed once per swap, each attempt only hashes its counter on a copy of that state.
+     * The stream is the same as hashing seed || deck_len || attempt from scratch, so archived seeds still verify.
+     */
+    pub fn swap_hasher(seed: u64, deck_len: usize) -> Sha256 {
+        Sha256::new()
+            .chain_update(seed.to_le_bytes())
+            .chain_update((deck_len as u64).to_le_bytes())
+    }
+
+    /*
+        Fallback of the rejection sampling once max_retries is reached, so the gas spent by a shuffle is bounded.
+
+        The index is the modulo of a 128-bit value instead of a 64-bit one, with the same reasoning as above the bias is at most
+        upper_bound / 2^128 < 2^-121, far below anything observable.
+        Reaching this fallback with the default cap requires SHUFFLE_MAX_RETRIES consecutive rejections, each having a probability
+        below 2^-58, so in practice it is never used unless a table explicitly lowers the cap.
+     */
+    pub fn fallback_index(swap_rng: &Sha256, upper_bound: usize) -> usize {
+        let hash = swap_rng.clone().chain_update(u64::MAX.to_le_bytes()).finalize();
+        let random_value = u128::from_le_bytes(hash[..16].try_into().unwrap());
+
+        (random_value % upper_bound as u128) as usize
     }
 }
 
@@ -156,6 +358,46 @@ mod state_utils {
     ) -> Result<PokerTable, ContractError> {
         load_table(storage, table_id).ok_or(ContractError::TableNotFound { table_id })
     }
+
+    pub fn increment_metrics<F>(storage: &mut dyn cosmwasm_std::Storage, action: F) -> StdResult<()>
+    where
+        F: FnOnce(&mut ExecuteMetrics),
+    {
+        // absent on contracts instantiated before the metrics, they start from zero
+        let mut metrics = METRICS_KEY.may_load(storage)?.unwrap_or_default();
+        action(&mut metrics);
+        METRICS_KEY.save(storage, &metrics)
+    }
+
+    /*
+     * The players whose hands are shown, shared by the Showdowns and the hand logs: a repeated id would reveal
+     * a hand twice and a folded player's cards must stay private, whatever path reveals them.
+     */
+    pub fn showdown_players<'a>(
+        table: &'a PokerTable,
+        table_id: u32,
+        player_ids: &[Uuid],
+    ) -> Result<Vec<&'a Player>, ContractError> {
+        let unique_ids: HashSet<_> = player_ids.iter().collect();
+        if unique_ids.len() != player_ids.len() {
+            return Err(ContractError::DuplicatePlayerIds {});
+        }
+
+        player_ids
+            .iter()
+            .map(|player_id| {
+                let player = table
+                    .players
+                    .iter()
+                    .find(|player| &player.player_id == player_id)
+                    .ok_or_else(|| ContractError::PlayerNotFound { table_id, player: player_id.to_string() })?;
+                if player.folded {
+                    return Err(ContractError::PlayerFolded { table_id, player: player_id.to_string() });
+                }
+                Ok(player)
+            })
+            .collect()
+    }
 }
 
 
@@ -188,61 +430,97 @@ mod query_handlers {
             
            to_binary(&serialized?)
             }
+            QueryWithPermit::UnknownCards { table_id } => {
+                let unknown_cards = query_unknown_cards(deps, table_id, viewer)?;
+                let serialized = serde_json_wasm::to_string(&unknown_cards)
+                    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+                to_binary(&serialized)
+            }
+            QueryWithPermit::Showdown { table_id, player_ids } => {
+                let showdown = query_showdown_by_ids(deps, table_id, player_ids, viewer)?;
+                let serialized = serde_json_wasm::to_string(&showdown)
+                    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+                to_binary(&serialized)
+            }
+            QueryWithPermit::LastHandLog { table_id } => {
+                let last_hand_log = query_last_hand_log(deps, table_id, viewer)?;
+                let serialized = serde_json_wasm::to_string(&last_hand_log)
+                    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+                to_binary(&serialized)
+            }
+            QueryWithPermit::CommunityCards { table_id, game_state } => {
+                let community_cards = query_permit_community_cards(deps, table_id, game_state, viewer)?;
+                let serialized = serde_json_wasm::to_string(&community_cards)
+                    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+                to_binary(&serialized)
+            }
+            QueryWithPermit::AllPlayerShares { table_id } => {
+                let all_player_shares = query_all_player_shares(deps, table_id, viewer)?;
+                let serialized = serde_json_wasm::to_string(&all_player_shares)
+                    .map_err(|e| StdError::generic_err(e.to_string()))?;
+
+                to_binary(&serialized)
+            }
         }
     }
 
-    pub fn query_player_private_data(
-        deps: Deps,
-        table_id: u32,
-        pub_key: String,
-    ) -> StdResult<PlayerDataResponse> {
+    // for players who missed the StartGame that emitted it, only the players seated at the table can read it
+    pub fn query_last_hand_log(deps: Deps, table_id: u32, pub_key: String) -> StdResult<LastHandLogQueryResponse> {
         let table =
             load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-        table
-            .players
-            .iter()
-            .find(|p| p.public_key == pub_key)
-            .cloned()
-            .ok_or(StdError::generic_err("No player found"))
-            .map(|player| PlayerDataResponse {
-                table_id,
-                hand_ref: table.hand_ref,
-                hand: player.hand,
-                hand_secret: player.hand_secret.to_string(),
-                flop_secret_share: player.flop_secret_share.to_string(),
-                turn_secret_share: player.turn_secret_share.to_string(),
-                river_secret_share: player.river_secret_share.to_string(),
-            })
+        if !table.players.iter().any(|p| p.public_key == pub_key) {
+            return Err(StdError::generic_err("No player found"));
+        }
+
+        let history = hand_history(table_id);
+        let last_hand = match history.get_len(deps.storage)? {
+            0 => None,
+            len => Some(history.get_at(deps.storage, len - 1)?),
+        };
+
+        Ok(LastHandLogQueryResponse { table_id, last_hand })
     }
 
-    pub fn query_community_cards(
+    /*
+     * A seated player can read a street without its secret, but only once it is public: revealed by the owner
+     * or past the showdown. Otherwise a single player could peek at the streets to come.
+     */
+    pub fn query_permit_community_cards(
         deps: Deps,
         table_id: u32,
         game_state: GameState,
-        secret_key: u64,
+        pub_key: String,
     ) -> StdResult<CommunityCardsResponse> {
         let table =
             load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-        let (stored_key, cards) = match game_state {
-            GameState::Flop => (
-                table.community_cards.flop.secret,
-                table.community_cards.flop.cards,
-            ),
-            GameState::Turn => (
-                table.community_cards.turn.secret,
-                vec![table.community_cards.turn.card],
-            ),
-            GameState::River => (
-                table.community_cards.river.secret,
-                vec![table.community_cards.river.card],
-            ),
+        if !table.players.iter().any(|p| p.public_key == pub_key) {
+            return Err(StdError::generic_err("No player found"));
+        }
+
+        let community_cards = &table.community_cards;
+        let (retrieved_at, cards) = match game_state {
+            GameState::Flop => (community_cards.flop.retrieved_at, community_cards.flop.cards.clone()),
+            GameState::Turn => community_cards
+                .turn
+                .as_ref()
+                .map(|turn| (turn.retrieved_at, vec![turn.card.clone()]))
+                .ok_or_else(|| StdError::generic_err("Phase not dealt"))?,
+            GameState::River => community_cards
+                .river
+                .as_ref()
+                .map(|river| (river.retrieved_at, vec![river.card.clone()]))
+                .ok_or_else(|| StdError::generic_err("Phase not dealt"))?,
             _ => return Err(StdError::generic_err("Invalid game state")),
         };
 
-        if stored_key != secret_key {
-            return Err(StdError::generic_err("Invalid viewing key"));
+        if retrieved_at.is_none() && table.showdown_retrieved_at.is_none() {
+            return Err(StdError::generic_err("Phase not revealed yet"));
         }
 
         Ok(CommunityCardsResponse {
@@ -250,919 +528,6319 @@ mod query_handlers {
             hand_ref: table.hand_ref,
             game_state,
             community_cards: cards,
+            retrieved_at,
         })
     }
 
-    pub fn query_showdown(
-        deps: Deps,
-        table_id: u32,
-        flop_secret: Option<u64>,
-        turn_secret: Option<u64>,
-        river_secret: Option<u64>,
-        players_secrets: Vec<u64>,
-    ) -> StdResult<ShowdownResponse> {
+    /*
+     * A player only knows their own hand and the streets already revealed, the whole board being public
+     * once the showdown is retrieved. Other players' hands stay unknown, even after the showdown.
+     */
+    pub fn query_unknown_cards(deps: Deps, table_id: u32, pub_key: String) -> StdResult<UnknownCardsResponse> {
         let table =
             load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-        let mut community_cards = Vec::new();
+        let player = table
+            .players
+            .iter()
+            .find(|p| p.public_key == pub_key)
+            .ok_or(StdError::generic_err("No player found"))?;
 
-        if let Some(secret) = flop_secret {
-            if table.community_cards.flop.secret != secret {
-                return Err(StdError::generic_err("Invalid secret key"));
-            }
-            community_cards.extend(table.community_cards.flop.cards.clone());
-        }
+        let community_cards = &table.community_cards;
+        let showdown = table.showdown_retrieved_at.is_some();
+        let mut known: HashSet<Card> = player.hand.iter().cloned().collect();
 
-        if let Some(secret) = turn_secret {
-            if table.community_cards.turn.secret != secret {
-                return Err(StdError::generic_err("Invalid secret key"));
-            }
-            community_cards.push(table.community_cards.turn.card);
+        if showdown || community_cards.flop.retrieved_at.is_some() {
+            known.extend(community_cards.flop.cards.iter().cloned());
         }
-
-        if let Some(secret) = river_secret {
-            if table.community_cards.river.secret != secret {
-                return Err(StdError::generic_err("Invalid secret key"));
-            }
-            community_cards.push(table.community_cards.river.card);
+        if let Some(turn) = community_cards.turn.as_ref().filter(|turn| showdown || turn.retrieved_at.is_some()) {
+            known.insert(turn.card.clone());
+        }
+        if let Some(river) = community_cards.river.as_ref().filter(|river| showdown || river.retrieved_at.is_some()) {
+            known.insert(river.card.clone());
         }
 
-        let players_cards = players_secrets
-            .iter()
-            .map(|secret| {
-                table
-                    .players
-                    .iter()
-                    .find(|player| &player.hand_secret == secret)
-                    .map(|player| (player.player_id.clone(), player.hand.clone()))
-                    .ok_or_else(|| StdError::generic_err("Player not found"))
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        Ok(ShowdownResponse {
+        Ok(UnknownCardsResponse {
             table_id,
             hand_ref: table.hand_ref,
-            players_cards,
-            community_cards: Some(community_cards),
+            cards: table.deck_variant.deck().cards.into_iter().filter(|card| !known.contains(card)).collect(),
         })
     }
-}
 
+    /*
+     * pub_key is the address the permit was validated for, so the only seat it can read is the one registered
+     * under that exact key, anyone else is rejected as Unauthorized. The shares come with their share_index.
+     */
+    pub fn query_player_private_data(
+        deps: Deps,
+        table_id: u32,
+        pub_key: String,
+    ) -> StdResult<PlayerDataResponse> {
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-mod execute_handlers {
-    use super::{state_utils::load_table_or_error, *};
+        let player = table
+            .players
+            .iter()
+            .find(|p| p.public_key == pub_key)
+            .ok_or_else(|| StdError::generic_err(ContractError::Unauthorized {}.to_string()))?;
 
-    pub fn handle_start_game(
-        deps: DepsMut,
-        env: Env,
-        table_id: u32,
-        hand_ref: u32,
-        players_info: Vec<StartGamePlayer>,
-        prev_hand_showdown_players: Vec<Uuid>,
-    ) -> Result<Response, ContractError> {
-        validate_players(&players_info)?;
-        let previous_hand_log = create_previous_hand_log(deps.as_ref(), table_id, prev_hand_showdown_players)?;
-        let mut counter = COUNTER_KEY.load(deps.storage)?;
-        let mut deck = initialize_deck(&env, &mut counter)?;
-        let player_cards = distribute_player_cards(&mut deck, &players_info);
-        let mut secrets = Vec::with_capacity(COMMUNITY_CARD_PHASES);
-        let community_cards =
-            generate_community_cards(&env, &mut counter, &mut secrets, &mut deck, players_info.len())?;
-        let players = create_players(
-            players_info,
-            player_cards,
-            &secrets,
-            &env,
-            &mut counter,
-        )?;
+        Ok(player_data(table_id, &table, player))
+    }
 
-        let table = PokerTable {
-            hand_ref,
-            players,
-            community_cards,
-            showdown_retrieved_at: None,
-        };
+    /*
+     * Together these reveal every hand and street of the table, so only the owner's permit can read them:
+     * neither a dealer nor a seated player.
+     */
+    pub fn query_all_player_shares(deps: Deps, table_id: u32, viewer: String) -> StdResult<Vec<PlayerDataResponse>> {
+        if CONFIG_KEY.load(deps.storage)?.owner != viewer {
+            return Err(StdError::generic_err(ContractError::Unauthorized {}.to_string()));
+        }
 
-        save_table(deps.storage, table_id, &table)?;
-        COUNTER_KEY.save(deps.storage, &counter)?;
+        let table = load_table(deps.storage, table_id)
+            .ok_or_else(|| StdError::generic_err(ContractError::TableNotFound { table_id }.to_string()))?;
 
-        create_start_game_response(
-            table_id,
-            hand_ref,
-            &table.players,
-            previous_hand_log,
-        )
+        Ok(table.players.iter().map(|player| player_data(table_id, &table, player)).collect())
     }
 
-    fn validate_players(players_info: &[StartGamePlayer]) -> Result<(), ContractError> {
-        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&players_info.len()) {
-            return Err(ContractError::InvalidPlayerCount {
-                count: players_info.len(),
-            });
+    fn player_data(table_id: u32, table: &PokerTable, player: &Player) -> PlayerDataResponse {
+        PlayerDataResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            hand: player.hand.clone(),
+            hand_secret: player.hand_secret.to_string(),
+            flop_secret_share: player.flop_secret_share.to_string(),
+            turn_secret_share: player.turn_secret_share.to_string(),
+            river_secret_share: player.river_secret_share.to_string(),
+            share_index: player.share_index,
+            sharing_scheme: table.sharing_scheme.clone(),
         }
+    }
 
-        let unique_keys: HashSet<_> = players_info.iter().map(|p| &p.public_key).collect();
+    pub fn query_community_cards(
+        deps: Deps,
+        table_id: u32,
+        game_state: GameState,
+        secret_key: u128,
+    ) -> StdResult<CommunityCardsResponse> {
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-        if unique_keys.len() != players_info.len() {
-            return Err(ContractError::DuplicatePublicKeys {});
+        // same error as the CommunityCards execute for a street without cards
+        if !table.community_cards.is_dealt(&game_state) {
+            let error = ContractError::GameStateError {
+                method: "query_community_cards".to_string(),
+                table_id,
+                game_state: Some(game_state),
+            };
+            return Err(StdError::generic_err(error.to_string()));
         }
 
-        Ok(())
-    }
-
-    fn initialize_deck(env: &Env, counter: &mut u128) -> Result<Deck, ContractError> {
-        let mut deck = Deck::new();
-        let seed = helpers::generate_random_number(env, counter)?;
-        helpers::shuffle_deck(&mut deck, seed);
-        Ok(deck)
-    }
+        let cards = street_cards(deps, &table.community_cards, &game_state, secret_key)?
+            .ok_or_else(|| secret_mismatch(&game_state))?;
 
-    fn distribute_player_cards(
-        deck: &mut Deck,
-        players: &[StartGamePlayer],
-    ) -> Vec<(String, Vec<Card>)> {
-        players
-            .iter()
-            .map(|player| {
-                (
-                    player.public_key.clone(),
-                    vec![
-                        deck.cards.pop().unwrap().clone(),
-                        deck.cards.pop().unwrap().clone(),
-                    ],
-                )
-            })
-            .collect()
+        Ok(CommunityCardsResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            retrieved_at: table.community_cards.retrieved_at(&game_state),
+            game_state,
+            community_cards: cards,
+        })
     }
 
-    fn generate_community_cards(
-        env: &Env,
-        counter: &mut u128,
-        secrets: &mut Vec<(u64, Vec<u64>)>,
-        deck: &mut Deck,
-        player_count: usize,
-    ) -> Result<CommunityCards, ContractError> {
-
-        for _ in 0..COMMUNITY_CARD_PHASES {
-            let secret = helpers::generate_random_number(env, counter)?;
-            let shares = helpers::additive_secret_sharing(env, player_count, secret, counter)?;
-            secrets.push((secret, shares));
+    pub fn query_retrieved_community_cards(
+        deps: Deps,
+        table_id: u32,
+        game_state: GameState,
+        secret_key: u128,
+    ) -> StdResult<CommunityCardsResponse> {
+        let table = load_table(deps.storage, table_id)
+            .ok_or_else(|| StdError::generic_err(ContractError::TableNotFound { table_id }.to_string()))?;
+        let retrieved_at = table.community_cards.retrieved_at(&game_state).ok_or_else(|| {
+            StdError::generic_err(ContractError::CardsNotRetrieved { table_id, street: game_state.as_str().to_string() }.to_string())
+        })?;
+        ensure_not_consumed(deps, &table.community_cards, &game_state)?;
+        let (stored_key, cards) = street_secret_and_cards(&table.community_cards, &game_state)?;
+        if stored_key != secret_key {
+            return Err(secret_mismatch(&game_state));
         }
 
-        Ok(CommunityCards {
-            flop: Flop {
-                cards: collect_cards(deck, 3),
-                secret: secrets[0].0,
-                retrieved_at: None,
-            },
-            turn: Turn {
-                card: deck.cards.pop().unwrap().clone(),
-                secret: secrets[1].0,
-                retrieved_at: None,
-            },
-            river: River {
-                card: deck.cards.pop().unwrap().clone(),
-                secret: secrets[2].0,
-                retrieved_at: None,
-            },
+        Ok(CommunityCardsResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            retrieved_at: Some(retrieved_at),
+            game_state,
+            community_cards: cards,
         })
     }
 
-    fn collect_cards(deck: &mut Deck, count: usize) -> Vec<Card> {
-        (0..count).filter_map(|_| Some(deck.cards.pop().unwrap().clone())).collect()
+    // the cards of the street, or None when the secret doesn't match
+    fn street_cards(
+        deps: Deps,
+        community_cards: &CommunityCards,
+        game_state: &GameState,
+        secret_key: u128,
+    ) -> StdResult<Option<Vec<Card>>> {
+        ensure_not_consumed(deps, community_cards, game_state)?;
+
+        let (stored_key, cards) = street_secret_and_cards(community_cards, game_state)?;
+        Ok((stored_key == secret_key).then_some(cards))
     }
 
-    fn create_players(
-        players_info: Vec<StartGamePlayer>,
-        player_cards: Vec<(String, Vec<Card>)>,
-        secrets: &[(u64, Vec<u64>)],
-        env: &Env,
-        counter: &mut u128,
-    ) -> Result<Vec<Player>, ContractError> {
+    fn street_secret_and_cards(community_cards: &CommunityCards, game_state: &GameState) -> StdResult<(u128, Vec<Card>)> {
+        match game_state {
+            GameState::Flop => Ok((community_cards.flop.secret, community_cards.flop.cards.clone())),
+            GameState::Turn => community_cards
+                .turn
+                .as_ref()
+                .map(|turn| (turn.secret, vec![turn.card.clone()]))
+                .ok_or_else(|| StdError::generic_err("Phase not dealt")),
+            GameState::River => community_cards
+                .river
+                .as_ref()
+                .map(|river| (river.secret, vec![river.card.clone()]))
+                .ok_or_else(|| StdError::generic_err("Phase not dealt")),
+            _ => Err(StdError::generic_err("Invalid game state")),
+        }
+    }
 
-        players_info
-            .into_iter()
-            .zip(player_cards)
-            .enumerate()
-            .map(|(i, (info, (_, cards)))| {
-                Ok(Player {
-                    username: info.username,
-                    player_id: info.player_id,
-                    public_key: info.public_key,
-                    hand: cards,
-                    hand_secret: helpers::generate_random_number(env, counter)?,
-                    flop_secret_share: secrets[0].1[i],
-                    turn_secret_share: secrets[1].1[i],
-                    river_secret_share: secrets[2].1[i],
-                })
-            })
-            .collect()
+    fn secret_mismatch(game_state: &GameState) -> StdError {
+        StdError::generic_err(ContractError::SecretMismatch { street: game_state.as_str().to_string() }.to_string())
     }
 
-    fn create_start_game_response(
+    /*
+     * Every street whose secret is given, in a single query. A mismatched secret fails the whole query
+     * and names its street.
+     */
+    pub fn query_all_community_cards(
+        deps: Deps,
         table_id: u32,
-        hand_ref: u32,
-        players: &[Player],
-        previous_hand_log: Option<LastHandLogResponse>,
-    ) -> Result<Response, ContractError> {
-        let response = ResponsePayload::StartGame(StartGameResponse {
-            table_id,
-            hand_ref,
-            players: players.iter().map(|p| p.username.clone()).collect(),
-        });
-        let mut res = create_plaintext_response(RESPONSE_KEY.to_string(), response)?;
-
-        if let Some(previous_hand_log) = previous_hand_log {
-            res = res.add_attribute_plaintext("previous_hand_log", serialize_response(ResponsePayload::LastHand(previous_hand_log))?);
-        }
-        Ok(res)
-    }
+        flop_secret: Option<u128>,
+        turn_secret: Option<u128>,
+        river_secret: Option<u128>,
+    ) -> StdResult<AllCommunityCardsResponse> {
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-    fn create_previous_hand_log(deps: Deps, table_id: u32, showdown_player_ids: Vec<Uuid>) -> Result<Option<LastHandLogResponse>, ContractError> {
-        let table = load_table(deps.storage, table_id);
-        let previous_hand_log = if table.is_some() {
-            let table = table.unwrap();
-            
-            Some(LastHandLogResponse {
-                showdown_players: showdown_player_ids.iter().map(|player_id| {
-                    let player = table.players.iter().find(|player| &player.player_id == player_id).unwrap();
-                    ShowdownPlayer {
-                        username: player.username.clone(),
-                        hand: player.hand.iter().map(|card| card.to_string()).collect(),
-                    }
-                }).collect(),
-                community_cards: [table.community_cards.flop.cards.iter().map(|card| card.to_string()).collect(), vec![table.community_cards.turn.card.to_string()], vec![table.community_cards.river.card.to_string()]].concat(),
-                flop_retrieved_at: table.community_cards.flop.retrieved_at,
-                turn_retrieved_at: table.community_cards.turn.retrieved_at,
-                river_retrieved_at: table.community_cards.river.retrieved_at,
-                showdown_retrieved_at: table.showdown_retrieved_at,
-            })
-        } else {
-            None
+        let reveal = |game_state: GameState, secret: Option<u128>| {
+            secret
+                .map(|secret| {
+                    street_cards(deps, &table.community_cards, &game_state, secret)?
+                        .ok_or_else(|| secret_mismatch(&game_state))
+                })
+                .transpose()
         };
 
-        Ok(previous_hand_log)
-    }
-
-    fn create_plaintext_response(
-        key: String,
-        response: ResponsePayload
-    ) -> Result<Response, ContractError> {
-        Ok(Response::new().add_attribute_plaintext(key, serialize_response(response)?))
+        Ok(AllCommunityCardsResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            flop: reveal(GameState::Flop, flop_secret)?,
+            turn: reveal(GameState::Turn, turn_secret)?.and_then(|cards| cards.into_iter().next()),
+            river: reveal(GameState::River, river_secret)?.and_then(|cards| cards.into_iter().next()),
+        })
     }
 
-    fn serialize_response(response: ResponsePayload) -> Result<String, ContractError> {
-        match serde_json_wasm::to_string(&response) {
-            Ok(json) => Ok(json),
-            Err(e) => Err(ContractError::SerializationFailed {
-                error: e.to_string(),
-            }),
+    /*
+     * With strict_one_time_reveal, a phase secret can no longer reveal its cards once the owner revealed them,
+     * so the execute response stays the only disclosure of the phase.
+     */
+    fn ensure_not_consumed(deps: Deps, community_cards: &CommunityCards, game_state: &GameState) -> StdResult<()> {
+        if community_cards.is_consumed(game_state) && CONFIG_KEY.load(deps.storage)?.strict_one_time_reveal {
+            return Err(StdError::generic_err("Phase secret already consumed"));
         }
+        Ok(())
     }
 
-
-    pub fn handle_community_cards(
-        deps: DepsMut,
-        env: Env,
+    pub fn query_community_cards_from_shares(
+        deps: Deps,
         table_id: u32,
         game_state: GameState,
-    ) -> Result<Response, ContractError> {
-        let mut table = load_table_or_error(deps.storage, table_id)?;
-        
-        /*
-         * We check if the cards have already been retrieved, if so we return an error.
-         * This ensures that the logged time is the only time the cards were retrieved.
-         */
-        let cards = match game_state {
-            GameState::Flop => {
-                if table.community_cards.flop.retrieved_at.is_some() {
-                    return Err(ContractError::CardsAlreadyRetrieved {});
-                }
-                table.community_cards.flop.retrieved_at = Some(env.block.time);
-                Some(table.community_cards.flop.cards.clone())
+        shares: Vec<PlayerShare>,
+    ) -> StdResult<CommunityCardsResponse> {
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
+
+        let mut unique_shares: HashMap<(Uuid, GameState), u128> = HashMap::new();
+
+        for share in shares.into_iter().filter(|share| share.game_state == game_state) {
+            if !table.players.iter().any(|player| player.player_id == share.player_id) {
+                return Err(StdError::generic_err("Player not found"));
             }
-            GameState::Turn => {
-                if table.community_cards.turn.retrieved_at.is_some() {
-                    return Err(ContractError::CardsAlreadyRetrieved {});
-                }
-                table.community_cards.turn.retrieved_at = Some(env.block.time);
-                Some(vec![table.community_cards.turn.card.clone()])
+
+            let previous = unique_shares.insert((share.player_id, share.game_state), share.share);
+            if previous.is_some_and(|previous| previous != share.share) {
+                return Err(StdError::generic_err("Conflicting shares submitted for the same player"));
             }
-            GameState::River => {
-                if table.community_cards.river.retrieved_at.is_some() {
-                    return Err(ContractError::CardsAlreadyRetrieved {});
+        }
+
+        for player in table.players.iter().filter(|player| player.inactive) {
+            let stored_share = match game_state {
+                GameState::Flop => player.flop_secret_share,
+                GameState::Turn => player.turn_secret_share,
+                GameState::River => player.river_secret_share,
+                GameState::PreFlop | GameState::Showdown => continue,
+            };
+            unique_shares.entry((player.player_id, game_state.clone())).or_insert(stored_share);
+        }
+
+        let secret = match table.sharing_scheme {
+            SharingScheme::Additive => helpers::reconstruct_additive(&unique_shares.values().copied().collect::<Vec<_>>()),
+            SharingScheme::Threshold { threshold } => {
+                if unique_shares.len() < threshold as usize {
+                    return Err(StdError::generic_err("Not enough shares to reach the threshold"));
                 }
-                table.community_cards.river.retrieved_at = Some(env.block.time);
-                Some(vec![table.community_cards.river.card.clone()])
-            }
-            _ => {
-                return Err(ContractError::GameStateError {
-                    method: "distribute_community_cards".to_string(),
-                    table_id,
-                    game_state: Some(game_state),
-                })
+
+                let points: Vec<(u128, u128)> = table
+                    .players
+                    .iter()
+                    .filter_map(|player| {
+                        unique_shares
+                            .get(&(player.player_id, game_state.clone()))
+                            .map(|&share| (player.share_index as u128, share))
+                    })
+                    .collect();
+                helpers::shamir::reconstruct(&points)
             }
         };
 
-        
-        save_table(deps.storage, table_id, &table)?;
-
-        let response = ResponsePayload::CommunityCards(CommunityCardsResponse {
-            table_id,
-            hand_ref: table.hand_ref,
-            game_state,
-            community_cards: cards.unwrap(),
-        });
-
-        create_plaintext_response(RESPONSE_KEY.to_string(), response)
+        query_community_cards(deps, table_id, game_state, secret)
     }
 
-    pub fn handle_showdown(
-        deps: DepsMut,
-        env: Env,
+    pub fn query_partial_sum(
+        deps: Deps,
         table_id: u32,
         game_state: GameState,
-        showdown_player_ids: Vec<Uuid>,
-    ) -> Result<Response, ContractError> {
-        let mut table = load_table(deps.storage, table_id)
-            .ok_or_else(|| ContractError::TableNotFound { table_id })?;
+        player_ids: Vec<Uuid>,
+    ) -> StdResult<PartialSumResponse> {
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-        /*
-         * Ensures that the cards cannot be retrieved twice, 
-         * this ensures that the logged time is the only time the cards were retrieved.
-         */
-        if table.showdown_retrieved_at.is_some() {
-            return Err(ContractError::CardsAlreadyRetrieved {});
+        if table.showdown_retrieved_at.is_none() {
+            return Err(StdError::generic_err("Partial sums are only available after showdown"));
+        }
+
+        if table.sharing_scheme != SharingScheme::Additive {
+            return Err(StdError::generic_err("Partial sums are only available for additive sharing"));
+        }
+
+        if matches!(game_state, GameState::Turn | GameState::River) && !table.community_cards.is_dealt(&game_state) {
+            return Err(StdError::generic_err("Phase not dealt"));
         }
 
-        let mut player_hands: Vec<(Uuid, Vec<Card>)> = Vec::new();
+        let player_ids: HashSet<Uuid> = player_ids.into_iter().collect();
+        let mut partial_sum: u128 = 0;
 
-        for player_id in showdown_player_ids.iter() {
-            let players = table
+        for player_id in player_ids.iter() {
+            let player = table
                 .players
                 .iter()
-                .find(|player| &player.player_id == player_id);
+                .find(|player| &player.player_id == player_id)
+                .ok_or_else(|| StdError::generic_err("Player not found"))?;
 
-            if let Some(player) = players {
-                player_hands.push((player.player_id.clone(), player.hand.clone()));
-            } else {
-                return Err(ContractError::PlayerNotFound {
-                    table_id,
-                    player: player_id.to_string(),
-                });
-            }
+            let share = match game_state {
+                GameState::Flop => player.flop_secret_share,
+                GameState::Turn => player.turn_secret_share,
+                GameState::River => player.river_secret_share,
+                _ => return Err(StdError::generic_err("Invalid game state")),
+            };
+            partial_sum = partial_sum.wrapping_add(share);
         }
 
-        let response = ResponsePayload::Showdown(ShowdownResponse {
+        Ok(PartialSumResponse {
             table_id,
             hand_ref: table.hand_ref,
-            players_cards: player_hands,
-            community_cards: handle_all_in_showdown(&table.community_cards, game_state),
-        });
+            game_state,
+            partial_sum: partial_sum.to_string(),
+            complete: player_ids.len() == table.players.len(),
+        })
+    }
 
-        
-        table.showdown_retrieved_at = Some(env.block.time);
-        save_table(deps.storage, table_id, &table)?;
+    pub fn query_showdown(
+        deps: Deps,
+        table_id: u32,
+        flop_secret: Option<u128>,
+        turn_secret: Option<u128>,
+        river_secret: Option<u128>,
+        players_secrets: Vec<u128>,
+    ) -> StdResult<ShowdownResponse> {
+        // most likely a client bug, community only reveals stay allowed
+        if players_secrets.is_empty() && flop_secret.is_none() && turn_secret.is_none() && river_secret.is_none() {
+            return Err(StdError::generic_err(ContractError::NoSecretsProvided {}.to_string()));
+        }
 
-        create_plaintext_response(RESPONSE_KEY.to_string(), response)
-    }
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-    fn handle_all_in_showdown(
-        community_cards: &CommunityCards,
-        game_state: GameState,
-    ) -> Option<Vec<Card>> {
-        match game_state {
-            GameState::PreFlop => {
-                let mut cards = community_cards.flop.cards.clone();
-                cards.push(community_cards.turn.card.clone());
-                cards.push(community_cards.river.card.clone());
-                Some(cards)
+        let mut community_cards = Vec::new();
+
+        if let Some(secret) = flop_secret {
+            if table.community_cards.flop.secret != secret {
+                return Err(secret_mismatch(&GameState::Flop));
             }
-            GameState::Flop => Some(vec![
-                community_cards.turn.card.clone(),
-                community_cards.river.card.clone(),
-            ]),
-            GameState::Turn => Some(vec![community_cards.river.card.clone()]),
-            _ => return None,
+            ensure_not_consumed(deps, &table.community_cards, &GameState::Flop)?;
+            community_cards.extend(table.community_cards.flop.cards.clone());
         }
-    }
-}
 
+        if let Some(secret) = turn_secret {
+            match &table.community_cards.turn {
+                Some(turn) if turn.secret == secret => {
+                    ensure_not_consumed(deps, &table.community_cards, &GameState::Turn)?;
+                    community_cards.push(turn.card.clone())
+                }
+                _ => return Err(secret_mismatch(&GameState::Turn)),
+            }
+        }
 
-#[entry_point]
-pub fn instantiate(deps: DepsMut, env: Env, info: MessageInfo, _msg: InstantiateMsg,) -> Result<Response, StdError> {
-    let config = Config {
-        owner: info.sender,
-        contract_address: env.contract.address.clone(),
-    };
+        if let Some(secret) = river_secret {
+            match &table.community_cards.river {
+                Some(river) if river.secret == secret => {
+                    ensure_not_consumed(deps, &table.community_cards, &GameState::River)?;
+                    community_cards.push(river.card.clone())
+                }
+                _ => return Err(secret_mismatch(&GameState::River)),
+            }
+        }
 
-    let counter = init_counter(&env)?;
+        let players_cards = players_secrets
+            .iter()
+            .map(|secret| {
+                table
+                    .players
+                    .iter()
+                    .find(|player| &player.hand_secret == secret)
+                    .map(|player| (player.player_id, player.hand.clone()))
+                    .ok_or_else(|| StdError::generic_err("Player not found"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
 
-    CONFIG_KEY.save(deps.storage, &config)?;
-    COUNTER_KEY.save(deps.storage, &counter)?;
+        let player_ids: Vec<Uuid> = players_cards.iter().map(|(player_id, _)| *player_id).collect();
+        // the street before the first one unlocked by a secret
+        let from_state = match (flop_secret, turn_secret, river_secret) {
+            (Some(_), _, _) => GameState::PreFlop,
+            (None, Some(_), _) => GameState::Flop,
+            (None, None, Some(_)) => GameState::Turn,
+            (None, None, None) => GameState::River,
+        };
 
-    Ok(Response::default())
-}
+        Ok(ShowdownResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            players_cards,
+            board_len: community_cards.len(),
+            community_cards: Some(community_cards),
+            burned: None,
+            undealt: None,
+            reveal_order: table.reveal_order(&player_ids),
+            from_state,
+        })
+    }
 
-fn init_counter(env: &Env) -> StdResult<u128> {
-    let seed = env
-        .block
-        .random
-        .as_ref()
-        .ok_or(StdError::generic_err("No random seed available"))?;
-    let seed_bytes: [u8; RANDOM_SEED_SIZE] = seed[..RANDOM_SEED_SIZE]
-        .try_into()
-        .map_err(|_| StdError::generic_err("Failed to convert seed to array"))?;
-    Ok(u128::from_le_bytes(seed_bytes))
-}
+    pub fn query_player_hand(deps: Deps, table_id: u32, hand_secret: u128) -> StdResult<PlayerHandResponse> {
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
 
-#[entry_point]
-pub fn execute(
-    deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
-    msg: ExecuteMsg,
-) -> Result<Response, ContractError> {
-    let config = CONFIG_KEY.load(deps.storage)?;
-    if info.sender != config.owner {
-        return Err(ContractError::Unauthorized {});
-    }
+        // same matching as query_showdown, the secret itself is never echoed back
+        let player = table
+            .players
+            .iter()
+            .find(|player| player.hand_secret == hand_secret)
+            .ok_or_else(|| StdError::generic_err(ContractError::UnknownHandSecret { table_id }.to_string()))?;
 
-    match msg {
-        ExecuteMsg::StartGame {
+        Ok(PlayerHandResponse {
             table_id,
-            hand_ref,
-            players,
-            prev_hand_showdown_players,
-        } => execute_handlers::handle_start_game(
-            deps,
-            env,
-            table_id,
-            hand_ref,
-            players,
-            prev_hand_showdown_players,
-        ),
-        ExecuteMsg::CommunityCards {
-            table_id,
-            game_state,
-        } => execute_handlers::handle_community_cards(deps, env, table_id, game_state),
-        ExecuteMsg::Showdown {
-            table_id,
-            game_state,
-            showdown_player_ids,
-        } => execute_handlers::handle_showdown(deps, env, table_id, game_state, showdown_player_ids),
+            hand_ref: table.hand_ref,
+            player_id: player.player_id,
+            hand: player.hand.clone(),
+        })
     }
-}
 
-#[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::WithPermit { permit, query } => {
-            query_handlers::handle_permit_query(deps, permit, query)
+    /*
+     * Only after the Showdown execute, so the logged retrieval stays the first time the hands could be seen.
+     */
+    pub fn query_showdown_by_ids(
+        deps: Deps,
+        table_id: u32,
+        player_ids: Vec<Uuid>,
+        viewer: String,
+    ) -> StdResult<ShowdownResponse> {
+        if !CONFIG_KEY.load(deps.storage)?.is_dealer(&Addr::unchecked(viewer)) {
+            return Err(StdError::generic_err(ContractError::Unauthorized {}.to_string()));
         }
-        QueryMsg::CommunityCards {
-            table_id,
-            game_state,
-            secret_key,
-        } => to_binary(&query_handlers::query_community_cards(
-            deps, table_id, game_state, secret_key,
-        )?),
-        QueryMsg::Showdown {
+
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
+
+        if table.showdown_retrieved_at.is_none() {
+            return Err(StdError::generic_err("Showdown not retrieved yet"));
+        }
+
+        let players_cards = state_utils::showdown_players(&table, table_id, &player_ids)
+            .map_err(|err| StdError::generic_err(err.to_string()))?
+            .into_iter()
+            .map(|player| (player.player_id, player.hand.clone()))
+            .collect();
+
+        let community_cards = table.all_dealt_cards().board();
+
+        Ok(ShowdownResponse {
             table_id,
-            flop_secret,
+            hand_ref: table.hand_ref,
+            players_cards,
+            board_len: community_cards.len(),
+            community_cards: Some(community_cards),
+            burned: None,
+            undealt: None,
+            reveal_order: table.reveal_order(&player_ids),
+            from_state: GameState::PreFlop,
+        })
+    }
+
+    pub fn query_winners(
+        deps: Deps,
+        table_id: u32,
+        flop_secret: Option<u128>,
+        turn_secret: Option<u128>,
+        river_secret: Option<u128>,
+        players_secrets: Vec<u128>,
+    ) -> StdResult<WinnersResponse> {
+        let showdown = query_showdown(deps, table_id, flop_secret, turn_secret, river_secret, players_secrets)?;
+        let community_cards = showdown.community_cards.unwrap_or_default();
+        let deck_variant = query_deck_variant(deps, table_id)?.deck_variant;
+        let rankings = eval::rank_hands(&showdown.players_cards, &community_cards, &deck_variant);
+        let tie_broken_by = match rankings.as_slice() {
+            [winners, runners_up, ..] => eval::tie_broken_by(&winners[0].1, &runners_up[0].1),
+            _ => None,
+        };
+
+        Ok(WinnersResponse {
+            table_id,
+            hand_ref: showdown.hand_ref,
+            rankings,
+            tie_broken_by,
+            community_cards,
+        })
+    }
+
+    pub fn query_showdown_with_winners(
+        deps: Deps,
+        table_id: u32,
+        flop_secret: Option<u128>,
+        turn_secret: Option<u128>,
+        river_secret: Option<u128>,
+        players_secrets: Vec<u128>,
+    ) -> StdResult<ShowdownWithWinnersResponse> {
+        let showdown = query_showdown(deps, table_id, flop_secret, turn_secret, river_secret, players_secrets)?;
+        let community_cards = showdown.community_cards.clone().unwrap_or_default();
+        let deck_variant = query_deck_variant(deps, table_id)?.deck_variant;
+        let winners = eval::rank_hands(&showdown.players_cards, &community_cards, &deck_variant)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+
+        Ok(ShowdownWithWinnersResponse { showdown, winners })
+    }
+
+    pub fn query_hand_chain(deps: Deps, table_id: u32) -> StdResult<HandChainResponse> {
+        let links = hand_chain(table_id)
+            .iter(deps.storage)?
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let current_hand_hash = load_table(deps.storage, table_id)
+            .map(|table| table.hand_hash().map(|hash| Binary::from(hash.to_vec())))
+            .transpose()?;
+
+        Ok(HandChainResponse { table_id, links, current_hand_hash })
+    }
+
+    pub fn query_hand_history(
+        deps: Deps,
+        table_id: u32,
+        limit: Option<u32>,
+        start_after: Option<u32>,
+    ) -> StdResult<HandHistoryResponse> {
+        let history = hand_history(table_id);
+        let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT).min(MAX_HISTORY_LIMIT) as usize;
+
+        // pages by history index, the cursor is the index of the last hand of the previous page
+        let len = history.get_len(deps.storage)?;
+        let start = start_after.map_or(0, |index| index.saturating_add(1));
+        if start > len {
+            return Err(StdError::generic_err("Hand not found in history"));
+        }
+
+        let hands = history
+            .iter(deps.storage)?
+            .skip(start as usize)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+        let end = start + hands.len() as u32;
+        let next_start_after = (end > 0 && end < len).then(|| end - 1);
+
+        Ok(HandHistoryResponse { table_id, hands, next_start_after })
+    }
+
+    pub fn query_tables(deps: Deps, start_after: Option<u32>, limit: Option<u32>) -> StdResult<TablesResponse> {
+        let table_ids = TABLE_IDS.may_load(deps.storage)?.unwrap_or_default();
+        let limit = limit.unwrap_or(DEFAULT_TABLES_LIMIT).min(MAX_TABLES_LIMIT) as usize;
+        let start = start_after.map_or(0, |start_after| table_ids.partition_point(|&table_id| table_id <= start_after));
+
+        let tables = table_ids[start..]
+            .iter()
+            .take(limit)
+            .filter_map(|&table_id| {
+                load_table(deps.storage, table_id).map(|table| TableSummary {
+                    table_id,
+                    hand_ref: table.hand_ref,
+                    player_count: table.players.len() as u32,
+                })
+            })
+            .collect();
+
+        Ok(TablesResponse { tables })
+    }
+
+    pub fn query_next_table_id(deps: Deps) -> StdResult<NextTableIdResponse> {
+        Ok(NextTableIdResponse { table_id: next_table_id(deps.storage)? })
+    }
+
+    pub fn query_game_result(deps: Deps, table_id: u32, hand_ref: u32) -> StdResult<GameResult> {
+        load_game_result(deps.storage, table_id, hand_ref).ok_or(StdError::generic_err("No game result found"))
+    }
+
+    pub fn query_verify_shuffle(deps: Deps, table_id: u32) -> StdResult<VerifyShuffleResponse> {
+        let table =
+            load_table(deps.storage, table_id).ok_or(StdError::generic_err("No table found"))?;
+
+        if table.showdown_retrieved_at.is_none() {
+            return Err(StdError::generic_err("Shuffle seed is only available after showdown"));
+        }
+
+        let mut deck = table.deck_variant.deck();
+        helpers::shuffle_deck(&mut deck, table.shuffle_seed, table.shuffle_max_retries);
+
+        Ok(VerifyShuffleResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            seed: table.shuffle_seed.to_string(),
+            max_retries: table.shuffle_max_retries,
+            deck: Binary::from(deck.to_bytes()),
+            counter_start: table.counter_start.to_string(),
+            counter_end: table.counter_end.to_string(),
+        })
+    }
+
+    pub fn query_deck_commitment(deps: Deps, table_id: u32) -> StdResult<DeckCommitmentResponse> {
+        let table = load_table(deps.storage, table_id)
+            .ok_or_else(|| StdError::generic_err(ContractError::TableNotFound { table_id }.to_string()))?;
+
+        Ok(DeckCommitmentResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            commitment: Binary::from(table.deck_commitment.to_vec()),
+        })
+    }
+
+    pub fn query_showdown_status(deps: Deps, table_id: u32) -> StdResult<ShowdownStatusResponse> {
+        let table = load_table(deps.storage, table_id)
+            .ok_or_else(|| StdError::generic_err(ContractError::TableNotFound { table_id }.to_string()))?;
+        let community_cards = &table.community_cards;
+
+        Ok(ShowdownStatusResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            showdown_retrieved_at: table.showdown_retrieved_at,
+            flop_retrieved_at: community_cards.retrieved_at(&GameState::Flop),
+            turn_retrieved_at: community_cards.retrieved_at(&GameState::Turn),
+            river_retrieved_at: community_cards.retrieved_at(&GameState::River),
+        })
+    }
+
+    pub fn query_deck_variant(deps: Deps, table_id: u32) -> StdResult<DeckVariantResponse> {
+        let table = load_table(deps.storage, table_id)
+            .ok_or_else(|| StdError::generic_err(ContractError::TableNotFound { table_id }.to_string()))?;
+
+        Ok(DeckVariantResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            cards: table.deck_variant.deck().cards,
+            deck_variant: table.deck_variant,
+        })
+    }
+
+    pub fn query_table_state(deps: Deps, table_id: u32) -> StdResult<TableStateResponse> {
+        let table = load_table(deps.storage, table_id)
+            .ok_or_else(|| StdError::generic_err(ContractError::TableNotFound { table_id }.to_string()))?;
+
+        Ok(TableStateResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            players: table
+                .players
+                .into_iter()
+                .map(|player| TableStatePlayer {
+                    username: player.username,
+                    player_id: player.player_id,
+                })
+                .collect(),
+            current_phase: table.current_phase,
+            button_seat: table.button_seat,
+            deck_variant: table.deck_variant,
+            flop_retrieved_at: table.community_cards.flop.retrieved_at,
+            turn_retrieved_at: table.community_cards.turn.and_then(|turn| turn.retrieved_at),
+            river_retrieved_at: table.community_cards.river.and_then(|river| river.retrieved_at),
+            showdown_retrieved_at: table.showdown_retrieved_at,
+            started_at: table.started_at,
+        })
+    }
+
+    pub fn query_metrics(deps: Deps) -> StdResult<ExecuteMetrics> {
+        Ok(METRICS_KEY.may_load(deps.storage)?.unwrap_or_default())
+    }
+
+    pub fn query_version(deps: Deps) -> StdResult<VersionResponse> {
+        Ok(VersionResponse {
+            contract: CONTRACT_NAME.to_string(),
+            version: CONTRACT_VERSION_KEY.may_load(deps.storage)?,
+        })
+    }
+
+    pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+        let config = CONFIG_KEY.load(deps.storage)?;
+        let instantiation = INSTANTIATION_KEY.may_load(deps.storage)?;
+        let limits = load_player_limits(deps.storage)?;
+
+        Ok(ConfigResponse {
+            owner: config.owner,
+            contract_address: config.contract_address,
+            instantiated_at: instantiation.as_ref().map(|instantiation| instantiation.time),
+            instantiated_height: instantiation.map(|instantiation| instantiation.height),
+            strict_one_time_reveal: config.strict_one_time_reveal,
+            pending_owner: config.pending_owner,
+            dealers: config.dealers,
+            paused: config.paused,
+            min_players: limits.min_players,
+            max_players: limits.max_players,
+        })
+    }
+}
+
+
+mod execute_handlers {
+    use super::{helpers::Randomness, state_utils::{increment_metrics, load_table_or_error, showdown_players}, *};
+
+    pub fn handle_start_game(mut deps: DepsMut, env: Env, game: StartGameArgs) -> Result<Response, ContractError> {
+        ensure_not_paused(deps.as_ref())?;
+        let mut rng = helpers::BlockRandomness::new(&env, COUNTER_KEY.load(deps.storage)?);
+        let compress_hand_log = game.compress_hand_log;
+        let (start_game, previous_hand_log) = start_hand(deps.branch(), &mut rng, env.block.time, game, None)?;
+        COUNTER_KEY.save(deps.storage, &rng.counter)?;
+
+        create_start_game_response(start_game, previous_hand_log, compress_hand_log)
+    }
+
+    // the counter is loaded and saved once for the whole batch rather than once per game
+    pub fn handle_bulk_start_game(
+        mut deps: DepsMut,
+        env: Env,
+        games: Vec<StartGameArgs>,
+    ) -> Result<Response, ContractError> {
+        if games.is_empty() || games.len() > MAX_BULK_START_GAMES {
+            return Err(ContractError::InvalidBatchSize { size: games.len(), max: MAX_BULK_START_GAMES });
+        }
+        ensure_not_paused(deps.as_ref())?;
+
+        let mut rng = helpers::BlockRandomness::new(&env, COUNTER_KEY.load(deps.storage)?);
+        let mut responses = Vec::with_capacity(games.len());
+        let mut previous_hand_logs = Vec::new();
+        for game in games {
+            let compress_hand_log = game.compress_hand_log;
+            let (start_game, previous_hand_log) = start_hand(deps.branch(), &mut rng, env.block.time, game, None)?;
+            if let Some(previous_hand_log) = previous_hand_log {
+                previous_hand_logs.push((
+                    format!("previous_hand_log_{}", start_game.table_id),
+                    serialize_hand_log(previous_hand_log, compress_hand_log)?,
+                ));
+            }
+            responses.push(start_game);
+        }
+        COUNTER_KEY.save(deps.storage, &rng.counter)?;
+
+        let response = ResponsePayload::BulkStartGame(BulkStartGameResponse { games: responses });
+        let mut res = create_plaintext_response(RESPONSE_KEY.to_string(), response)?;
+        for (key, previous_hand_log) in previous_hand_logs {
+            res = res.add_attribute_plaintext(key, previous_hand_log);
+        }
+        Ok(res)
+    }
+
+    pub fn handle_rebuild_table(
+        mut deps: DepsMut,
+        env: Env,
+        table_id: u32,
+        players: Vec<StartGamePlayer>,
+        hand_ref: u32,
+    ) -> Result<Response, ContractError> {
+        let table = load_table_or_error(deps.storage, table_id)?;
+        let mut hand_log = create_hand_log(&table, table_id, &[])?;
+        hand_log.corrupted = true;
+        hand_history(table_id).push(deps.storage, &hand_log)?;
+        // the broken hand is neither finalized nor carried over by the new one
+        delete_table(deps.storage, table_id)?;
+
+        let game = StartGameArgs {
+            table_id,
+            hand_ref,
+            players,
+            prev_hand_showdown_players: vec![],
+            compress_hand_log: false,
+            shuffle_max_retries: None,
+            table_intent: TableIntent::Any,
+            phases: None,
+            sharing_scheme: SharingScheme::default(),
+            button_seat: None,
+            deck_variant: DeckVariant::default(),
+            max_hands: table.max_hands,
+            stakes: None,
+        };
+        // the new hand takes the place of the broken one in the table's hand count
+        let hands_dealt_before = table.hands_dealt.saturating_sub(1);
+        let mut rng = helpers::BlockRandomness::new(&env, COUNTER_KEY.load(deps.storage)?);
+        let (start_game, _) = start_hand(deps.branch(), &mut rng, env.block.time, game, Some(hands_dealt_before))?;
+        COUNTER_KEY.save(deps.storage, &rng.counter)?;
+
+        Ok(create_start_game_response(start_game, Some(hand_log), false)?.add_attribute_plaintext("action", "rebuild_table"))
+    }
+
+    fn ensure_not_paused(deps: Deps) -> Result<(), ContractError> {
+        if CONFIG_KEY.load(deps.storage)?.paused {
+            return Err(ContractError::ContractPaused {});
+        }
+        Ok(())
+    }
+
+    // deals a hand from the caller's randomness, which is left for the caller to save
+    fn start_hand(
+        deps: DepsMut,
+        rng: &mut helpers::BlockRandomness,
+        started_at: Timestamp,
+        game: StartGameArgs,
+        hands_dealt_before: Option<u32>, // set by a rebuild, whose deleted table can't tell the count anymore
+    ) -> Result<(StartGameResponse, Option<LastHandLogResponse>), ContractError> {
+        let StartGameArgs {
+            table_id,
+            hand_ref,
+            players: players_info,
+            prev_hand_showdown_players,
+            compress_hand_log: _,
+            shuffle_max_retries,
+            table_intent,
+            phases,
+            sharing_scheme,
+            button_seat,
+            deck_variant,
+            max_hands,
+            stakes,
+        } = game;
+
+        validate_players(&players_info, &load_player_limits(deps.storage)?)?;
+        let phases = validate_phases(phases, players_info.len())?;
+        validate_deck_size(&deck_variant, players_info.len(), phases)?;
+        let shuffle_max_retries = shuffle_max_retries.unwrap_or(SHUFFLE_MAX_RETRIES);
+        if shuffle_max_retries > MAX_SHUFFLE_RETRIES {
+            return Err(ContractError::InvalidShuffleRetries { max_retries: shuffle_max_retries, max: MAX_SHUFFLE_RETRIES });
+        }
+        validate_sharing_scheme(&sharing_scheme, players_info.len())?;
+        if let Some(stakes) = &stakes {
+            validate_stakes(stakes)?;
+        }
+        validate_table_intent(deps.as_ref(), table_id, table_intent)?;
+        let previous_table = load_table(deps.storage, table_id);
+        if previous_table.as_ref().is_some_and(|table| table.showdown_retrieved_at.is_none()) {
+            return Err(ContractError::HandInProgress { table_id });
+        }
+        // refused before dealing, the hand could never be finalized
+        if previous_table.as_ref().is_some_and(|table| table.hand_ref == hand_ref)
+            || load_game_result(deps.storage, table_id, hand_ref).is_some()
+        {
+            return Err(ContractError::HandRefAlreadyUsed { table_id, hand_ref });
+        }
+        let button_seat = resolve_button_seat(button_seat, previous_table.as_ref(), players_info.len())?;
+        let max_hands = max_hands.or(previous_table.as_ref().and_then(|table| table.max_hands));
+        let hands_dealt = hands_dealt_before
+            .or(previous_table.as_ref().map(|table| table.hands_dealt))
+            .unwrap_or(0)
+            .saturating_add(1);
+        if let Some(max_hands) = max_hands.filter(|&max_hands| hands_dealt > max_hands) {
+            return Err(ContractError::TableHandLimitReached { table_id, max_hands });
+        }
+        let previous_hand_log = previous_table
+            .map(|table| finalize_hand(deps.storage, table_id, &table, &prev_hand_showdown_players))
+            .transpose()?;
+        let prev_hand_hash = last_hand_hash(deps.storage, table_id)?;
+        let counter_start = rng.counter;
+        let (mut deck, shuffle_seed, shuffle_fallback_used) =
+            initialize_deck(rng, shuffle_max_retries, &deck_variant)?;
+        let deck_commitment: [u8; 32] = Sha256::digest(deck.to_bytes()).into();
+        let player_cards = distribute_player_cards(&mut deck, &players_info)?;
+        validate_distinct_hole_cards(&player_cards)?;
+        let mut phase_secrets = helpers::generate_distinct_secrets(
+            phases + players_info.len(),
+            // threshold secrets are field elements, the same reduction is applied to the hand secrets for simplicity
+            || match sharing_scheme {
+                SharingScheme::Additive => rng.next(),
+                SharingScheme::Threshold { .. } => rng.next().map(|n| n % helpers::shamir::PRIME),
+            },
+        )?;
+        let hand_secrets = phase_secrets.split_off(phases);
+        let mut secrets = Vec::with_capacity(phases);
+        let community_cards =
+            generate_community_cards(
+                rng,
+                &phase_secrets,
+                &mut secrets,
+                &mut deck,
+                players_info.len(),
+                &sharing_scheme,
+            )?;
+        let players = create_players(
+            players_info,
+            player_cards,
+            &secrets,
+            &hand_secrets,
+        )?;
+
+        let table = PokerTable {
+            hand_ref,
+            players,
+            community_cards,
+            showdown_retrieved_at: None,
+            current_phase: GameState::PreFlop,
+            shuffle_fallback_used,
+            prev_hand_hash,
+            shuffle_seed,
+            shuffle_max_retries,
+            sharing_scheme,
+            button_seat,
+            deck_variant,
+            undealt: deck.cards,
+            counter_start,
+            counter_end: rng.counter,
+            max_hands,
+            hands_dealt,
+            stakes,
+            deck_commitment,
+            started_at,
+        };
+
+        save_table(deps.storage, table_id, &table)?;
+        index_table_id(deps.storage, table_id)?;
+        reserve_table_id(deps.storage, table_id)?;
+        append_hand_link(deps.storage, table_id, &table)?;
+        increment_metrics(deps.storage, |metrics| metrics.start_games += 1)?;
+
+        let start_game = StartGameResponse {
+            table_id,
+            hand_ref,
+            players: table.players.iter().map(|p| p.username.clone()).collect(),
+            button_seat,
+            cards_dealt: table.players.iter().map(|player| player.hand.len()).sum(),
+            deck_remaining: table.undealt.len(),
+        };
+        Ok((start_game, previous_hand_log))
+    }
+
+    fn validate_stakes(stakes: &Stakes) -> Result<(), ContractError> {
+        let invalid = |reason: &str| Err(ContractError::InvalidStakes { reason: reason.to_string() });
+
+        if stakes.small_blind.is_zero() || stakes.big_blind.is_zero() || stakes.ante.is_some_and(|ante| ante.is_zero()) {
+            return invalid("blinds and ante must be nonzero");
+        }
+        if stakes.big_blind < stakes.small_blind {
+            return invalid("big blind must be at least the small blind");
+        }
+        if stakes.currency.is_empty() {
+            return invalid("currency must not be empty");
+        }
+        Ok(())
+    }
+
+    fn validate_table_intent(deps: Deps, table_id: u32, table_intent: TableIntent) -> Result<(), ContractError> {
+        let table_exists = load_table(deps.storage, table_id).is_some();
+
+        match table_intent {
+            TableIntent::New if table_exists => Err(ContractError::TableAlreadyExists { table_id }),
+            // a new table never reuses the id of an earlier hand, even once its table was deleted
+            TableIntent::New if table_id_used(deps.storage, table_id)? => {
+                Err(ContractError::TableIdReserved { table_id })
+            }
+            TableIntent::Continue if !table_exists => Err(ContractError::TableNotFound { table_id }),
+            _ => Ok(()),
+        }
+    }
+
+    fn validate_players(players_info: &[StartGamePlayer], limits: &PlayerLimits) -> Result<(), ContractError> {
+        if !(limits.min_players..=limits.max_players).contains(&players_info.len()) {
+            return Err(ContractError::InvalidPlayerCount {
+                count: players_info.len(),
+            });
+        }
+
+        let unique_keys: HashSet<_> = players_info.iter().map(|p| &p.public_key).collect();
+
+        if unique_keys.len() != players_info.len() {
+            return Err(ContractError::DuplicatePublicKeys {});
+        }
+
+        // showdown and private data lookups are keyed by player_id
+        let unique_ids: HashSet<_> = players_info.iter().map(|p| p.player_id).collect();
+
+        if unique_ids.len() != players_info.len() {
+            return Err(ContractError::DuplicatePlayerIds {});
+        }
+
+        Ok(())
+    }
+
+    /*
+     * Only the flop, turn and river exist on the board, a variant can stop dealing after any of them.
+     */
+    fn validate_phases(phases: Option<u8>, player_count: usize) -> Result<usize, ContractError> {
+        let Some(phases) = phases else {
+            return Ok(COMMUNITY_CARD_PHASES);
+        };
+
+        if !(1..=COMMUNITY_CARD_PHASES).contains(&(phases as usize)) {
+            return Err(ContractError::InvalidPhaseCount { phases, player_count });
+        }
+
+        Ok(phases as usize)
+    }
+
+    // checked before any card is drawn, so a deal never runs out of cards midway
+    pub fn validate_deck_size(deck_variant: &DeckVariant, player_count: usize, phases: usize) -> Result<(), ContractError> {
+        let required = required_cards(player_count, phases);
+        if required > deck_variant.size() {
+            return Err(ContractError::InsufficientDeck { required, available: deck_variant.size() });
+        }
+        Ok(())
+    }
+
+    /*
+     * Without an explicit seat, the button moves to the next seat of the previous hand on the table
+     * and starts on the first seat of a new table.
+     */
+    fn resolve_button_seat(
+        button_seat: Option<u32>,
+        previous_table: Option<&PokerTable>,
+        player_count: usize,
+    ) -> Result<u32, ContractError> {
+        match button_seat {
+            Some(seat) if seat as usize >= player_count => Err(ContractError::InvalidButtonSeat { seat, player_count }),
+            Some(seat) => Ok(seat),
+            None => Ok(previous_table.map_or(0, |table| (table.button_seat + 1) % player_count as u32)),
+        }
+    }
+
+    fn validate_sharing_scheme(sharing_scheme: &SharingScheme, player_count: usize) -> Result<(), ContractError> {
+        if let SharingScheme::Threshold { threshold } = *sharing_scheme {
+            // a threshold of 1 would hand every player the secrets
+            if !(2..=player_count).contains(&(threshold as usize)) {
+                return Err(ContractError::InvalidThreshold { threshold, player_count });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn initialize_deck(
+        rng: &mut dyn Randomness,
+        max_retries: u64,
+        deck_variant: &DeckVariant,
+    ) -> Result<(Deck, u64, bool), ContractError> {
+        let mut deck = deck_variant.deck();
+        // the shuffle only needs a 64 bit seed, the upper half of the draw is dropped
+        let seed = rng.next()? as u64;
+        let fallback_used = helpers::shuffle_deck(&mut deck, seed, max_retries);
+        deck.verify_integrity(deck_variant)?;
+        Ok((deck, seed, fallback_used))
+    }
+
+    pub fn distribute_player_cards(
+        deck: &mut Deck,
+        players: &[StartGamePlayer],
+    ) -> Result<Vec<(String, Vec<Card>)>, ContractError> {
+        players
+            .iter()
+            .map(|player| Ok((player.public_key.clone(), vec![deck.deal()?, deck.deal()?])))
+            .collect()
+    }
+
+    /*
+     * Dealing from a single deck can't produce the same card twice, this guards against any future dealing bug.
+     */
+    pub fn validate_distinct_hole_cards(player_cards: &[(String, Vec<Card>)]) -> Result<(), ContractError> {
+        let mut dealt = HashSet::new();
+
+        for (player, cards) in player_cards {
+            for card in cards {
+                if !dealt.insert(card) {
+                    return Err(ContractError::DuplicateHoleCard {
+                        player: player.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn generate_community_cards(
+        rng: &mut dyn Randomness,
+        phase_secrets: &[u128],
+        secrets: &mut Vec<(u128, Vec<(u32, u128)>)>,
+        deck: &mut Deck,
+        player_count: usize,
+        sharing_scheme: &SharingScheme,
+    ) -> Result<CommunityCards, ContractError> {
+
+        for &secret in phase_secrets {
+            let shares = match *sharing_scheme {
+                SharingScheme::Additive => helpers::additive_secret_sharing(rng, player_count, secret)?,
+                SharingScheme::Threshold { threshold } => {
+                    helpers::shamir::split(rng, secret, threshold as usize, player_count)?
+                }
+            };
+            // each share is kept with its share_index, the x-coordinate it was evaluated at for the threshold scheme
+            secrets.push((secret, (1..).zip(shares).collect()));
+        }
+
+        /*
+         * As in a live game, one card is burned before dealing each street.
+         * The burned cards are only disclosed once the hand is over, in the previous hand log.
+         */
+        let mut burned = Vec::with_capacity(secrets.len());
+
+        burned.push(deck.deal()?);
+        let flop = Flop {
+            cards: collect_cards(deck, 3)?,
+            secret: secrets[0].0,
+            retrieved_at: None,
+            consumed: false,
+        };
+
+        let turn = match secrets.get(1) {
+            Some(&(secret, _)) => {
+                burned.push(deck.deal()?);
+                Some(Turn {
+                    card: deck.deal()?,
+                    secret,
+                    retrieved_at: None,
+                    consumed: false,
+                })
+            }
+            None => None,
+        };
+
+        let river = match secrets.get(2) {
+            Some(&(secret, _)) => {
+                burned.push(deck.deal()?);
+                Some(River {
+                    card: deck.deal()?,
+                    secret,
+                    retrieved_at: None,
+                    consumed: false,
+                })
+            }
+            None => None,
+        };
+
+        Ok(CommunityCards {
+            flop,
+            turn,
+            river,
+            burned,
+        })
+    }
+
+    fn collect_cards(deck: &mut Deck, count: usize) -> Result<Vec<Card>, ContractError> {
+        (0..count).map(|_| deck.deal()).collect()
+    }
+
+    fn create_players(
+        players_info: Vec<StartGamePlayer>,
+        player_cards: Vec<(String, Vec<Card>)>,
+        secrets: &[(u128, Vec<(u32, u128)>)],
+        hand_secrets: &[u128],
+    ) -> Result<Vec<Player>, ContractError> {
+
+        players_info
+            .into_iter()
+            .zip(player_cards)
+            .enumerate()
+            .map(|(i, (info, (_, cards)))| {
+                // the player holds the share evaluated at share_index, looked up by its x-coordinate
+                let share_index = i as u32 + 1;
+                let share = |shares: &Vec<(u32, u128)>| {
+                    shares
+                        .iter()
+                        .find(|(x, _)| *x == share_index)
+                        .map(|&(_, share)| share)
+                        .ok_or(ContractError::MissingShare { share_index })
+                };
+                Ok(Player {
+                    username: info.username,
+                    player_id: info.player_id,
+                    public_key: info.public_key,
+                    hand: cards,
+                    hand_secret: hand_secrets[i],
+                    flop_secret_share: share(&secrets[0].1)?,
+                    // phases that aren't dealt have no secret to share
+                    turn_secret_share: secrets.get(1).map_or(Ok(0), |(_, shares)| share(shares))?,
+                    river_secret_share: secrets.get(2).map_or(Ok(0), |(_, shares)| share(shares))?,
+                    share_index,
+                    inactive: false,
+                    folded: false,
+                })
+            })
+            .collect()
+    }
+
+    fn create_start_game_response(
+        start_game: StartGameResponse,
+        previous_hand_log: Option<LastHandLogResponse>,
+        compress_hand_log: bool,
+    ) -> Result<Response, ContractError> {
+        let response = ResponsePayload::StartGame(start_game);
+        let mut res = create_plaintext_response(RESPONSE_KEY.to_string(), response)?;
+
+        if let Some(previous_hand_log) = previous_hand_log {
+            res = res.add_attribute_plaintext("previous_hand_log", serialize_hand_log(previous_hand_log, compress_hand_log)?);
+        }
+        Ok(res)
+    }
+
+    fn serialize_hand_log(hand_log: LastHandLogResponse, compress: bool) -> Result<String, ContractError> {
+        let mut payload = ResponsePayload::LastHand(hand_log);
+        if compress {
+            payload = ResponsePayload::Compressed(CompressedResponse::encode(&payload)?);
+        }
+        serialize_response(payload)
+    }
+
+    /*
+     * A hand is finalized when the next StartGame overwrites it or when it is ended with EndHand:
+     * its log is archived in the hand history and its settlement record is saved.
+     */
+    fn finalize_hand(
+        storage: &mut dyn Storage,
+        table_id: u32,
+        table: &PokerTable,
+        showdown_player_ids: &[Uuid],
+    ) -> Result<LastHandLogResponse, ContractError> {
+        let mut hand_log = create_hand_log(table, table_id, showdown_player_ids)?;
+        let game_result = create_game_result(table, table_id, showdown_player_ids);
+        hand_log.winners = split_pot(&game_result.winners);
+
+        hand_history(table_id).push(storage, &hand_log)?;
+        save_game_result(storage, &game_result)?;
+
+        Ok(hand_log)
+    }
+
+    // even percentages of the pot, the leftover points going one by one to the first winners
+    fn split_pot(winners: &[Uuid]) -> Vec<(Uuid, u8)> {
+        if winners.is_empty() {
+            return vec![];
+        }
+        let share = 100 / winners.len();
+        let leftover = 100 % winners.len();
+
+        winners
+            .iter()
+            .enumerate()
+            .map(|(i, player_id)| (*player_id, (share + usize::from(i < leftover)) as u8))
+            .collect()
+    }
+
+    fn create_game_result(table: &PokerTable, table_id: u32, showdown_player_ids: &[Uuid]) -> GameResult {
+        let community_cards = table.all_dealt_cards().board();
+        // a folded player gave up the pot, whatever their hand
+        let showdown_hands: Vec<(Uuid, Vec<Card>)> = table
+            .players
+            .iter()
+            .filter(|player| !player.folded && showdown_player_ids.contains(&player.player_id))
+            .map(|player| (player.player_id, player.hand.clone()))
+            .collect();
+        let rankings = eval::rank_hands(&showdown_hands, &community_cards, &table.deck_variant);
+        let winners = rankings
+            .first()
+            .map(|best| best.iter().map(|(player_id, _)| *player_id).collect())
+            .unwrap_or_default();
+
+        GameResult {
+            table_id,
+            hand_ref: table.hand_ref,
+            community_cards,
+            showdown_hands,
+            rankings,
+            winners,
+        }
+    }
+
+    fn create_hand_log(
+        table: &PokerTable,
+        table_id: u32,
+        showdown_player_ids: &[Uuid],
+    ) -> Result<LastHandLogResponse, ContractError> {
+        let showdown_players = showdown_players(table, table_id, showdown_player_ids)?
+            .into_iter()
+            .map(|player| ShowdownPlayer {
+                username: player.username.clone(),
+                hand: player.hand.iter().map(|card| card.to_string()).collect(),
+            })
+            .collect();
+
+        Ok(LastHandLogResponse {
+            hand_ref: table.hand_ref,
+            showdown_players,
+            community_cards: table.all_dealt_cards().board().iter().map(|card| card.to_string()).collect(),
+            burned_cards: table.community_cards.burned.iter().map(|card| card.to_string()).collect(),
+            flop_retrieved_at: table.community_cards.flop.retrieved_at,
+            turn_retrieved_at: table.community_cards.turn.as_ref().and_then(|turn| turn.retrieved_at),
+            river_retrieved_at: table.community_cards.river.as_ref().and_then(|river| river.retrieved_at),
+            showdown_retrieved_at: table.showdown_retrieved_at,
+            started_at: table.started_at,
+            stakes: table.stakes.clone(),
+            corrupted: false,
+            winners: vec![],
+        })
+    }
+
+    /*
+     * Ends the hand for good: the final hand log is emitted in plaintext, as StartGame would for the next hand,
+     * and the table is removed from storage.
+     */
+    pub fn handle_end_hand(
+        deps: DepsMut,
+        table_id: u32,
+        showdown_player_ids: Vec<Uuid>,
+    ) -> Result<Response, ContractError> {
+        let table = load_table_or_error(deps.storage, table_id)?;
+        let hand_log = finalize_hand(deps.storage, table_id, &table, &showdown_player_ids)?;
+
+        delete_table(deps.storage, table_id)?;
+
+        create_plaintext_response(RESPONSE_KEY.to_string(), ResponsePayload::LastHand(hand_log))
+    }
+
+    pub fn handle_mark_player_inactive(deps: DepsMut, table_id: u32, player_id: Uuid) -> Result<Response, ContractError> {
+        let mut table = load_table_or_error(deps.storage, table_id)?;
+
+        let player = table
+            .players
+            .iter_mut()
+            .find(|player| player.player_id == player_id)
+            .ok_or(ContractError::PlayerNotFound {
+                table_id,
+                player: player_id.to_string(),
+            })?;
+        player.inactive = true;
+
+        save_table(deps.storage, table_id, &table)?;
+
+        Ok(Response::new()
+            .add_attribute_plaintext("action", "mark_player_inactive")
+            .add_attribute_plaintext("player_id", player_id.to_string()))
+    }
+
+    pub fn handle_fold(deps: DepsMut, table_id: u32, player_id: Uuid) -> Result<Response, ContractError> {
+        let mut table = load_table_or_error(deps.storage, table_id)?;
+
+        if table.showdown_retrieved_at.is_some() {
+            return Err(ContractError::CardsAlreadyRetrieved {});
+        }
+
+        let player = table
+            .players
+            .iter_mut()
+            .find(|player| player.player_id == player_id)
+            .ok_or(ContractError::PlayerNotFound {
+                table_id,
+                player: player_id.to_string(),
+            })?;
+        player.folded = true;
+
+        save_table(deps.storage, table_id, &table)?;
+
+        Ok(Response::new()
+            .add_attribute_plaintext("action", "fold")
+            .add_attribute_plaintext("player_id", player_id.to_string()))
+    }
+
+    pub fn handle_transfer_ownership(deps: DepsMut, new_owner: Addr) -> Result<Response, ContractError> {
+        let new_owner = validate_addr(deps.api, &new_owner)?;
+
+        CONFIG_KEY.update(deps.storage, |mut config| -> StdResult<_> {
+            config.pending_owner = Some(new_owner.clone());
+            Ok(config)
+        })?;
+
+        Ok(Response::new()
+            .add_attribute_plaintext("action", "transfer_ownership")
+            .add_attribute_plaintext("pending_owner", new_owner))
+    }
+
+    pub fn handle_add_dealer(deps: DepsMut, dealer: Addr) -> Result<Response, ContractError> {
+        let dealer = validate_addr(deps.api, &dealer)?;
+
+        CONFIG_KEY.update(deps.storage, |mut config| -> StdResult<_> {
+            if !config.dealers.contains(&dealer) {
+                config.dealers.push(dealer.clone());
+            }
+            Ok(config)
+        })?;
+
+        Ok(Response::new()
+            .add_attribute_plaintext("action", "add_dealer")
+            .add_attribute_plaintext("dealer", dealer))
+    }
+
+    pub fn handle_set_paused(deps: DepsMut, paused: bool) -> Result<Response, ContractError> {
+        CONFIG_KEY.update(deps.storage, |mut config| -> StdResult<_> {
+            config.paused = paused;
+            Ok(config)
+        })?;
+
+        Ok(Response::new()
+            .add_attribute_plaintext("action", "set_paused")
+            .add_attribute_plaintext("paused", paused.to_string()))
+    }
+
+    // not validated, so a dealer stored before validation can always be removed
+    pub fn handle_remove_dealer(deps: DepsMut, dealer: Addr) -> Result<Response, ContractError> {
+        CONFIG_KEY.update(deps.storage, |mut config| -> StdResult<_> {
+            config.dealers.retain(|existing| *existing != dealer);
+            Ok(config)
+        })?;
+
+        Ok(Response::new()
+            .add_attribute_plaintext("action", "remove_dealer")
+            .add_attribute_plaintext("dealer", dealer))
+    }
+
+    /*
+     * Permits are validated against the signer's account, which is the sender of this message.
+     */
+    pub fn handle_revoke_permit(deps: DepsMut, info: MessageInfo, permit_name: String) -> Result<Response, ContractError> {
+        RevokedPermits::revoke_permit(deps.storage, PREFIX_REVOKED_PERMITS, info.sender.as_str(), &permit_name);
+
+        Ok(Response::new()
+            .add_attribute_plaintext("action", "revoke_permit")
+            .add_attribute_plaintext("permit_name", permit_name))
+    }
+
+    pub fn handle_accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+        let mut config = CONFIG_KEY.load(deps.storage)?;
+        if config.pending_owner.as_ref() != Some(&info.sender) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // the previous owner's dealer rights go with the ownership
+        let previous_owner = std::mem::replace(&mut config.owner, info.sender);
+        config.dealers.retain(|dealer| *dealer != previous_owner);
+        config.pending_owner = None;
+        CONFIG_KEY.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute_plaintext("action", "accept_ownership")
+            .add_attribute_plaintext("owner", config.owner))
+    }
+
+    fn create_plaintext_response(
+        key: String,
+        response: ResponsePayload
+    ) -> Result<Response, ContractError> {
+        Ok(Response::new().add_attribute_plaintext(key, serialize_response(response)?))
+    }
+
+    fn serialize_response(response: ResponsePayload) -> Result<String, ContractError> {
+        match serde_json_wasm::to_string(&response) {
+            Ok(json) => Ok(json),
+            Err(e) => Err(ContractError::SerializationFailed {
+                error: e.to_string(),
+            }),
+        }
+    }
+
+
+    pub fn handle_community_cards(
+        deps: DepsMut,
+        env: Env,
+        table_id: u32,
+        game_state: GameState,
+        expected_hand_ref: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let mut table = load_table_or_error(deps.storage, table_id)?;
+        validate_hand_ref(&table, expected_hand_ref)?;
+        let cards = reveal_community_cards(&mut table, &env, table_id, game_state.clone())?;
+
+        save_table(deps.storage, table_id, &table)?;
+        increment_metrics(deps.storage, |metrics| metrics.community_cards += 1)?;
+
+        let board_paired = table.community_cards.is_paired();
+        create_community_cards_response(table_id, table.hand_ref, game_state, cards, board_paired, env.block.time)
+    }
+
+    // a client acting on a stale hand would otherwise reveal the cards of the hand that replaced it
+    fn validate_hand_ref(table: &PokerTable, expected_hand_ref: Option<u32>) -> Result<(), ContractError> {
+        match expected_hand_ref {
+            Some(expected) if expected != table.hand_ref => {
+                Err(ContractError::HandRefMismatch { expected, actual: table.hand_ref })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn handle_advance_phase(
+        deps: DepsMut,
+        env: Env,
+        table_id: u32,
+    ) -> Result<Response, ContractError> {
+        let mut table = load_table_or_error(deps.storage, table_id)?;
+
+        let next_phase = table.current_phase.next().ok_or(ContractError::GameStateError {
+            method: "advance_phase".to_string(),
+            table_id,
+            game_state: Some(table.current_phase.clone()),
+        })?;
+        let cards = reveal_community_cards(&mut table, &env, table_id, next_phase.clone())?;
+
+        save_table(deps.storage, table_id, &table)?;
+        increment_metrics(deps.storage, |metrics| metrics.community_cards += 1)?;
+
+        let board_paired = table.community_cards.is_paired();
+        create_community_cards_response(table_id, table.hand_ref, next_phase, cards, board_paired, env.block.time)
+    }
+
+    fn reveal_community_cards(
+        table: &mut PokerTable,
+        env: &Env,
+        table_id: u32,
+        game_state: GameState,
+    ) -> Result<Vec<Card>, ContractError> {
+        /*
+         * We check if the cards have already been retrieved, if so we return an error.
+         * This ensures that the logged time is the only time the cards were retrieved.
+         */
+        if !table.community_cards.is_dealt(&game_state) {
+            return Err(ContractError::GameStateError {
+                method: "distribute_community_cards".to_string(),
+                table_id,
+                game_state: Some(game_state),
+            });
+        }
+
+        let cards = match (game_state.clone(), &mut table.community_cards) {
+            (GameState::Flop, _) => {
+                if table.community_cards.flop.retrieved_at.is_some() {
+                    return Err(ContractError::CardsAlreadyRetrieved {});
+                }
+                validate_reveal_order(&table.current_phase, &game_state)?;
+                table.community_cards.flop.retrieved_at = Some(env.block.time);
+                table.community_cards.flop.consumed = true;
+                table.community_cards.flop.cards.clone()
+            }
+            (GameState::Turn, CommunityCards { turn: Some(turn), .. }) => {
+                if turn.retrieved_at.is_some() {
+                    return Err(ContractError::CardsAlreadyRetrieved {});
+                }
+                validate_reveal_order(&table.current_phase, &game_state)?;
+                turn.retrieved_at = Some(env.block.time);
+                turn.consumed = true;
+                vec![turn.card.clone()]
+            }
+            (GameState::River, CommunityCards { river: Some(river), .. }) => {
+                if river.retrieved_at.is_some() {
+                    return Err(ContractError::CardsAlreadyRetrieved {});
+                }
+                validate_reveal_order(&table.current_phase, &game_state)?;
+                river.retrieved_at = Some(env.block.time);
+                river.consumed = true;
+                vec![river.card.clone()]
+            }
+            _ => {
+                return Err(ContractError::GameStateError {
+                    method: "distribute_community_cards".to_string(),
+                    table_id,
+                    game_state: Some(game_state),
+                })
+            }
+        };
+
+        table.current_phase = game_state;
+        Ok(cards)
+    }
+
+    // streets are revealed one after the other, and none once the showdown completed the hand
+    fn validate_reveal_order(current_phase: &GameState, game_state: &GameState) -> Result<(), ContractError> {
+        let expected = current_phase.next().unwrap_or(GameState::Showdown);
+        if *game_state != expected {
+            return Err(ContractError::OutOfOrderReveal { expected, got: game_state.clone() });
+        }
+        Ok(())
+    }
+
+    fn create_community_cards_response(
+        table_id: u32,
+        hand_ref: u32,
+        game_state: GameState,
+        community_cards: Vec<Card>,
+        board_paired: bool, // over the streets revealed so far, for the UI and hand evaluation
+        retrieved_at: Timestamp,
+    ) -> Result<Response, ContractError> {
+        let card_count = community_cards.len();
+        let game_state_attribute = game_state.as_str();
+        let response = ResponsePayload::CommunityCards(CommunityCardsResponse {
+            table_id,
+            hand_ref,
+            game_state,
+            community_cards,
+            retrieved_at: Some(retrieved_at),
+        });
+
+        // flat copies of the response fields, so indexers can filter the reveals without parsing the payload
+        Ok(create_plaintext_response(RESPONSE_KEY.to_string(), response)?
+            .add_attribute_plaintext("table_id", table_id.to_string())
+            .add_attribute_plaintext("hand_ref", hand_ref.to_string())
+            .add_attribute_plaintext("game_state", game_state_attribute)
+            .add_attribute_plaintext("card_count", card_count.to_string())
+            .add_attribute_plaintext("board_paired", board_paired.to_string()))
+    }
+
+    pub fn handle_showdown(
+        deps: DepsMut,
+        env: Env,
+        table_id: u32,
+        game_state: GameState,
+        showdown_player_ids: Vec<Uuid>,
+        expected_hand_ref: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let mut table = load_table_or_error(deps.storage, table_id)?;
+        validate_hand_ref(&table, expected_hand_ref)?;
+
+        /*
+         * Ensures that the cards cannot be retrieved twice, 
+         * this ensures that the logged time is the only time the cards were retrieved.
+         */
+        if table.showdown_retrieved_at.is_some() {
+            return Err(ContractError::CardsAlreadyRetrieved {});
+        }
+
+        let player_hands: Vec<(Uuid, Vec<Card>)> = showdown_players(&table, table_id, &showdown_player_ids)?
+            .into_iter()
+            .map(|player| (player.player_id, player.hand.clone()))
+            .collect();
+
+        let community_cards = handle_all_in_showdown(&table.community_cards, game_state.clone());
+        let response = ResponsePayload::Showdown(ShowdownResponse {
+            table_id,
+            hand_ref: table.hand_ref,
+            players_cards: player_hands,
+            board_len: community_cards.as_ref().map_or(0, Vec::len),
+            community_cards,
+            burned: Some(table.community_cards.burned.clone()),
+            undealt: Some(table.undealt.clone()),
+            reveal_order: table.reveal_order(&showdown_player_ids),
+            from_state: game_state,
+        });
+
+        
+        table.showdown_retrieved_at = Some(env.block.time);
+        table.current_phase = GameState::Showdown;
+        // the whole board is public once the showdown is retrieved
+        table.community_cards.consume_all();
+        save_table(deps.storage, table_id, &table)?;
+        increment_metrics(deps.storage, |metrics| metrics.showdowns += 1)?;
+
+        create_plaintext_response(RESPONSE_KEY.to_string(), response)
+    }
+
+    fn handle_all_in_showdown(
+        community_cards: &CommunityCards,
+        game_state: GameState,
+    ) -> Option<Vec<Card>> {
+        match game_state {
+            /*
+             * On a river showdown every street was already dealt, we still send the whole board
+             * so the showdown response is self-sufficient to evaluate the hands.
+             */
+            GameState::PreFlop | GameState::River | GameState::Showdown => {
+                let mut cards = community_cards.flop.cards.clone();
+                cards.extend(community_cards.turn.as_ref().map(|turn| turn.card.clone()));
+                cards.extend(community_cards.river.as_ref().map(|river| river.card.clone()));
+                Some(cards)
+            }
+            GameState::Flop => Some(
+                community_cards
+                    .turn
+                    .as_ref()
+                    .map(|turn| turn.card.clone())
+                    .into_iter()
+                    .chain(community_cards.river.as_ref().map(|river| river.card.clone()))
+                    .collect(),
+            ),
+            GameState::Turn => Some(community_cards.river.as_ref().map(|river| river.card.clone()).into_iter().collect()),
+        }
+    }
+}
+
+
+#[entry_point]
+pub fn instantiate(deps: DepsMut, env: Env, info: MessageInfo, msg: InstantiateMsg,) -> Result<Response, ContractError> {
+    let min_players = msg.min_players.unwrap_or(MIN_PLAYERS);
+    let max_players = msg.max_players.unwrap_or(MAX_PLAYERS);
+    if !(MIN_PLAYERS <= min_players && min_players <= max_players && max_players <= MAX_PLAYERS) {
+        return Err(ContractError::InvalidPlayerLimits { min: min_players, max: max_players });
+    }
+
+    // both come from the chain, validated all the same so every stored address went through validate_addr
+    let owner = validate_addr(deps.api, &info.sender)?;
+    let config = Config {
+        dealers: vec![owner.clone()],
+        owner,
+        contract_address: validate_addr(deps.api, &env.contract.address)?,
+        strict_one_time_reveal: msg.strict_one_time_reveal,
+        pending_owner: None,
+        paused: false,
+    };
+
+    let counter = init_counter(&env)?;
+
+    CONFIG_KEY.save(deps.storage, &config)?;
+    INSTANTIATION_KEY.save(deps.storage, &Instantiation { time: env.block.time, height: env.block.height })?;
+    PLAYER_LIMITS_KEY.save(deps.storage, &PlayerLimits { min_players, max_players })?;
+    COUNTER_KEY.save(deps.storage, &counter)?;
+    METRICS_KEY.save(deps.storage, &ExecuteMetrics::default())?;
+    CONTRACT_VERSION_KEY.save(deps.storage, &CONTRACT_VERSION.to_string())?;
+
+    Ok(Response::default())
+}
+
+/*
+ * Contracts instantiated before versioning have no stored version and are migrated as 0.0.0.
+ * The Json stores (tables, hand history, game results) absorb added fields with serde defaults,
+ * the Bincode2 items can't: their older layouts are rewritten here.
+ */
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = CONTRACT_VERSION_KEY.may_load(deps.storage)?.unwrap_or_else(|| "0.0.0".to_string());
+    let invalid_migration = || ContractError::InvalidMigration {
+        stored: stored.clone(),
+        current: CONTRACT_VERSION.to_string(),
+    };
+
+    let stored_version = parse_version(&stored).ok_or_else(invalid_migration)?;
+    let current_version = parse_version(CONTRACT_VERSION).ok_or_else(invalid_migration)?;
+    if stored_version > current_version {
+        return Err(invalid_migration());
+    }
+
+    // a Config saved before it grew only decodes with its original layout
+    if CONFIG_KEY.load(deps.storage).is_err() {
+        let legacy = LEGACY_CONFIG_KEY.load(deps.storage)?;
+        CONFIG_KEY.save(deps.storage, &Config::from(legacy))?;
+    }
+    if METRICS_KEY.may_load(deps.storage)?.is_none() {
+        METRICS_KEY.save(deps.storage, &ExecuteMetrics::default())?;
+    }
+
+    CONTRACT_VERSION_KEY.save(deps.storage, &CONTRACT_VERSION.to_string())?;
+
+    Ok(Response::new()
+        .add_attribute_plaintext("action", "migrate")
+        .add_attribute_plaintext("from_version", stored)
+        .add_attribute_plaintext("to_version", CONTRACT_VERSION))
+}
+
+// addresses are stored as validated by the chain, so comparing them with a sender holds
+fn validate_addr(api: &dyn Api, addr: &Addr) -> Result<Addr, ContractError> {
+    api.addr_validate(addr.as_str())
+        .map_err(|_| ContractError::InvalidAddress { address: addr.to_string() })
+}
+
+// major.minor.patch, pre-release and build metadata are not used by this crate
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.split('.').map(|part| part.parse::<u64>().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    parts.next().is_none().then_some(version)
+}
+
+/*
+ * The contract cannot deal a single hand without block randomness, so we make sure the target chain
+ * provides enough of it at instantiation instead of failing at the first StartGame.
+ * The seed is kept as is as the first counter: every draw is checked, so the contract has u128::MAX - seed
+ * unique draws before they fail with CounterExhausted instead of wrapping around and repeating.
+ */
+fn init_counter(env: &Env) -> Result<u128, ContractError> {
+    let seed = env
+        .block
+        .random
+        .as_ref()
+        .ok_or(ContractError::MissingRandomness {})?;
+
+    if seed.len() < RANDOM_SEED_SIZE {
+        return Err(ContractError::InsufficientRandomness {
+            length: seed.len(),
+            required: RANDOM_SEED_SIZE,
+        });
+    }
+
+    let seed_bytes: [u8; RANDOM_SEED_SIZE] = seed[..RANDOM_SEED_SIZE]
+        .try_into()
+        .map_err(|_| StdError::generic_err("Failed to convert seed to array"))?;
+    Ok(u128::from_le_bytes(seed_bytes))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG_KEY.load(deps.storage)?;
+    let authorized = match msg {
+        // the pending owner isn't the owner yet, handle_accept_ownership authorizes it
+        ExecuteMsg::AcceptOwnership {} => true,
+        ExecuteMsg::RevokePermit { .. } => true,
+        ExecuteMsg::TransferOwnership { .. }
+        | ExecuteMsg::AddDealer { .. }
+        | ExecuteMsg::RemoveDealer { .. }
+        | ExecuteMsg::SetPaused { .. }
+        | ExecuteMsg::RebuildTable { .. } => info.sender == config.owner,
+        _ => config.is_dealer(&info.sender),
+    };
+    if !authorized {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    match msg {
+        ExecuteMsg::StartGame(game) => execute_handlers::handle_start_game(deps, env, game),
+        ExecuteMsg::BulkStartGame { games } => execute_handlers::handle_bulk_start_game(deps, env, games),
+        ExecuteMsg::CommunityCards {
+            table_id,
+            game_state,
+            expected_hand_ref,
+        } => execute_handlers::handle_community_cards(deps, env, table_id, game_state, expected_hand_ref),
+        ExecuteMsg::Showdown {
+            table_id,
+            game_state,
+            showdown_player_ids,
+            expected_hand_ref,
+        } => execute_handlers::handle_showdown(
+            deps,
+            env,
+            table_id,
+            game_state,
+            showdown_player_ids,
+            expected_hand_ref,
+        ),
+        ExecuteMsg::AdvancePhase { table_id } => {
+            execute_handlers::handle_advance_phase(deps, env, table_id)
+        }
+        ExecuteMsg::EndHand {
+            table_id,
+            showdown_player_ids,
+        } => execute_handlers::handle_end_hand(deps, table_id, showdown_player_ids),
+        ExecuteMsg::TransferOwnership { new_owner } => {
+            execute_handlers::handle_transfer_ownership(deps, new_owner)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_handlers::handle_accept_ownership(deps, info),
+        ExecuteMsg::AddDealer { dealer } => execute_handlers::handle_add_dealer(deps, dealer),
+        ExecuteMsg::RemoveDealer { dealer } => execute_handlers::handle_remove_dealer(deps, dealer),
+        ExecuteMsg::SetPaused { paused } => execute_handlers::handle_set_paused(deps, paused),
+        ExecuteMsg::RevokePermit { permit_name } => execute_handlers::handle_revoke_permit(deps, info, permit_name),
+        ExecuteMsg::MarkPlayerInactive { table_id, player_id } => {
+            execute_handlers::handle_mark_player_inactive(deps, table_id, player_id)
+        }
+        ExecuteMsg::Fold { table_id, player_id } => execute_handlers::handle_fold(deps, table_id, player_id),
+        ExecuteMsg::RebuildTable { table_id, players, hand_ref } => {
+            execute_handlers::handle_rebuild_table(deps, env, table_id, players, hand_ref)
+        }
+    }
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::WithPermit { permit, query } => {
+            query_handlers::handle_permit_query(deps, permit, query)
+        }
+        QueryMsg::CommunityCards {
+            table_id,
+            game_state,
+            secret_key,
+        } => to_binary(&query_handlers::query_community_cards(
+            deps, table_id, game_state, secret_key,
+        )?),
+        QueryMsg::RetrievedCommunityCards {
+            table_id,
+            game_state,
+            secret_key,
+        } => to_binary(&query_handlers::query_retrieved_community_cards(
+            deps, table_id, game_state, secret_key,
+        )?),
+        QueryMsg::Showdown {
+            table_id,
+            flop_secret,
             turn_secret,
             river_secret,
             players_secrets,
         } => to_binary(&query_handlers::query_showdown(
             deps,
-            table_id,
-            flop_secret,
-            turn_secret,
-            river_secret,
-            players_secrets,
-        )?),
+            table_id,
+            flop_secret,
+            turn_secret,
+            river_secret,
+            players_secrets,
+        )?),
+        QueryMsg::PlayerHand { table_id, hand_secret } => {
+            to_binary(&query_handlers::query_player_hand(deps, table_id, hand_secret)?)
+        }
+        QueryMsg::Winners {
+            table_id,
+            flop_secret,
+            turn_secret,
+            river_secret,
+            players_secrets,
+        } => to_binary(&query_handlers::query_winners(
+            deps,
+            table_id,
+            flop_secret,
+            turn_secret,
+            river_secret,
+            players_secrets,
+        )?),
+        QueryMsg::ShowdownWithWinners {
+            table_id,
+            flop_secret,
+            turn_secret,
+            river_secret,
+            players_secrets,
+        } => to_binary(&query_handlers::query_showdown_with_winners(
+            deps,
+            table_id,
+            flop_secret,
+            turn_secret,
+            river_secret,
+            players_secrets,
+        )?),
+        QueryMsg::HandChain { table_id } => to_binary(&query_handlers::query_hand_chain(deps, table_id)?),
+        QueryMsg::HandHistory {
+            table_id,
+            limit,
+            start_after,
+            compress,
+        } => {
+            let history = query_handlers::query_hand_history(deps, table_id, limit, start_after)?;
+            if compress {
+                let payload = ResponsePayload::HandHistory(history);
+                to_binary(&ResponsePayload::Compressed(CompressedResponse::encode(&payload)?))
+            } else {
+                to_binary(&history)
+            }
+        }
+        QueryMsg::GameResult { table_id, hand_ref } => {
+            to_binary(&query_handlers::query_game_result(deps, table_id, hand_ref)?)
+        }
+        QueryMsg::VerifyShuffle { table_id } => to_binary(&query_handlers::query_verify_shuffle(deps, table_id)?),
+        QueryMsg::Metrics {} => to_binary(&query_handlers::query_metrics(deps)?),
+        QueryMsg::Version {} => to_binary(&query_handlers::query_version(deps)?),
+        QueryMsg::Config {} => to_binary(&query_handlers::query_config(deps)?),
+        QueryMsg::AllCommunityCards {
+            table_id,
+            flop_secret,
+            turn_secret,
+            river_secret,
+        } => to_binary(&query_handlers::query_all_community_cards(
+            deps,
+            table_id,
+            flop_secret,
+            turn_secret,
+            river_secret,
+        )?),
+        QueryMsg::NextTableId {} => to_binary(&query_handlers::query_next_table_id(deps)?),
+        QueryMsg::Tables { start_after, limit } => to_binary(&query_handlers::query_tables(deps, start_after, limit)?),
+        QueryMsg::TableState { table_id } => to_binary(&query_handlers::query_table_state(deps, table_id)?),
+        QueryMsg::ShowdownStatus { table_id } => to_binary(&query_handlers::query_showdown_status(deps, table_id)?),
+        QueryMsg::DeckVariant { table_id } => to_binary(&query_handlers::query_deck_variant(deps, table_id)?),
+        QueryMsg::DeckCommitment { table_id } => to_binary(&query_handlers::query_deck_commitment(deps, table_id)?),
+        QueryMsg::CommunityCardsFromShares {
+            table_id,
+            game_state,
+            shares,
+        } => to_binary(&query_handlers::query_community_cards_from_shares(
+            deps, table_id, game_state, shares,
+        )?),
+        QueryMsg::PartialSum {
+            table_id,
+            game_state,
+            player_ids,
+        } => to_binary(&query_handlers::query_partial_sum(
+            deps, table_id, game_state, player_ids,
+        )?),
+    }
+}
+
+#[cfg(test)]
+mod complete_tests {
+    use crate::contract::helpers::Randomness;
+    use crate::contract::query_handlers::query_player_private_data;
+    use crate::msg::PlayerDataResponse;
+    use crate::state::{LegacyConfig, PlayerBuilder, PokerTableBuilder, NEXT_TABLE_ID};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, from_binary, Addr, Timestamp};
+    use super::*;
+    use std::time::Instant;
+    use std::collections::HashMap;
+
+    // players 1..=count, named after their index
+    fn start_game_players(count: u128) -> Vec<StartGamePlayer> {
+        (1..=count)
+            .map(|i| StartGamePlayer {
+                username: format!("player{}", i),
+                player_id: Uuid::from_u128(i),
+                public_key: format!("key{}", i),
+            })
+            .collect()
+    }
+
+    // hand 1 of the table with the default settings, tests override the fields they exercise
+    fn start_game_args(table_id: u32, players: Vec<StartGamePlayer>) -> StartGameArgs {
+        StartGameArgs {
+            table_id,
+            hand_ref: 1,
+            players,
+            prev_hand_showdown_players: vec![],
+            compress_hand_log: false,
+            shuffle_max_retries: None,
+            table_intent: TableIntent::Any,
+            phases: None,
+            sharing_scheme: SharingScheme::Additive,
+            button_seat: None,
+            deck_variant: DeckVariant::Standard,
+            max_hands: None,
+            stakes: None,
+        }
+    }
+
+    // StartGame sent by the owner
+    fn execute_start_game(deps: DepsMut, game: StartGameArgs) -> Result<Response, ContractError> {
+        execute(deps, mock_env(), mock_info("creator", &[]), ExecuteMsg::StartGame(game))
+    }
+
+    fn start_game(deps: DepsMut, table_id: u32, players: Vec<StartGamePlayer>) -> Response {
+        execute_start_game(deps, start_game_args(table_id, players)).unwrap()
+    }
+
+    #[test]
+    fn test_instantiate() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let res = instantiate(mock_dependencies().as_mut(), mock_env(), mock_info("Creator", &[]), InstantiateMsg::default());
+        assert_eq!(res.unwrap_err(), ContractError::InvalidAddress { address: "Creator".to_string() });
+    }
+
+    #[test]
+    fn test_query_config() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let mut env = mock_env();
+        env.block.height = 4_242;
+        env.block.time = Timestamp::from_seconds(1_700_000_000);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config.owner, Addr::unchecked("creator"));
+        assert_eq!(config.contract_address, env.contract.address);
+        assert_eq!(config.instantiated_at, Some(env.block.time));
+        assert_eq!(config.instantiated_height, Some(env.block.height));
+        assert!(!config.strict_one_time_reveal);
+        assert_eq!((config.min_players, config.max_players), (MIN_PLAYERS, MAX_PLAYERS));
+
+        // contracts instantiated before the block was recorded
+        INSTANTIATION_KEY.remove(&mut deps.storage);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!((config.instantiated_at, config.instantiated_height), (None, None));
+    }
+
+    #[test]
+    fn test_player_limits() {
+        let info = mock_info("creator", &coins(1000, "earth"));
+        for (min_players, max_players) in [(Some(1), None), (None, Some(10)), (Some(6), Some(4))] {
+            let msg = InstantiateMsg { min_players, max_players, ..Default::default() };
+            assert_eq!(
+                instantiate(mock_dependencies().as_mut(), mock_env(), info.clone(), msg).unwrap_err(),
+                ContractError::InvalidPlayerLimits {
+                    min: min_players.unwrap_or(MIN_PLAYERS),
+                    max: max_players.unwrap_or(MAX_PLAYERS),
+                }
+            );
+        }
+
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg { max_players: Some(6), ..Default::default() };
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let start_game = |deps: DepsMut, table_id: u32, player_count: u128| {
+            execute_start_game(deps, start_game_args(table_id, start_game_players(player_count)))
+        };
+
+        assert_eq!(start_game(deps.as_mut(), 1, 7).unwrap_err(), ContractError::InvalidPlayerCount { count: 7 });
+        start_game(deps.as_mut(), 1, 6).unwrap();
+        start_game(deps.as_mut(), 2, 2).unwrap();
+
+        // contracts instantiated before the limits fall back to MIN_PLAYERS and MAX_PLAYERS
+        PLAYER_LIMITS_KEY.remove(&mut deps.storage);
+        start_game(deps.as_mut(), 3, MAX_PLAYERS as u128).unwrap();
+        assert_eq!(
+            start_game(deps.as_mut(), 4, MAX_PLAYERS as u128 + 1).unwrap_err(),
+            ContractError::InvalidPlayerCount { count: MAX_PLAYERS + 1 }
+        );
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let query_config = |deps: Deps| -> ConfigResponse {
+            from_binary(&query(deps, mock_env(), QueryMsg::Config {}).unwrap()).unwrap()
+        };
+        let transfer = |new_owner: &str| ExecuteMsg::TransferOwnership {
+            new_owner: Addr::unchecked(new_owner),
+        };
+
+        // Only the owner can transfer, to a valid address
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), transfer("anyone"));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), transfer("NotNormalized"));
+        assert_eq!(res.unwrap_err(), ContractError::InvalidAddress { address: "NotNormalized".to_string() });
+        assert_eq!(query_config(deps.as_ref()).pending_owner, None);
+
+        // Nothing to accept yet
+        let res = execute(deps.as_mut(), mock_env(), mock_info("new_owner", &[]), ExecuteMsg::AcceptOwnership {});
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+        execute(deps.as_mut(), mock_env(), info.clone(), transfer("new_owner")).unwrap();
+        let config = query_config(deps.as_ref());
+        assert_eq!(config.owner, Addr::unchecked("creator"));
+        assert_eq!(config.pending_owner, Some(Addr::unchecked("new_owner")));
+
+        // Only the pending owner can accept
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), ExecuteMsg::AcceptOwnership {});
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("new_owner", &[]), ExecuteMsg::AcceptOwnership {}).unwrap();
+
+        let config = query_config(deps.as_ref());
+        assert_eq!(config.owner, Addr::unchecked("new_owner"));
+        assert_eq!(config.pending_owner, None);
+        assert!(config.dealers.is_empty());
+
+        // The previous owner lost its rights
+        let res = execute(deps.as_mut(), mock_env(), info, transfer("creator"));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info("new_owner", &[]), transfer("creator")).unwrap();
+    }
+
+    #[test]
+    fn test_dealers() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let query_config = |deps: Deps| -> ConfigResponse {
+            from_binary(&query(deps, mock_env(), QueryMsg::Config {}).unwrap()).unwrap()
+        };
+        assert_eq!(query_config(deps.as_ref()).dealers, vec![Addr::unchecked("creator")]);
+
+        let start_game = |deps: DepsMut, sender: &str, table_id: u32| {
+            execute(
+                deps,
+                mock_env(),
+                mock_info(sender, &[]),
+                ExecuteMsg::StartGame(start_game_args(table_id, start_game_players(2))),
+            )
+        };
+        let add_dealer = |dealer: &str| ExecuteMsg::AddDealer { dealer: Addr::unchecked(dealer) };
+
+        assert_eq!(start_game(deps.as_mut(), "dealer", 1).unwrap_err(), ContractError::Unauthorized {});
+
+        // Only the owner manages dealers
+        let res = execute(deps.as_mut(), mock_env(), mock_info("dealer", &[]), add_dealer("dealer"));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), info.clone(), add_dealer("dealer")).unwrap();
+        execute(deps.as_mut(), mock_env(), info.clone(), add_dealer("dealer")).unwrap();
+        // a malformed address would never match a sender
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), add_dealer("Dealer"));
+        assert_eq!(res.unwrap_err(), ContractError::InvalidAddress { address: "Dealer".to_string() });
+        assert_eq!(
+            query_config(deps.as_ref()).dealers,
+            vec![Addr::unchecked("creator"), Addr::unchecked("dealer")]
+        );
+
+        // A dealer runs hands but can't manage the contract
+        start_game(deps.as_mut(), "dealer", 1).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("dealer", &[]), add_dealer("other"));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::RemoveDealer { dealer: Addr::unchecked("dealer") },
+        )
+        .unwrap();
+        assert_eq!(start_game(deps.as_mut(), "dealer", 2).unwrap_err(), ContractError::Unauthorized {});
+
+        // Configs saved before dealers existed still authorize their owner
+        let mut config = CONFIG_KEY.load(&deps.storage).unwrap();
+        config.dealers.clear();
+        CONFIG_KEY.save(&mut deps.storage, &config).unwrap();
+        start_game(deps.as_mut(), "creator", 2).unwrap();
+    }
+
+    #[test]
+    fn test_set_paused() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let start_game = |deps: DepsMut, table_id: u32, hand_ref: u32| {
+            execute_start_game(deps, StartGameArgs { hand_ref, ..start_game_args(table_id, start_game_players(2)) })
+        };
+        let set_paused = |paused: bool| ExecuteMsg::SetPaused { paused };
+        let is_paused = |deps: Deps| -> bool {
+            from_binary::<ConfigResponse>(&query(deps, mock_env(), QueryMsg::Config {}).unwrap()).unwrap().paused
+        };
+        start_game(deps.as_mut(), 1, 1).unwrap();
+
+        // Only the owner pauses, not even a dealer
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AddDealer { dealer: Addr::unchecked("dealer") })
+            .unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("dealer", &[]), set_paused(true));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        assert!(!is_paused(deps.as_ref()));
+
+        execute(deps.as_mut(), mock_env(), info.clone(), set_paused(true)).unwrap();
+        assert!(is_paused(deps.as_ref()));
+        assert_eq!(start_game(deps.as_mut(), 2, 1).unwrap_err(), ContractError::ContractPaused {});
+        assert!(load_table(&deps.storage, 2).is_none());
+
+        // the hand in progress still finishes
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CommunityCards { table_id: 1, game_state: GameState::Flop, expected_hand_ref: None },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Showdown {
+                table_id: 1,
+                game_state: GameState::Flop,
+                showdown_player_ids: vec![Uuid::from_u128(1), Uuid::from_u128(2)],
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(start_game(deps.as_mut(), 1, 2).unwrap_err(), ContractError::ContractPaused {});
+
+        execute(deps.as_mut(), mock_env(), info.clone(), set_paused(false)).unwrap();
+        assert!(!is_paused(deps.as_ref()));
+        start_game(deps.as_mut(), 1, 2).unwrap();
+        start_game(deps.as_mut(), 2, 1).unwrap();
+    }
+
+    #[test]
+    fn test_max_hands() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let start_game = |deps: DepsMut, hand_ref: u32, max_hands: Option<u32>| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                max_hands,
+                ..start_game_args(1, start_game_players(2))
+            })
+        };
+
+        start_game(deps.as_mut(), 1, Some(2)).unwrap();
+        retrieve_showdown(deps.as_mut(), 1);
+        // the limit carries over to the next hands
+        start_game(deps.as_mut(), 2, None).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!((table.max_hands, table.hands_dealt), (Some(2), 2));
+        retrieve_showdown(deps.as_mut(), 1);
+
+        assert_eq!(
+            start_game(deps.as_mut(), 3, None).unwrap_err(),
+            ContractError::TableHandLimitReached { table_id: 1, max_hands: 2 }
+        );
+        // the last hand isn't finalized by the refused StartGame
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!((table.hand_ref, table.hands_dealt), (2, 2));
+        assert!(load_game_result(&deps.storage, 1, 2).is_none());
+
+        // ending the table resets the count
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::EndHand { table_id: 1, showdown_player_ids: vec![] },
+        )
+        .unwrap();
+        start_game(deps.as_mut(), 3, Some(2)).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!((table.hand_ref, table.max_hands, table.hands_dealt), (3, Some(2), 1));
+
+        // without a limit tables deal on
+        retrieve_showdown(deps.as_mut(), 1);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::EndHand { table_id: 1, showdown_player_ids: vec![] },
+        )
+        .unwrap();
+        for hand_ref in 4..8 {
+            start_game(deps.as_mut(), hand_ref, None).unwrap();
+            retrieve_showdown(deps.as_mut(), 1);
+        }
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hands_dealt, 4);
+    }
+
+    #[test]
+    fn test_stakes() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let start_game = |deps: DepsMut, hand_ref: u32, stakes: Option<Stakes>| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                stakes,
+                ..start_game_args(1, start_game_players(2))
+            })
+        };
+        let stakes = |small_blind: u128, big_blind: u128, ante: Option<u128>, currency: &str| Stakes {
+            small_blind: small_blind.into(),
+            big_blind: big_blind.into(),
+            ante: ante.map(Into::into),
+            currency: currency.to_string(),
+        };
+        let invalid = |reason: &str| ContractError::InvalidStakes { reason: reason.to_string() };
+
+        for (invalid_stakes, reason) in [
+            (stakes(0, 100, None, "uscrt"), "blinds and ante must be nonzero"),
+            (stakes(50, 0, None, "uscrt"), "blinds and ante must be nonzero"),
+            (stakes(50, 100, Some(0), "uscrt"), "blinds and ante must be nonzero"),
+            (stakes(100, 50, None, "uscrt"), "big blind must be at least the small blind"),
+            (stakes(50, 100, Some(10), ""), "currency must not be empty"),
+        ] {
+            assert_eq!(start_game(deps.as_mut(), 1, Some(invalid_stakes)).unwrap_err(), invalid(reason));
+        }
+        assert!(load_table(&deps.storage, 1).is_none());
+
+        let played = stakes(50, 100, Some(10), "uscrt");
+        start_game(deps.as_mut(), 1, Some(played.clone())).unwrap();
+        assert_eq!(load_table(&deps.storage, 1).unwrap().stakes, Some(played.clone()));
+
+        // the archived hand records its own stakes, not the next hand's
+        retrieve_showdown(deps.as_mut(), 1);
+        let res = start_game(deps.as_mut(), 2, None).unwrap();
+        let hand_log_attr = res.attributes.iter().find(|attr| attr.key == "previous_hand_log").unwrap();
+        assert!(hand_log_attr.value.contains(r#""stakes":{"small_blind":"50","big_blind":"100","ante":"10","currency":"uscrt"}"#));
+        let hand_log = match serde_json_wasm::from_str(&hand_log_attr.value).unwrap() {
+            ResponsePayload::LastHand(hand_log) => hand_log,
+            _ => panic!("Expected LastHand response"),
+        };
+        assert_eq!((hand_log.hand_ref, hand_log.stakes), (1, Some(played)));
+
+        let equal_blinds = stakes(100, 100, None, "chips");
+        retrieve_showdown(deps.as_mut(), 1);
+        start_game(deps.as_mut(), 3, Some(equal_blinds.clone())).unwrap();
+        retrieve_showdown(deps.as_mut(), 1);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::EndHand { table_id: 1, showdown_player_ids: vec![] },
+        )
+        .unwrap();
+        let response_attr = res.attributes.iter().find(|attr| attr.key == RESPONSE_KEY).unwrap();
+        match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::LastHand(hand_log) => assert_eq!(hand_log.stakes, Some(equal_blinds)),
+            _ => panic!("Expected LastHand response"),
+        }
+    }
+
+    // permit signed for this contract address by the account below
+    const PERMIT_CONTRACT_ADDRESS: &str = "secret1rf03820fp8gngzg2w02vd30ns78qkc8rg8dxaq";
+    const PERMIT_ACCOUNT: &str = "secret1399pyvvk3hvwgxwt3udkslsc5jl3rqv4yshfrl";
+    const PERMIT_NAME: &str = "memo_secret1rf03820fp8gngzg2w02vd30ns78qkc8rg8dxaq";
+
+    fn signed_permit() -> Permit {
+        use secret_toolkit_permit::{PermitParams, PermitSignature, PubKey, TokenPermissions};
+
+        Permit {
+            params: PermitParams {
+                allowed_tokens: vec![PERMIT_CONTRACT_ADDRESS.to_string()],
+                permit_name: PERMIT_NAME.to_string(),
+                chain_id: "pulsar-2".to_string(),
+                permissions: vec![TokenPermissions::History],
+            },
+            signature: PermitSignature {
+                pub_key: PubKey {
+                    r#type: "tendermint/PubKeySecp256k1".to_string(),
+                    value: Binary::from_base64("A5M49l32ZrV+SDsPnoRv8fH7ivNC4gEX9prvd4RwvRaL").unwrap(),
+                },
+                signature: Binary::from_base64(
+                    "hw/Mo3ZZYu1pEiDdymElFkuCuJzg9soDHw+4DxK7cL9rafiyykh7VynS+guotRAKXhfYMwCiyWmiznc6R+UlsQ==",
+                )
+                .unwrap(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_revoke_permit() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let account = PERMIT_ACCOUNT;
+        let permit_name = PERMIT_NAME;
+        let permit = signed_permit();
+        let mut config = CONFIG_KEY.load(&deps.storage).unwrap();
+        config.contract_address = Addr::unchecked(PERMIT_CONTRACT_ADDRESS);
+        CONFIG_KEY.save(&mut deps.storage, &config).unwrap();
+
+        let mut players = start_game_players(2);
+        players[0].public_key = account.to_string();
+        execute_start_game(deps.as_mut(), start_game_args(1, players)).unwrap();
+
+        let private_data_query = |deps: Deps| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::WithPermit {
+                    permit: permit.clone(),
+                    query: QueryWithPermit::PlayerPrivateData { table_id: 1 },
+                },
+            )
+        };
+        assert!(private_data_query(deps.as_ref()).is_ok());
+
+        // Revoking someone else's permit name has no effect
+        let revoke = ExecuteMsg::RevokePermit {
+            permit_name: permit_name.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("key2", &[]), revoke.clone()).unwrap();
+        assert!(private_data_query(deps.as_ref()).is_ok());
+
+        execute(deps.as_mut(), mock_env(), mock_info(account, &[]), revoke).unwrap();
+        assert!(private_data_query(deps.as_ref()).is_err());
+    }
+
+    #[test]
+    fn test_permit_scoped_to_seat() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let mut config = CONFIG_KEY.load(&deps.storage).unwrap();
+        config.contract_address = Addr::unchecked(PERMIT_CONTRACT_ADDRESS);
+        CONFIG_KEY.save(&mut deps.storage, &config).unwrap();
+
+        let start_game = |deps: DepsMut, table_id: u32, public_keys: [&str; 2]| {
+            let players = public_keys
+                .iter()
+                .enumerate()
+                .map(|(i, public_key)| StartGamePlayer {
+                    username: format!("player{}", i + 1),
+                    player_id: Uuid::from_u128(i as u128 + 1),
+                    public_key: public_key.to_string(),
+                })
+                .collect();
+            execute_start_game(deps, start_game_args(table_id, players)).unwrap();
+        };
+        // player A holds the permit, player B only sits at table 2
+        start_game(deps.as_mut(), 1, [PERMIT_ACCOUNT, "key2"]);
+        start_game(deps.as_mut(), 2, ["key2", "key3"]);
+
+        let private_data_query = |deps: Deps, table_id: u32| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::WithPermit {
+                    permit: signed_permit(),
+                    query: QueryWithPermit::PlayerPrivateData { table_id },
+                },
+            )
+        };
+
+        // A reads their own seat, never B's
+        let response: String = from_binary(&private_data_query(deps.as_ref(), 1).unwrap()).unwrap();
+        let private_data: PlayerDataResponse = serde_json_wasm::from_str(&response).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!(private_data.hand, table.players[0].hand);
+        assert_ne!(private_data.hand, table.players[1].hand);
+
+        assert_eq!(
+            private_data_query(deps.as_ref(), 2).unwrap_err(),
+            StdError::generic_err(ContractError::Unauthorized {}.to_string())
+        );
+    }
+
+    #[test]
+    fn test_all_player_shares() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let mut config = CONFIG_KEY.load(&deps.storage).unwrap();
+        config.contract_address = Addr::unchecked(PERMIT_CONTRACT_ADDRESS);
+        CONFIG_KEY.save(&mut deps.storage, &config).unwrap();
+
+        let mut players = start_game_players(3);
+        players[0].public_key = PERMIT_ACCOUNT.to_string();
+        start_game(deps.as_mut(), 1, players);
+
+        let all_player_shares = |deps: Deps, table_id: u32| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::WithPermit {
+                    permit: signed_permit(),
+                    query: QueryWithPermit::AllPlayerShares { table_id },
+                },
+            )
+        };
+
+        // a seated player's permit is not enough
+        assert_eq!(
+            all_player_shares(deps.as_ref(), 1).unwrap_err(),
+            StdError::generic_err(ContractError::Unauthorized {}.to_string())
+        );
+
+        let mut config = CONFIG_KEY.load(&deps.storage).unwrap();
+        config.owner = Addr::unchecked(PERMIT_ACCOUNT);
+        CONFIG_KEY.save(&mut deps.storage, &config).unwrap();
+
+        assert_eq!(
+            all_player_shares(deps.as_ref(), 2).unwrap_err(),
+            StdError::generic_err(ContractError::TableNotFound { table_id: 2 }.to_string())
+        );
+        let response: String = from_binary(&all_player_shares(deps.as_ref(), 1).unwrap()).unwrap();
+        let shares: Vec<PlayerDataResponse> = serde_json_wasm::from_str(&response).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!(shares.len(), 3);
+        for (player_data, player) in shares.iter().zip(table.players.iter()) {
+            assert_eq!(player_data.hand, player.hand);
+            assert_eq!(player_data.hand_secret, player.hand_secret.to_string());
+            assert_eq!(player_data.river_secret_share, player.river_secret_share.to_string());
+        }
+        let flop_secret = reconstruct_additive(
+            &shares.iter().map(|data| data.flop_secret_share.parse::<u128>().unwrap()).collect::<Vec<_>>(),
+        );
+        assert_eq!(flop_secret, table.community_cards.flop.secret);
+    }
+
+    #[test]
+    fn test_instantiate_insufficient_randomness() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        let mut env = mock_env();
+        env.block.random = Some(Binary::from(vec![0u8; RANDOM_SEED_SIZE - 1]));
+        let res = instantiate(deps.as_mut(), env, info.clone(), InstantiateMsg::default());
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::InsufficientRandomness {
+                length: RANDOM_SEED_SIZE - 1,
+                required: RANDOM_SEED_SIZE,
+            }
+        );
+
+        let mut env = mock_env();
+        env.block.random = None;
+        let res = instantiate(deps.as_mut(), env, info, InstantiateMsg::default());
+        assert_eq!(res.unwrap_err(), ContractError::MissingRandomness {});
+        assert!(CONFIG_KEY.load(deps.as_ref().storage).is_err());
+    }
+
+    #[test]
+    fn test_start_game() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::StartGame(StartGameArgs {
+                table_id: 1,
+                hand_ref: 1,
+                players,
+                prev_hand_showdown_players: vec![],
+                compress_hand_log: false,
+                shuffle_max_retries: None,
+                table_intent: TableIntent::Any,
+                phases: None,
+                sharing_scheme: SharingScheme::Additive,
+                button_seat: None,
+                deck_variant: DeckVariant::Standard,
+                max_hands: None,
+                stakes: None,
+            }),
+        )
+        .unwrap();
+        
+        let attrs = &res.attributes;
+        let response_attr = attrs.iter().find(|attr| attr.key == "response").unwrap();
+        assert!(response_attr.value.contains("\"players\":[\"player1\",\"player2\"]"));
+        assert!(response_attr.value.contains("\"table_id\":1"));
+        assert!(response_attr.value.contains("\"hand_ref\":1"));
+        match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::StartGame(start_game) => {
+                assert_eq!(start_game.cards_dealt, 4);
+                // two hole cards each, three burns and a five cards board
+                assert_eq!(start_game.deck_remaining, 52 - required_cards(2, COMMUNITY_CARD_PHASES));
+                assert_eq!(start_game.deck_remaining, load_table(&deps.storage, 1).unwrap().undealt.len());
+            }
+            _ => panic!("Expected StartGame response"),
+        }
+
+        let player_info1 = query_player_private_data(deps.as_ref(), 1, "key1".to_string()).unwrap();
+        let player_info2 = query_player_private_data(deps.as_ref(), 1, "key2".to_string()).unwrap();
+        
+        
+        assert_eq!(player_info1.table_id, 1);
+        assert_eq!(player_info1.hand_ref, 1);
+        assert_eq!(player_info1.hand.len(), 2);
+        assert!(player_info1.flop_secret_share.parse::<u128>().is_ok());
+        
+        assert_eq!(player_info2.table_id, 1);
+        assert_eq!(player_info2.hand_ref, 1);
+        assert_eq!(player_info2.hand.len(), 2);
+        assert!(player_info2.flop_secret_share.parse::<u128>().is_ok());
+        
+        let flop_secret = reconstruct_additive(&[
+            player_info1.flop_secret_share.parse::<u128>().unwrap(),
+            player_info2.flop_secret_share.parse::<u128>().unwrap(),
+        ]);
+        let turn_secret = reconstruct_additive(&[
+            player_info1.turn_secret_share.parse::<u128>().unwrap(),
+            player_info2.turn_secret_share.parse::<u128>().unwrap(),
+        ]);
+        let river_secret = reconstruct_additive(&[
+            player_info1.river_secret_share.parse::<u128>().unwrap(),
+            player_info2.river_secret_share.parse::<u128>().unwrap(),
+        ]);
+
+        
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::Flop,
+                secret_key: flop_secret,
+            },
+        );
+        let flop_response: CommunityCardsResponse = from_binary(res.as_ref().unwrap()).unwrap();
+        assert_eq!(flop_response.table_id, 1);
+        assert_eq!(flop_response.hand_ref, 1);
+        assert_eq!(flop_response.game_state, GameState::Flop);
+        assert_eq!(flop_response.retrieved_at, None);
+        assert_eq!(flop_response.community_cards.len(), 3);
+
+        
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::Turn,
+                secret_key: turn_secret,
+            },
+        );
+        let turn_response: CommunityCardsResponse = from_binary(res.as_ref().unwrap()).unwrap();
+        assert_eq!(turn_response.table_id, 1);
+        assert_eq!(turn_response.hand_ref, 1);
+        assert_eq!(turn_response.game_state, GameState::Turn);
+        assert_eq!(turn_response.retrieved_at, None);
+        assert_eq!(turn_response.community_cards.len(), 1);
+
+        
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::River,
+                secret_key: river_secret,
+            },
+        );
+        let river_response: CommunityCardsResponse = from_binary(res.as_ref().unwrap()).unwrap();
+        assert_eq!(river_response.table_id, 1);
+        assert_eq!(river_response.hand_ref, 1);
+        assert_eq!(river_response.game_state, GameState::River);
+        assert_eq!(river_response.community_cards.len(), 1);
+        assert_eq!(river_response.retrieved_at, None);
+
+        // once the owner reveals the flop, the query echoes the stored reveal time
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::CommunityCards { table_id: 1, game_state: GameState::Flop, expected_hand_ref: None },
+        )
+        .unwrap();
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::Flop,
+                secret_key: flop_secret,
+            },
+        );
+        let flop_response: CommunityCardsResponse = from_binary(res.as_ref().unwrap()).unwrap();
+        assert_eq!(flop_response.retrieved_at, Some(mock_env().block.time));
+
+        
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::Flop,
+                secret_key: flop_secret + 1, 
+            },
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err(ContractError::SecretMismatch { street: "flop".to_string() }.to_string())
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::PreFlop,
+                secret_key: flop_secret,
+            },
+        );
+        let expected = ContractError::GameStateError {
+            method: "query_community_cards".to_string(),
+            table_id: 1,
+            game_state: Some(GameState::PreFlop),
+        };
+        assert_eq!(res.unwrap_err(), StdError::generic_err(expected.to_string()));
+    }
+    
+    #[test]
+    fn test_community_cards() {
+        let mut deps = mock_dependencies();
+        
+        
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        
+        
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+        
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::StartGame(StartGameArgs {
+                table_id: 1,
+                hand_ref: 1,
+                players,
+                prev_hand_showdown_players: vec![],
+                compress_hand_log: false,
+                shuffle_max_retries: None,
+                table_intent: TableIntent::Any,
+                phases: None,
+                sharing_scheme: SharingScheme::Additive,
+                button_seat: None,
+                deck_variant: DeckVariant::Standard,
+                max_hands: None,
+                stakes: None,
+            }),
+        )
+        .unwrap();
+        
+        
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::Flop,
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+        
+        
+        let attrs = &res.attributes;
+        println!("{:?}", attrs);
+        let response_attr = attrs.iter().find(|attr| attr.key == "response").unwrap();
+        assert!(response_attr.value.contains("\"game_state\":\"flop\""));
+        
+        
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::Turn,
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+        
+        
+        let attrs = &res.attributes;
+        let response_attr = attrs.iter().find(|attr| attr.key == "response").unwrap();
+        assert!(response_attr.value.contains("\"game_state\":\"turn\""));
+        let response_payload: ResponsePayload = serde_json_wasm::from_str(&response_attr.value).unwrap();
+        match response_payload {
+            ResponsePayload::CommunityCards(cards_response) => {
+            assert_eq!(cards_response.community_cards.len(), 1);
+            assert_eq!(cards_response.game_state, GameState::Turn);
+            assert_eq!(cards_response.retrieved_at, Some(mock_env().block.time));
+            },
+            _ => panic!("Expected CommunityCards response"),
+        }
+    }
+    
+    #[test]
+    fn test_invalid_game_state() {
+        let mut deps = mock_dependencies();
+        
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+        
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::StartGame(StartGameArgs {
+                table_id: 1,
+                hand_ref: 1,
+                players,
+                prev_hand_showdown_players: vec![],
+                compress_hand_log: false,
+                shuffle_max_retries: None,
+                table_intent: TableIntent::Any,
+                phases: None,
+                sharing_scheme: SharingScheme::Additive,
+                button_seat: None,
+                deck_variant: DeckVariant::Standard,
+                max_hands: None,
+                stakes: None,
+            }),
+        )
+        .unwrap();
+        
+        
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::PreFlop,
+                expected_hand_ref: None,
+            },
+        );
+        
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::GameStateError { method, table_id, game_state } => {
+                assert_eq!(method, "distribute_community_cards");
+                assert_eq!(table_id, 1);
+                assert_eq!(game_state, Some(GameState::PreFlop));
+            },
+            _ => panic!("Expected GameStateError"),
+        }
+    }
+    
+    #[test]
+    fn test_showdown() {
+        let mut deps = mock_dependencies();
+        
+        
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        
+        
+        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let player2_id = Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap();
+        
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: player1_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: player2_id,
+                public_key: "key2".to_string(),
+            },
+        ];
+        
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::StartGame(StartGameArgs {
+                table_id: 1,
+                hand_ref: 1,
+                players,
+                prev_hand_showdown_players: vec![],
+                compress_hand_log: false,
+                shuffle_max_retries: None,
+                table_intent: TableIntent::Any,
+                phases: None,
+                sharing_scheme: SharingScheme::Additive,
+                button_seat: None,
+                deck_variant: DeckVariant::Standard,
+                max_hands: None,
+                stakes: None,
+            }),
+        )
+        .unwrap();
+        
+        
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Showdown {
+                table_id: 1,
+                game_state: GameState::River,
+                showdown_player_ids: vec![player1_id, player2_id],
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+        
+        
+        let attrs = &res.attributes;
+        let response_attr = attrs.iter().find(|attr| attr.key == "response").unwrap();
+        assert!(response_attr.value.contains("\"players_cards\""));
+
+        let table = load_table(&deps.storage, 1).unwrap();
+        match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::Showdown(showdown_response) => {
+                let community_cards = showdown_response.community_cards.unwrap();
+                assert_eq!(community_cards.len(), 5);
+                assert_eq!(community_cards, table.all_dealt_cards().board());
+            }
+            _ => panic!("Expected Showdown response"),
+        }
+    }
+    
+    #[test]
+    fn test_player_not_found() {
+        let mut deps = mock_dependencies();
+        
+        
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        
+        
+        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let player2_id = Uuid::parse_str("e6799ecf-f202-418a-a535-0b42509f69f7").unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: player1_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: player2_id,
+                public_key: "key2".to_string(),
+            },
+        ];
+        
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::StartGame(StartGameArgs {
+                table_id: 1,
+                hand_ref: 1,
+                players,
+                prev_hand_showdown_players: vec![],
+                compress_hand_log: false,
+                shuffle_max_retries: None,
+                table_intent: TableIntent::Any,
+                phases: None,
+                sharing_scheme: SharingScheme::Additive,
+                button_seat: None,
+                deck_variant: DeckVariant::Standard,
+                max_hands: None,
+                stakes: None,
+            }),
+        )
+        .unwrap();
+        
+        
+        let non_existent_player = Uuid::parse_str("aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee").unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Showdown {
+                table_id: 1,
+                game_state: GameState::River,
+                showdown_player_ids: vec![non_existent_player],
+                expected_hand_ref: None,
+            },
+        );
+        
+        assert!(res.is_err());
+        match res.unwrap_err() {
+            ContractError::PlayerNotFound { table_id, player } => {
+                assert_eq!(table_id, 1);
+                assert_eq!(player, non_existent_player.to_string());
+            },
+            _ => panic!("Expected PlayerNotFound error"),
+        }
+    }
+
+    #[test]
+    fn test_showdown_table_not_found() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Showdown {
+                table_id: 42,
+                game_state: GameState::River,
+                showdown_player_ids: vec![],
+                expected_hand_ref: None,
+            },
+        );
+        assert_eq!(res.unwrap_err(), ContractError::TableNotFound { table_id: 42 });
+
+        // same error as the community cards path
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::CommunityCards {
+                table_id: 42,
+                game_state: GameState::Flop,
+                expected_hand_ref: None,
+            },
+        );
+        assert_eq!(res.unwrap_err(), ContractError::TableNotFound { table_id: 42 });
+    }
+
+    #[test]
+    fn test_previous_hand_log_player_not_found() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: player1_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("e6799ecf-f202-418a-a535-0b42509f69f7").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let start_game = |deps: DepsMut, hand_ref: u32, prev_hand_showdown_players: Vec<Uuid>| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                prev_hand_showdown_players,
+                ..start_game_args(1, players.clone())
+            })
+        };
+
+        start_game(deps.as_mut(), 1, vec![]).unwrap();
+        retrieve_showdown(deps.as_mut(), 1);
+
+        let stale_player = Uuid::parse_str("aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee").unwrap();
+        assert_eq!(
+            start_game(deps.as_mut(), 2, vec![player1_id, stale_player]).unwrap_err(),
+            ContractError::PlayerNotFound {
+                table_id: 1,
+                player: stale_player.to_string(),
+            }
+        );
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hand_ref, 1);
+
+        let res = start_game(deps.as_mut(), 2, vec![player1_id]).unwrap();
+        let hand_log_attr = res.attributes.iter().find(|attr| attr.key == "previous_hand_log").unwrap();
+        match serde_json_wasm::from_str(&hand_log_attr.value).unwrap() {
+            ResponsePayload::LastHand(hand_log) => {
+                assert_eq!(hand_log.hand_ref, 1);
+                assert_eq!(hand_log.showdown_players.len(), 1);
+                assert_eq!(hand_log.showdown_players[0].username, "player1");
+            }
+            _ => panic!("Expected LastHand response"),
+        }
+    }
+
+    #[test]
+    fn test_last_hand_log() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let start_game = |deps: DepsMut, hand_ref: u32| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                prev_hand_showdown_players: vec![Uuid::from_u128(2)],
+                ..start_game_args(1, start_game_players(2))
+            })
+            .unwrap()
+        };
+        let last_hand_log = |deps: Deps, pub_key: &str| query_handlers::query_last_hand_log(deps, 1, pub_key.to_string());
+
+        start_game(deps.as_mut(), 1);
+        assert_eq!(last_hand_log(deps.as_ref(), "key1").unwrap().last_hand, None);
+
+        for hand_ref in [2, 3] {
+            retrieve_showdown(deps.as_mut(), 1);
+            let res = start_game(deps.as_mut(), hand_ref);
+            let hand_log_attr = res.attributes.iter().find(|attr| attr.key == "previous_hand_log").unwrap();
+            let emitted = match serde_json_wasm::from_str(&hand_log_attr.value).unwrap() {
+                ResponsePayload::LastHand(hand_log) => hand_log,
+                _ => panic!("Expected LastHand response"),
+            };
+
+            let response = last_hand_log(deps.as_ref(), "key2").unwrap();
+            assert_eq!(response.last_hand, Some(emitted));
+            assert_eq!(response.last_hand.unwrap().hand_ref, hand_ref - 1);
+        }
+
+        assert_eq!(last_hand_log(deps.as_ref(), "key3").unwrap_err(), StdError::generic_err("No player found"));
+        assert!(query_handlers::query_last_hand_log(deps.as_ref(), 2, "key1".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_metrics() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Metrics {}).unwrap();
+        let metrics: ExecuteMetrics = from_binary(&res).unwrap();
+        assert_eq!(metrics, ExecuteMetrics::default());
+
+        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let player2_id = Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: player1_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: player2_id,
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        start_game(deps.as_mut(), 1, players.clone());
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Metrics {}).unwrap();
+        let metrics: ExecuteMetrics = from_binary(&res).unwrap();
+        assert_eq!(metrics.start_games, 1);
+        assert_eq!(metrics.community_cards, 0);
+        assert_eq!(metrics.showdowns, 0);
+
+        for game_state in [GameState::Flop, GameState::Turn] {
+            execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::CommunityCards {
+                    table_id: 1,
+                    game_state,
+                    expected_hand_ref: None,
+                },
+            )
+            .unwrap();
+        }
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Metrics {}).unwrap();
+        let metrics: ExecuteMetrics = from_binary(&res).unwrap();
+        assert_eq!(metrics.start_games, 1);
+        assert_eq!(metrics.community_cards, 2);
+        assert_eq!(metrics.showdowns, 0);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Showdown {
+                table_id: 1,
+                game_state: GameState::Turn,
+                showdown_player_ids: vec![player1_id, player2_id],
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+
+        // A failed execution must not be counted
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::PreFlop,
+                expected_hand_ref: None,
+            },
+        );
+        assert!(res.is_err());
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Metrics {}).unwrap();
+        let metrics: ExecuteMetrics = from_binary(&res).unwrap();
+        assert_eq!(metrics.start_games, 1);
+        assert_eq!(metrics.community_cards, 2);
+        assert_eq!(metrics.showdowns, 1);
+
+        // contracts instantiated before the metrics start counting from zero
+        METRICS_KEY.remove(&mut deps.storage);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Metrics {}).unwrap();
+        assert_eq!(from_binary::<ExecuteMetrics>(&res).unwrap(), ExecuteMetrics::default());
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            hand_ref: 2,
+            ..start_game_args(1, players)
+        })
+        .unwrap();
+        assert_eq!(METRICS_KEY.load(&deps.storage).unwrap().start_games, 1);
+    }
+
+    #[test]
+    fn test_compressed_hand_log() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let player2_id = Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: player1_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: player2_id,
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let mut hand_logs = Vec::new();
+        for hand_ref in 1..=3 {
+            let hand1 = query_player_private_data(deps.as_ref(), 1, "key1".to_string()).map(|data| data.hand);
+            let hand2 = query_player_private_data(deps.as_ref(), 1, "key2".to_string()).map(|data| data.hand);
+            if hand_ref > 1 {
+                retrieve_showdown(deps.as_mut(), 1);
+            }
+
+            let res = execute_start_game(deps.as_mut(), StartGameArgs {
+                hand_ref,
+                prev_hand_showdown_players: if hand_ref == 1 { vec![] } else { vec![player1_id, player2_id] },
+                compress_hand_log: true,
+                ..start_game_args(1, players.clone())
+            })
+            .unwrap();
+
+            let Some(log_attr) = res.attributes.iter().find(|attr| attr.key == "previous_hand_log") else {
+                assert_eq!(hand_ref, 1);
+                continue;
+            };
+
+            let compressed = match serde_json_wasm::from_str(&log_attr.value).unwrap() {
+                ResponsePayload::Compressed(compressed) => compressed,
+                _ => panic!("Expected Compressed response"),
+            };
+            let last_hand = match compressed.decode().unwrap() {
+                ResponsePayload::LastHand(last_hand) => last_hand,
+                _ => panic!("Expected LastHand response"),
+            };
+
+            let expected_hands: Vec<Vec<String>> = [hand1.unwrap(), hand2.unwrap()]
+                .iter()
+                .map(|hand| hand.iter().map(|card| card.to_string()).collect())
+                .collect();
+            assert_eq!(last_hand.showdown_players.len(), 2);
+            assert_eq!(last_hand.showdown_players[0].hand, expected_hands[0]);
+            assert_eq!(last_hand.showdown_players[1].hand, expected_hands[1]);
+            assert_eq!(last_hand.community_cards.len(), 5);
+            assert_eq!(last_hand.burned_cards.len(), 3);
+            assert!(last_hand.burned_cards.iter().all(|card| !last_hand.community_cards.contains(card)));
+            assert!(last_hand.burned_cards.iter().all(|card| !expected_hands.concat().contains(card)));
+            hand_logs.push(ResponsePayload::LastHand(last_hand));
+        }
+        assert_eq!(hand_logs.len(), 2);
+
+        for hand_log in hand_logs {
+            let compressed = CompressedResponse::encode(&hand_log).unwrap();
+            assert_eq!(compressed.decode().unwrap(), hand_log);
+        }
+    }
+
+    #[test]
+    fn test_strict_one_time_reveal() {
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        for strict_one_time_reveal in [false, true] {
+            let mut deps = mock_dependencies();
+            let msg = InstantiateMsg { strict_one_time_reveal, ..Default::default() };
+            let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+            execute_start_game(deps.as_mut(), start_game_args(1, players.clone()))
+            .unwrap();
+            let table = load_table(&deps.storage, 1).unwrap();
+            let flop_secret = table.community_cards.flop.secret;
+            let turn_secret = table.community_cards.turn.as_ref().unwrap().secret;
+
+            let query_cards = |deps: Deps, game_state: GameState, secret_key: u128| {
+                query(deps, mock_env(), QueryMsg::CommunityCards { table_id: 1, game_state, secret_key })
+            };
+
+            // Before the reveal the secret works in both modes
+            assert!(query_cards(deps.as_ref(), GameState::Flop, flop_secret).is_ok());
+
+            execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AdvancePhase { table_id: 1 }).unwrap();
+            assert!(load_table(&deps.storage, 1).unwrap().community_cards.flop.consumed);
+
+            let shares = table
+                .players
+                .iter()
+                .map(|player| PlayerShare {
+                    player_id: player.player_id,
+                    game_state: GameState::Flop,
+                    share: player.flop_secret_share,
+                })
+                .collect();
+            let from_shares = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::CommunityCardsFromShares { table_id: 1, game_state: GameState::Flop, shares },
+            );
+            let showdown = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::Showdown {
+                    table_id: 1,
+                    flop_secret: Some(flop_secret),
+                    turn_secret: None,
+                    river_secret: None,
+                    players_secrets: vec![],
+                },
+            );
+
+            assert_eq!(query_cards(deps.as_ref(), GameState::Flop, flop_secret).is_err(), strict_one_time_reveal);
+            assert_eq!(from_shares.is_err(), strict_one_time_reveal);
+            assert_eq!(showdown.is_err(), strict_one_time_reveal);
+            // the turn isn't revealed yet
+            assert!(query_cards(deps.as_ref(), GameState::Turn, turn_secret).is_ok());
+
+            retrieve_showdown(deps.as_mut(), 1);
+            assert_eq!(query_cards(deps.as_ref(), GameState::Turn, turn_secret).is_err(), strict_one_time_reveal);
+        }
+    }
+
+    #[test]
+    fn test_advance_phase() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        start_game(deps.as_mut(), 1, players);
+        assert_eq!(load_table(&deps.storage, 1).unwrap().current_phase, GameState::PreFlop);
+
+        for (game_state, cards_count) in [(GameState::Flop, 3), (GameState::Turn, 1), (GameState::River, 1)] {
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::AdvancePhase { table_id: 1 },
+            )
+            .unwrap();
+
+            let response_attr = res.attributes.iter().find(|attr| attr.key == "response").unwrap();
+            match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+                ResponsePayload::CommunityCards(cards_response) => {
+                    assert_eq!(cards_response.game_state, game_state);
+                    assert_eq!(cards_response.community_cards.len(), cards_count);
+                    assert_eq!(cards_response.retrieved_at, Some(mock_env().block.time));
+                }
+                _ => panic!("Expected CommunityCards response"),
+            }
+
+            let table = load_table(&deps.storage, 1).unwrap();
+            assert_eq!(table.current_phase, game_state);
+        }
+
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert!(table.community_cards.flop.retrieved_at.is_some());
+        assert!(table.community_cards.turn.unwrap().retrieved_at.is_some());
+        assert!(table.community_cards.river.unwrap().retrieved_at.is_some());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AdvancePhase { table_id: 1 },
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::GameStateError {
+                method: "advance_phase".to_string(),
+                table_id: 1,
+                game_state: Some(GameState::River),
+            }
+        );
+    }
+
+    #[test]
+    fn test_community_cards_from_duplicated_shares() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let player2_id = Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: player1_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: player2_id,
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        start_game(deps.as_mut(), 1, players);
+
+        let player_info1 = query_player_private_data(deps.as_ref(), 1, "key1".to_string()).unwrap();
+        let player_info2 = query_player_private_data(deps.as_ref(), 1, "key2".to_string()).unwrap();
+        let flop_share = |player_id: Uuid, share: &str| PlayerShare {
+            player_id,
+            game_state: GameState::Flop,
+            share: share.parse::<u128>().unwrap(),
+        };
+
+        let shares = vec![
+            flop_share(player1_id, &player_info1.flop_secret_share),
+            flop_share(player2_id, &player_info2.flop_secret_share),
+            flop_share(player1_id, &player_info1.flop_secret_share),
+            PlayerShare {
+                player_id: player2_id,
+                game_state: GameState::Turn,
+                share: player_info2.turn_secret_share.parse::<u128>().unwrap(),
+            },
+        ];
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCardsFromShares {
+                table_id: 1,
+                game_state: GameState::Flop,
+                shares: shares.clone(),
+            },
+        )
+        .unwrap();
+        let flop_response: CommunityCardsResponse = from_binary(&res).unwrap();
+        assert_eq!(flop_response.game_state, GameState::Flop);
+        assert_eq!(flop_response.community_cards, load_table(&deps.storage, 1).unwrap().community_cards.flop.cards);
+
+        let naive_sum = reconstruct_additive(&shares.iter().filter(|share| share.game_state == GameState::Flop).map(|share| share.share).collect::<Vec<_>>());
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCards {
+                table_id: 1,
+                game_state: GameState::Flop,
+                secret_key: naive_sum,
+            },
+        );
+        assert!(res.is_err());
+
+        let mut conflicting_shares = shares;
+        conflicting_shares.push(flop_share(player2_id, "42"));
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCardsFromShares {
+                table_id: 1,
+                game_state: GameState::Flop,
+                shares: conflicting_shares,
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_community_cards_with_backup_share() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let players = start_game_players(3);
+
+        start_game(deps.as_mut(), 1, players);
+        let table = load_table(&deps.storage, 1).unwrap();
+
+        // player 3 is disconnected, only players 1 and 2 submit their turn shares
+        let shares: Vec<PlayerShare> = table.players[..2]
+            .iter()
+            .map(|player| PlayerShare {
+                player_id: player.player_id,
+                game_state: GameState::Turn,
+                share: player.turn_secret_share,
+            })
+            .collect();
+        let from_shares = |deps: Deps| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::CommunityCardsFromShares {
+                    table_id: 1,
+                    game_state: GameState::Turn,
+                    shares: shares.clone(),
+                },
+            )
+        };
+        assert!(from_shares(deps.as_ref()).is_err());
+
+        // Only the owner can supply the stored share
+        let mark_inactive = |player_id: Uuid| ExecuteMsg::MarkPlayerInactive { table_id: 1, player_id };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("player1", &[]), mark_inactive(Uuid::from_u128(3)));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), mark_inactive(Uuid::from_u128(4)));
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::PlayerNotFound {
+                table_id: 1,
+                player: Uuid::from_u128(4).to_string(),
+            }
+        );
+
+        execute(deps.as_mut(), mock_env(), info, mark_inactive(Uuid::from_u128(3))).unwrap();
+        let response: CommunityCardsResponse = from_binary(&from_shares(deps.as_ref()).unwrap()).unwrap();
+        assert_eq!(response.community_cards, vec![table.community_cards.turn.unwrap().card]);
+
+        // the active players' shares are still required
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::CommunityCardsFromShares {
+                table_id: 1,
+                game_state: GameState::Turn,
+                shares: shares[..1].to_vec(),
+            },
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_duplicate_player_ids() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let player_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id,
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let res = execute_start_game(deps.as_mut(), start_game_args(1, players));
+        assert_eq!(res.unwrap_err(), ContractError::DuplicatePlayerIds {});
+        assert!(load_table(&deps.storage, 1).is_none());
+    }
+
+    #[test]
+    fn test_start_game_max_players() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let players: Vec<StartGamePlayer> = (0..MAX_PLAYERS)
+            .map(|i| StartGamePlayer {
+                username: format!("player{}", i),
+                player_id: Uuid::from_u128(i as u128 + 1),
+                public_key: format!("key{}", i),
+            })
+            .collect();
+
+        start_game(deps.as_mut(), 1, players);
+
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!(table.players.len(), MAX_PLAYERS);
+        assert!(table.players.iter().all(|player| player.hand.len() == 2));
+    }
+
+    #[test]
+    fn test_start_game_phases() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let start_game = |deps: DepsMut, hand_ref: u32, phases: Option<u8>| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                phases,
+                ..start_game_args(1, players.clone())
+            })
+        };
+
+        for phases in [0, COMMUNITY_CARD_PHASES as u8 + 1] {
+            assert_eq!(
+                start_game(deps.as_mut(), 1, Some(phases)).unwrap_err(),
+                ContractError::InvalidPhaseCount { phases, player_count: 2 }
+            );
+        }
+        assert!(required_cards(MAX_PLAYERS, COMMUNITY_CARD_PHASES) <= 52);
+        assert_eq!(required_cards(2, 1), 2 * 2 + 1 + 3);
+
+        // Flop only board
+        start_game(deps.as_mut(), 1, Some(1)).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!(table.community_cards.flop.cards.len(), 3);
+        assert_eq!(table.community_cards.turn, None);
+        assert_eq!(table.community_cards.river, None);
+        assert_eq!(table.community_cards.burned.len(), 1);
+        assert!(table.players.iter().all(|player| player.turn_secret_share == 0 && player.river_secret_share == 0));
+
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AdvancePhase { table_id: 1 }).unwrap();
+        assert_eq!(
+            execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AdvancePhase { table_id: 1 }).unwrap_err(),
+            ContractError::GameStateError {
+                method: "distribute_community_cards".to_string(),
+                table_id: 1,
+                game_state: Some(GameState::Turn),
+            }
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Showdown {
+                table_id: 1,
+                game_state: GameState::PreFlop,
+                showdown_player_ids: vec![],
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+        let response_attr = res.attributes.iter().find(|attr| attr.key == "response").unwrap();
+        match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::Showdown(showdown) => {
+                assert_eq!(showdown.community_cards, Some(table.community_cards.flop.cards.clone()));
+            }
+            _ => panic!("Expected Showdown response"),
+        }
+
+        // Default keeps the three phases
+        start_game(deps.as_mut(), 2, None).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert!(table.community_cards.turn.is_some() && table.community_cards.river.is_some());
+        assert_eq!(table.community_cards.burned.len(), COMMUNITY_CARD_PHASES);
+    }
+
+    #[test]
+    fn test_partial_sum() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let player_ids: Vec<Uuid> = (1..=3).map(Uuid::from_u128).collect();
+        let players = player_ids
+            .iter()
+            .enumerate()
+            .map(|(i, player_id)| StartGamePlayer {
+                username: format!("player{}", i),
+                player_id: *player_id,
+                public_key: format!("key{}", i),
+            })
+            .collect();
+
+        start_game(deps.as_mut(), 1, players);
+
+        let partial_sum_query = |deps: Deps, game_state: GameState, player_ids: Vec<Uuid>| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::PartialSum {
+                    table_id: 1,
+                    game_state,
+                    player_ids,
+                },
+            )
+            .and_then(|res| from_binary::<PartialSumResponse>(&res))
+        };
+
+        assert!(partial_sum_query(deps.as_ref(), GameState::Flop, vec![player_ids[0]]).is_err());
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Showdown {
+                table_id: 1,
+                game_state: GameState::River,
+                showdown_player_ids: player_ids.clone(),
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+
+        let table = load_table(&deps.storage, 1).unwrap();
+        for (game_state, secret) in [
+            (GameState::Flop, table.community_cards.flop.secret),
+            (GameState::Turn, table.community_cards.turn.as_ref().unwrap().secret),
+            (GameState::River, table.community_cards.river.as_ref().unwrap().secret),
+        ] {
+            let first = partial_sum_query(deps.as_ref(), game_state.clone(), vec![player_ids[0]]).unwrap();
+            let others = partial_sum_query(deps.as_ref(), game_state.clone(), player_ids[1..].to_vec()).unwrap();
+            assert!(!first.complete);
+            assert!(!others.complete);
+            assert_eq!(
+                reconstruct_additive(&[first.partial_sum.parse().unwrap(), others.partial_sum.parse().unwrap()]),
+                secret
+            );
+
+            // A player listed twice is only counted once
+            let mut all_player_ids = player_ids.clone();
+            all_player_ids.push(player_ids[0]);
+            let full = partial_sum_query(deps.as_ref(), game_state, all_player_ids).unwrap();
+            assert!(full.complete);
+            assert_eq!(full.partial_sum, secret.to_string());
+        }
+
+        assert!(partial_sum_query(deps.as_ref(), GameState::PreFlop, vec![player_ids[0]]).is_err());
+        assert!(partial_sum_query(deps.as_ref(), GameState::Flop, vec![Uuid::from_u128(42)]).is_err());
+    }
+
+    #[test]
+    fn test_winners() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let players = (0..4)
+            .map(|i| StartGamePlayer {
+                username: format!("player{}", i),
+                player_id: Uuid::from_u128(i + 1),
+                public_key: format!("key{}", i),
+            })
+            .collect();
+
+        start_game(deps.as_mut(), 1, players);
+
+        let table = load_table(&deps.storage, 1).unwrap();
+        let players_secrets: Vec<u128> = table.players.iter().map(|player| player.hand_secret).collect();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Winners {
+                table_id: 1,
+                flop_secret: Some(table.community_cards.flop.secret),
+                turn_secret: Some(table.community_cards.turn.as_ref().unwrap().secret),
+                river_secret: Some(table.community_cards.river.as_ref().unwrap().secret),
+                players_secrets: players_secrets.clone(),
+            },
+        )
+        .unwrap();
+        let winners: WinnersResponse = from_binary(&res).unwrap();
+        let board = table.all_dealt_cards().board();
+        assert_eq!(winners.community_cards, board);
+        assert_eq!(winners.rankings.iter().map(|group| group.len()).sum::<usize>(), 4);
+        for player in table.players.iter() {
+            let hand_rank = eval::best_hand(&player.hand, &board, &DeckVariant::Standard);
+            assert!(winners.rankings.iter().flatten().any(|ranked| ranked == &(player.player_id, hand_rank.clone())));
+        }
+        for groups in winners.rankings.windows(2) {
+            assert!(groups[0][0].1 > groups[1][0].1);
+        }
+        let expected_tie_break = match winners.rankings.as_slice() {
+            [winners, runners_up, ..] => eval::tie_broken_by(&winners[0].1, &runners_up[0].1),
+            _ => None,
+        };
+        assert_eq!(winners.tie_broken_by, expected_tie_break);
+
+        // All-in on the flop, only the flop is revealed
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Winners {
+                table_id: 1,
+                flop_secret: Some(table.community_cards.flop.secret),
+                turn_secret: None,
+                river_secret: None,
+                players_secrets: players_secrets[..2].to_vec(),
+            },
+        )
+        .unwrap();
+        let winners: WinnersResponse = from_binary(&res).unwrap();
+        assert_eq!(winners.community_cards, table.community_cards.flop.cards);
+        assert_eq!(winners.rankings.iter().map(|group| group.len()).sum::<usize>(), 2);
+        let first_player = &table.players[0];
+        assert!(winners.rankings.iter().flatten().any(|ranked| ranked
+            == &(first_player.player_id, eval::best_hand(&first_player.hand, &table.community_cards.flop.cards, &DeckVariant::Standard))));
+
+        // the same reveal with its winners in a single query
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ShowdownWithWinners {
+                table_id: 1,
+                flop_secret: Some(table.community_cards.flop.secret),
+                turn_secret: None,
+                river_secret: None,
+                players_secrets: players_secrets[..2].to_vec(),
+            },
+        )
+        .unwrap();
+        let showdown_with_winners: ShowdownWithWinnersResponse = from_binary(&res).unwrap();
+        assert_eq!(showdown_with_winners.winners, winners.rankings[0]);
+        assert_eq!(showdown_with_winners.showdown.community_cards, Some(table.community_cards.flop.cards.clone()));
+        assert_eq!(showdown_with_winners.showdown.players_cards.len(), 2);
+        assert_eq!(
+            showdown_with_winners.showdown,
+            query_handlers::query_showdown(
+                deps.as_ref(),
+                1,
+                Some(table.community_cards.flop.secret),
+                None,
+                None,
+                players_secrets[..2].to_vec(),
+            )
+            .unwrap()
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Winners {
+                table_id: 1,
+                flop_secret: Some(table.community_cards.flop.secret + 1),
+                turn_secret: None,
+                river_secret: None,
+                players_secrets: players_secrets.clone(),
+            },
+        );
+        assert!(res.is_err());
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ShowdownWithWinners {
+                table_id: 1,
+                flop_secret: Some(table.community_cards.flop.secret + 1),
+                turn_secret: None,
+                river_secret: None,
+                players_secrets,
+            },
+        );
+        assert_eq!(
+            res.unwrap_err(),
+            StdError::generic_err(ContractError::SecretMismatch { street: "flop".to_string() }.to_string())
+        );
+    }
+
+    #[test]
+    fn test_end_hand() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let player2_id = Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: player1_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: player2_id,
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        start_game(deps.as_mut(), 1, players);
+        let table = load_table(&deps.storage, 1).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::EndHand {
+                table_id: 1,
+                showdown_player_ids: vec![],
+            },
+        );
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        assert!(load_table(&deps.storage, 1).is_some());
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::EndHand {
+                table_id: 1,
+                showdown_player_ids: vec![player2_id],
+            },
+        )
+        .unwrap();
+        assert!(load_table(&deps.storage, 1).is_none());
+
+        let response_attr = res.attributes.iter().find(|attr| attr.key == "response").unwrap();
+        match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::LastHand(hand_log) => {
+                assert_eq!(hand_log.showdown_players.len(), 1);
+                assert_eq!(hand_log.showdown_players[0].username, "player2");
+                assert_eq!(hand_log.started_at, mock_env().block.time);
+                assert_eq!(
+                    hand_log.showdown_players[0].hand,
+                    table.players[1].hand.iter().map(|card| card.to_string()).collect::<Vec<_>>()
+                );
+                assert_eq!(hand_log.community_cards.len(), 5);
+            }
+            _ => panic!("Expected LastHand response"),
+        }
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::EndHand {
+                table_id: 1,
+                showdown_player_ids: vec![],
+            },
+        );
+        assert_eq!(res.unwrap_err(), ContractError::TableNotFound { table_id: 1 });
+    }
+
+    #[test]
+    fn test_hand_chain() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let query_chain = |deps: Deps| -> HandChainResponse {
+            from_binary(&query(deps, mock_env(), QueryMsg::HandChain { table_id: 1 }).unwrap()).unwrap()
+        };
+        assert!(query_chain(deps.as_ref()).links.is_empty());
+
+        for hand_ref in 1..=3 {
+            if hand_ref > 1 {
+                retrieve_showdown(deps.as_mut(), 1);
+            }
+            execute_start_game(deps.as_mut(), StartGameArgs {
+                hand_ref,
+                ..start_game_args(1, players.clone())
+            })
+            .unwrap();
+        }
+
+        let chain = query_chain(deps.as_ref());
+        assert_eq!(chain.links.iter().map(|link| link.hand_ref).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(chain.links[0].prev_hand_hash, [0u8; 32]);
+        assert!(chain.verify());
+
+        // revealing cards doesn't change the hash of the dealt hand
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::AdvancePhase { table_id: 1 },
+        )
+        .unwrap();
+        assert!(query_chain(deps.as_ref()).verify());
+
+        // tampering with an archived hand breaks the link to the next one
+        let mut tampered = chain.clone();
+        tampered.links[1].hand_hash[0] ^= 1;
+        assert!(!tampered.verify());
+
+        // tampering with the stored cards no longer matches the last link
+        let mut table = load_table(&deps.storage, 1).unwrap();
+        table.players[0].hand.swap(0, 1);
+        save_table(&mut deps.storage, 1, &table).unwrap();
+        assert!(!query_chain(deps.as_ref()).verify());
+        table.players[0].hand.swap(0, 1);
+        save_table(&mut deps.storage, 1, &table).unwrap();
+
+        // the chain outlives the table
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::EndHand {
+                table_id: 1,
+                showdown_player_ids: vec![],
+            },
+        )
+        .unwrap();
+        let ended = query_chain(deps.as_ref());
+        assert_eq!(ended.links, chain.links);
+        assert_eq!(ended.current_hand_hash, None);
+        assert!(ended.verify());
+    }
+
+    #[test]
+    fn test_hand_history() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: player1_id,
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let mut dealt_boards = Vec::new();
+        for hand_ref in 1..=4 {
+            if hand_ref > 1 {
+                retrieve_showdown(deps.as_mut(), 1);
+            }
+            execute_start_game(deps.as_mut(), StartGameArgs {
+                hand_ref,
+                prev_hand_showdown_players: vec![player1_id],
+                ..start_game_args(1, players.clone())
+            })
+            .unwrap();
+            dealt_boards.push(load_table(&deps.storage, 1).unwrap().all_dealt_cards().board());
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::EndHand {
+                table_id: 1,
+                showdown_player_ids: vec![],
+            },
+        )
+        .unwrap();
+
+        let query_page = |limit: Option<u32>, start_after: Option<u32>| {
+            query(deps.as_ref(), mock_env(), QueryMsg::HandHistory { table_id: 1, limit, start_after, compress: false })
+                .map(|res| from_binary::<HandHistoryResponse>(&res).unwrap())
+        };
+        let query_history = |limit: Option<u32>, start_after: Option<u32>| query_page(limit, start_after).map(|page| page.hands);
+
+        let hands = query_history(None, None).unwrap();
+        assert_eq!(hands.iter().map(|hand| hand.hand_ref).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        for (hand, board) in hands.iter().zip(&dealt_boards) {
+            assert_eq!(hand.community_cards, board.iter().map(|card| card.to_string()).collect::<Vec<_>>());
+        }
+        assert_eq!(hands[0].showdown_players.len(), 1);
+        assert!(hands[3].showdown_players.is_empty());
+
+        // the cursor is the history index of the last hand of a page, not its hand_ref
+        assert_eq!(query_page(None, None).unwrap().next_start_after, None);
+        let first_page = query_page(Some(3), None).unwrap();
+        assert_eq!(first_page.hands, hands[..3]);
+        assert_eq!(first_page.next_start_after, Some(2));
+        let second_page = query_page(Some(3), first_page.next_start_after).unwrap();
+        assert_eq!(second_page.hands, hands[3..]);
+        assert_eq!(second_page.next_start_after, None);
+        assert_eq!(query_history(Some(1), Some(0)).unwrap(), hands[1..2]);
+        assert!(query_history(Some(3), Some(3)).unwrap().is_empty());
+        assert!(query_history(None, Some(42)).is_err());
+
+        // the compressed history decodes to the same hands
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::HandHistory { table_id: 1, limit: None, start_after: None, compress: true },
+        )
+        .unwrap();
+        let compressed = match from_binary(&res).unwrap() {
+            ResponsePayload::Compressed(compressed) => compressed,
+            other => panic!("expected a compressed payload, got {:?}", other),
+        };
+        match compressed.decode().unwrap() {
+            ResponsePayload::HandHistory(history) => {
+                assert_eq!(history.table_id, 1);
+                assert_eq!(history.hands, hands);
+            }
+            other => panic!("expected a hand history, got {:?}", other),
+        }
+
+        let other_table: HandHistoryResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::HandHistory { table_id: 2, limit: None, start_after: None, compress: false }).unwrap(),
+        )
+        .unwrap();
+        assert!(other_table.hands.is_empty());
+    }
+
+    #[test]
+    fn test_game_result() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let player1_id = Uuid::from_u128(1);
+        let player2_id = Uuid::from_u128(2);
+        let player3_id = Uuid::from_u128(3);
+        let players: Vec<StartGamePlayer> = [player1_id, player2_id, player3_id]
+            .iter()
+            .map(|player_id| StartGamePlayer {
+                username: player_id.to_string(),
+                player_id: *player_id,
+                public_key: player_id.to_string(),
+            })
+            .collect();
+
+        let query_result = |deps: Deps, hand_ref: u32| {
+            query(deps, mock_env(), QueryMsg::GameResult { table_id: 1, hand_ref })
+                .map(|res| from_binary::<GameResult>(&res).unwrap())
+        };
+
+        // Hand 1 is finalized by the next StartGame
+        start_game(deps.as_mut(), 1, players.clone());
+        let hand1 = load_table(&deps.storage, 1).unwrap();
+        assert!(query_result(deps.as_ref(), 1).is_err());
+
+        retrieve_showdown(deps.as_mut(), 1);
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            hand_ref: 2,
+            prev_hand_showdown_players: vec![player1_id, player2_id],
+            ..start_game_args(1, players.clone())
+        })
+        .unwrap();
+
+        let result = query_result(deps.as_ref(), 1).unwrap();
+        let board = hand1.all_dealt_cards().board();
+        let showdown_hands = vec![
+            (player1_id, hand1.players[0].hand.clone()),
+            (player2_id, hand1.players[1].hand.clone()),
+        ];
+        let rankings = eval::rank_hands(&showdown_hands, &board, &DeckVariant::Standard);
+        assert_eq!(result.table_id, 1);
+        assert_eq!(result.hand_ref, 1);
+        assert_eq!(result.community_cards, board);
+        assert_eq!(result.showdown_hands, showdown_hands);
+        assert_eq!(result.winners, rankings[0].iter().map(|(player_id, _)| *player_id).collect::<Vec<_>>());
+        assert_eq!(result.rankings, rankings);
+
+        // Hand 2 ends on a split pot: both players play the board
+        let table = PokerTableBuilder::new()
+            .hand_ref(2)
+            .player(PlayerBuilder::new(player1_id).hand(vec![Card::new(1, 2), Card::new(2, 3)]).build())
+            .player(PlayerBuilder::new(player2_id).hand(vec![Card::new(2, 2), Card::new(3, 3)]).build())
+            .player(PlayerBuilder::new(player3_id).hand(vec![Card::new(1, 4), Card::new(3, 5)]).build())
+            .board(vec![Card::new(0, 1), Card::new(0, 13), Card::new(0, 12), Card::new(0, 11), Card::new(0, 10)])
+            .build();
+        save_table(&mut deps.storage, 1, &table).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::EndHand {
+                table_id: 1,
+                showdown_player_ids: vec![player1_id, player2_id, player3_id],
+            },
+        )
+        .unwrap();
+
+        let result = query_result(deps.as_ref(), 2).unwrap();
+        assert_eq!(result.showdown_hands.len(), 3);
+        assert_eq!(result.rankings.len(), 1);
+        assert_eq!(result.winners, vec![player1_id, player2_id, player3_id]);
+        assert!(query_result(deps.as_ref(), 3).is_err());
+        let history = hand_history(1);
+        let hand_log = history.get_at(&deps.storage, history.get_len(&deps.storage).unwrap() - 1).unwrap();
+        assert_eq!(hand_log.winners, vec![(player1_id, 34), (player2_id, 33), (player3_id, 33)]);
+
+        // A saved result is never overwritten, its hand_ref can't be dealt again
+        assert_eq!(
+            save_game_result(&mut deps.storage, &GameResult { winners: vec![], ..result.clone() }).unwrap_err(),
+            ContractError::HandRefAlreadyUsed { table_id: 1, hand_ref: 2 }
+        );
+        assert_eq!(query_result(deps.as_ref(), 2).unwrap(), result);
+        assert_eq!(
+            execute_start_game(deps.as_mut(), start_game_args(1, players.clone())).unwrap_err(),
+            ContractError::HandRefAlreadyUsed { table_id: 1, hand_ref: 1 }
+        );
+
+        // Hand 3 is a two-way tie on ace-king-queen-jack, player3 loses with nine-eight
+        let table = PokerTableBuilder::new()
+            .hand_ref(3)
+            .player(PlayerBuilder::new(player1_id).hand(vec![Card::new(1, 12), Card::new(2, 11)]).build())
+            .player(PlayerBuilder::new(player2_id).hand(vec![Card::new(2, 12), Card::new(3, 11)]).build())
+            .player(PlayerBuilder::new(player3_id).hand(vec![Card::new(1, 9), Card::new(3, 8)]).build())
+            .board(vec![Card::new(0, 1), Card::new(1, 13), Card::new(2, 7), Card::new(3, 4), Card::new(0, 2)])
+            .build();
+        save_table(&mut deps.storage, 1, &table).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::EndHand {
+                table_id: 1,
+                showdown_player_ids: vec![player1_id, player2_id, player3_id],
+            },
+        )
+        .unwrap();
+        let response_attr = res.attributes.iter().find(|attr| attr.key == RESPONSE_KEY).unwrap();
+        match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::LastHand(hand_log) => {
+                assert_eq!(hand_log.winners, vec![(player1_id, 50), (player2_id, 50)])
+            }
+            _ => panic!("Expected LastHand response"),
+        }
+        assert_eq!(query_result(deps.as_ref(), 3).unwrap().winners, vec![player1_id, player2_id]);
+
+        // Hand 4: player3 folded a set of aces, the pot goes to the best hand still in
+        let table = PokerTableBuilder::new()
+            .hand_ref(4)
+            .player(PlayerBuilder::new(player1_id).hand(vec![Card::new(1, 12), Card::new(2, 11)]).build())
+            .player(PlayerBuilder::new(player2_id).hand(vec![Card::new(1, 9), Card::new(3, 8)]).build())
+            .player(PlayerBuilder::new(player3_id).hand(vec![Card::new(2, 1), Card::new(3, 1)]).folded().build())
+            .board(vec![Card::new(0, 1), Card::new(1, 13), Card::new(2, 7), Card::new(3, 4), Card::new(0, 2)])
+            .build();
+        save_table(&mut deps.storage, 1, &table).unwrap();
+
+        let end_hand = |deps: DepsMut, showdown_player_ids: Vec<Uuid>| {
+            execute(
+                deps,
+                mock_env(),
+                mock_info("creator", &[]),
+                ExecuteMsg::EndHand { table_id: 1, showdown_player_ids },
+            )
+        };
+        assert_eq!(
+            end_hand(deps.as_mut(), vec![player1_id, player2_id, player3_id]).unwrap_err(),
+            ContractError::PlayerFolded { table_id: 1, player: player3_id.to_string() }
+        );
+        let res = end_hand(deps.as_mut(), vec![player1_id, player2_id]).unwrap();
+        let response_attr = res.attributes.iter().find(|attr| attr.key == RESPONSE_KEY).unwrap();
+        match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::LastHand(hand_log) => assert_eq!(hand_log.winners, vec![(player1_id, 100)]),
+            _ => panic!("Expected LastHand response"),
+        }
+        let result = query_result(deps.as_ref(), 4).unwrap();
+        assert_eq!(result.winners, vec![player1_id]);
+        assert!(result.rankings.iter().flatten().all(|(player_id, _)| *player_id != player3_id));
+    }
+
+    #[test]
+    fn test_counter_range() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let start_game = |deps: DepsMut, hand_ref: u32| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                ..start_game_args(1, start_game_players(2))
+            })
+            .unwrap();
+        };
+
+        // the shuffle seed, one secret per phase and per player, then all shares but the last one of each phase
+        let player_count = 2;
+        let expected_draws = (1 + COMMUNITY_CARD_PHASES + player_count + COMMUNITY_CARD_PHASES * (player_count - 1)) as u128;
+
+        let mut previous_end = COUNTER_KEY.load(&deps.storage).unwrap();
+        for hand_ref in 1..=2 {
+            start_game(deps.as_mut(), hand_ref);
+            let table = load_table(&deps.storage, 1).unwrap();
+            assert_eq!(table.counter_start, previous_end);
+            assert_eq!(table.counter_end - table.counter_start, expected_draws);
+            assert_eq!(table.counter_end, COUNTER_KEY.load(&deps.storage).unwrap());
+
+            retrieve_showdown(deps.as_mut(), 1);
+            let response: VerifyShuffleResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::VerifyShuffle { table_id: 1 }).unwrap())
+                    .unwrap();
+            assert_eq!(response.counter_start, table.counter_start.to_string());
+            assert_eq!(response.counter_end, table.counter_end.to_string());
+            previous_end = table.counter_end;
+        }
+    }
+
+    #[test]
+    fn test_verify_shuffle() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let players = start_game_players(3);
+
+        start_game(deps.as_mut(), 1, players);
+
+        let verify_shuffle = |deps: Deps| query(deps, mock_env(), QueryMsg::VerifyShuffle { table_id: 1 });
+        assert!(verify_shuffle(deps.as_ref()).is_err());
+
+        retrieve_showdown(deps.as_mut(), 1);
+        let response: VerifyShuffleResponse = from_binary(&verify_shuffle(deps.as_ref()).unwrap()).unwrap();
+        assert_eq!(response.max_retries, SHUFFLE_MAX_RETRIES);
+
+        // Replaying the shuffle offline gives back the deck
+        let mut replayed = Deck::new();
+        helpers::shuffle_deck(&mut replayed, response.seed.parse().unwrap(), response.max_retries);
+        assert_eq!(replayed.to_bytes(), response.deck.to_vec());
+
+        // Cards are dealt from the end: hole cards, then a burned card before each street
+        let table = load_table(&deps.storage, 1).unwrap();
+        let dealt = table.all_dealt_cards();
+        let mut expected: Vec<Card> = dealt.players.iter().flat_map(|(_, hand)| hand.clone()).collect();
+        for (burned, street) in dealt.burned.iter().zip([dealt.flop.clone(), vec![dealt.turn.unwrap()], vec![dealt.river.unwrap()]]) {
+            expected.push(burned.clone());
+            expected.extend(street);
+        }
+        let deck = Deck::try_from_bytes(&response.deck, &table.deck_variant).unwrap();
+        assert_eq!(deck.cards.iter().rev().take(expected.len()).cloned().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_start_game_table_intent() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let start_game = |deps: DepsMut, hand_ref: u32, table_intent: TableIntent| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                table_intent,
+                ..start_game_args(1, players.clone())
+            })
+        };
+
+        // Absent table
+        assert_eq!(
+            start_game(deps.as_mut(), 1, TableIntent::Continue).unwrap_err(),
+            ContractError::TableNotFound { table_id: 1 }
+        );
+        assert!(load_table(&deps.storage, 1).is_none());
+        start_game(deps.as_mut(), 1, TableIntent::New).unwrap();
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hand_ref, 1);
+
+        // Present table
+        assert_eq!(
+            start_game(deps.as_mut(), 2, TableIntent::New).unwrap_err(),
+            ContractError::TableAlreadyExists { table_id: 1 }
+        );
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hand_ref, 1);
+        retrieve_showdown(deps.as_mut(), 1);
+        start_game(deps.as_mut(), 2, TableIntent::Continue).unwrap();
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hand_ref, 2);
+
+        // Any creates or overwrites
+        retrieve_showdown(deps.as_mut(), 1);
+        start_game(deps.as_mut(), 3, TableIntent::Any).unwrap();
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hand_ref, 3);
+        delete_table(&mut deps.storage, 1).unwrap();
+        start_game(deps.as_mut(), 4, TableIntent::Any).unwrap();
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hand_ref, 4);
+
+        // Deleted table ids aren't new
+        delete_table(&mut deps.storage, 1).unwrap();
+        assert_eq!(
+            start_game(deps.as_mut(), 5, TableIntent::New).unwrap_err(),
+            ContractError::TableIdReserved { table_id: 1 }
+        );
+        assert!(load_table(&deps.storage, 1).is_none());
+    }
+
+    #[test]
+    fn test_card_strings_in_responses() {
+        let ace = Card::new(3, 1);
+        let ten = Card::new(2, 10);
+        let player_id = Uuid::from_u128(1);
+
+        let response = ShowdownResponse {
+            table_id: 1,
+            hand_ref: 2,
+            players_cards: vec![(player_id, vec![ace.clone(), ten.clone()])],
+            community_cards: None,
+            burned: Some(vec![ten.clone()]),
+            undealt: None,
+            reveal_order: vec![player_id],
+            from_state: GameState::Turn,
+            board_len: 0,
+        };
+        let json = serde_json_wasm::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"table_id":1,"hand_ref":2,"players_cards":[["{}",["♠A","♥10"]]],"community_cards":null,"burned":["♥10"],"undealt":null,"reveal_order":["{}"],"from_state":"turn","board_len":0}}"#,
+                player_id, player_id
+            )
+        );
+        assert_eq!(serde_json_wasm::from_str::<ShowdownResponse>(&json).unwrap(), response);
+
+        // storage keeps the packed byte
+        assert_eq!(serde_json_wasm::to_string(&ace).unwrap(), ((3 << 4) | 1).to_string());
+
+        let invalid = r#"{"table_id":1,"hand_ref":2,"cards":["♠1"]}"#;
+        assert!(serde_json_wasm::from_str::<UnknownCardsResponse>(invalid).is_err());
+    }
+
+    #[test]
+    fn test_duplicate_hole_card() {
+        let player_cards = vec![
+            ("key1".to_string(), vec![Card::new(0, 1), Card::new(1, 1)]),
+            ("key2".to_string(), vec![Card::new(2, 1), Card::new(3, 1)]),
+        ];
+        assert!(execute_handlers::validate_distinct_hole_cards(&player_cards).is_ok());
+
+        let player_cards = vec![
+            ("key1".to_string(), vec![Card::new(0, 1), Card::new(1, 1)]),
+            ("key2".to_string(), vec![Card::new(2, 1), Card::new(3, 1)]),
+            ("key3".to_string(), vec![Card::new(3, 2), Card::new(1, 1)]),
+        ];
+        assert_eq!(
+            execute_handlers::validate_distinct_hole_cards(&player_cards).unwrap_err(),
+            ContractError::DuplicateHoleCard {
+                player: "key3".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_share_index() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        start_game(deps.as_mut(), 1, start_game_players(3));
+
+        let share_indexes: Vec<u32> = (1..=3)
+            .map(|i| query_player_private_data(deps.as_ref(), 1, format!("key{}", i)).unwrap().share_index)
+            .collect();
+        assert_eq!(share_indexes, vec![1, 2, 3]);
+        assert_eq!(share_indexes.iter().collect::<HashSet<_>>().len(), share_indexes.len());
+
+        // stable across queries
+        for (i, share_index) in share_indexes.iter().enumerate() {
+            let player_data = query_player_private_data(deps.as_ref(), 1, format!("key{}", i + 1)).unwrap();
+            assert_eq!(&player_data.share_index, share_index);
+        }
+
+        // the shares gathered by share_index reconstruct each community secret
+        let mut player_data: Vec<PlayerDataResponse> =
+            (1..=3).map(|i| query_player_private_data(deps.as_ref(), 1, format!("key{}", i)).unwrap()).collect();
+        player_data.sort_by_key(|data| data.share_index);
+        let community_cards = load_table(&deps.storage, 1).unwrap().community_cards;
+        let phase_shares = |share: fn(&PlayerDataResponse) -> &String| {
+            player_data.iter().map(|data| share(data).parse::<u128>().unwrap()).collect::<Vec<_>>()
+        };
+        assert_eq!(reconstruct_additive(&phase_shares(|data| &data.flop_secret_share)), community_cards.flop.secret);
+        assert_eq!(
+            reconstruct_additive(&phase_shares(|data| &data.turn_secret_share)),
+            community_cards.turn.unwrap().secret
+        );
+        assert_eq!(
+            reconstruct_additive(&phase_shares(|data| &data.river_secret_share)),
+            community_cards.river.unwrap().secret
+        );
+
+        // threshold shares are points at the share_index, any two of them in any order give the secret
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            sharing_scheme: SharingScheme::Threshold { threshold: 2 },
+            ..start_game_args(2, start_game_players(3))
+        })
+        .unwrap();
+        let point = |public_key: &str| {
+            let data = query_player_private_data(deps.as_ref(), 2, public_key.to_string()).unwrap();
+            (data.share_index as u128, data.flop_secret_share.parse::<u128>().unwrap())
+        };
+        let flop_secret = load_table(&deps.storage, 2).unwrap().community_cards.flop.secret;
+        assert_eq!(helpers::shamir::reconstruct(&[point("key3"), point("key1")]), flop_secret);
+        assert_eq!(helpers::shamir::reconstruct(&[point("key2"), point("key3")]), flop_secret);
+    }
+
+    #[test]
+    fn test_unknown_cards() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let players = start_game_players(3);
+
+        start_game(deps.as_mut(), 1, players);
+        let table = load_table(&deps.storage, 1).unwrap();
+
+        let unknown_cards = |deps: Deps| query_handlers::query_unknown_cards(deps, 1, "key1".to_string()).unwrap().cards;
+
+        let unknown = unknown_cards(deps.as_ref());
+        assert_eq!(unknown.len(), 52 - 2);
+        assert!(table.players[0].hand.iter().all(|card| !unknown.contains(card)));
+        assert!(table.players[1].hand.iter().all(|card| unknown.contains(card)));
+        assert!(table.community_cards.flop.cards.iter().all(|card| unknown.contains(card)));
+
+        for known in [2 + 3, 2 + 4] {
+            execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AdvancePhase { table_id: 1 }).unwrap();
+            assert_eq!(unknown_cards(deps.as_ref()).len(), 52 - known);
+        }
+
+        // the river is revealed by the showdown, the other hands are not
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::Showdown {
+                table_id: 1,
+                game_state: GameState::Turn,
+                showdown_player_ids: vec![],
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+        let unknown = unknown_cards(deps.as_ref());
+        assert_eq!(unknown.len(), 52 - 2 - 5);
+        assert!(table.all_dealt_cards().board().iter().all(|card| !unknown.contains(card)));
+
+        assert!(query_handlers::query_unknown_cards(deps.as_ref(), 1, "key4".to_string()).is_err());
+        assert!(query_handlers::query_unknown_cards(deps.as_ref(), 2, "key1".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_permit_community_cards() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let players = start_game_players(2);
+        start_game(deps.as_mut(), 1, players);
+        let table = load_table(&deps.storage, 1).unwrap();
+
+        let community_cards = |deps: Deps, game_state: GameState, pub_key: &str| {
+            query_handlers::query_permit_community_cards(deps, 1, game_state, pub_key.to_string())
+        };
+
+        // streets to come stay hidden from the seated players
+        assert_eq!(
+            community_cards(deps.as_ref(), GameState::Flop, "key1").unwrap_err(),
+            StdError::generic_err("Phase not revealed yet")
+        );
+
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AdvancePhase { table_id: 1 }).unwrap();
+        let response = community_cards(deps.as_ref(), GameState::Flop, "key2").unwrap();
+        assert_eq!(response.community_cards, table.community_cards.flop.cards);
+        assert!(community_cards(deps.as_ref(), GameState::Turn, "key1").is_err());
+        assert_eq!(
+            community_cards(deps.as_ref(), GameState::Flop, "key3").unwrap_err(),
+            StdError::generic_err("No player found")
+        );
+        assert!(community_cards(deps.as_ref(), GameState::PreFlop, "key1").is_err());
+
+        // the whole board is public once the showdown is retrieved
+        retrieve_showdown(deps.as_mut(), 1);
+        let response = community_cards(deps.as_ref(), GameState::River, "key1").unwrap();
+        assert_eq!(response.community_cards, vec![table.community_cards.river.unwrap().card]);
+    }
+
+    // a table can only be dealt a new hand once the showdown of the current one is retrieved
+    #[test]
+    fn test_bulk_start_game() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let game = |table_id: u32, hand_ref: u32| StartGameArgs {
+            hand_ref,
+            ..start_game_args(table_id, start_game_players(2))
+        };
+        let bulk_start_game = |deps: DepsMut, games: Vec<StartGameArgs>| {
+            execute(deps, mock_env(), info.clone(), ExecuteMsg::BulkStartGame { games })
+        };
+
+        // StartGame takes the fields of a game directly under its tag, with the same defaults
+        let start_game_json = format!(
+            r#"{{"start_game":{{"table_id":1,"hand_ref":2,"players":{},"prev_hand_showdown_players":[]}}}}"#,
+            serde_json_wasm::to_string(&game(1, 2).players).unwrap()
+        );
+        assert_eq!(serde_json_wasm::from_str::<ExecuteMsg>(&start_game_json).unwrap(), ExecuteMsg::StartGame(game(1, 2)));
+
+        let counter = COUNTER_KEY.load(&deps.storage).unwrap();
+        let res = bulk_start_game(deps.as_mut(), vec![game(1, 1), game(2, 1), game(3, 1)]).unwrap();
+        let response_attr = res.attributes.iter().find(|attr| attr.key == "response").unwrap();
+        match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::BulkStartGame(bulk_response) => {
+                let table_ids: Vec<u32> = bulk_response.games.iter().map(|game| game.table_id).collect();
+                assert_eq!(table_ids, vec![1, 2, 3]);
+                assert!(bulk_response.games.iter().all(|game| game.players == vec!["player1", "player2"]));
+            }
+            _ => panic!("Expected BulkStartGame response"),
+        }
+
+        // the games draw one after the other from a single counter
+        let tables: Vec<PokerTable> = (1..=3).map(|table_id| load_table(&deps.storage, table_id).unwrap()).collect();
+        assert_eq!(tables[0].counter_start, counter);
+        assert_eq!(tables[1].counter_start, tables[0].counter_end);
+        assert_eq!(tables[2].counter_start, tables[1].counter_end);
+        assert_eq!(COUNTER_KEY.load(&deps.storage).unwrap(), tables[2].counter_end);
+        assert_ne!(tables[0].players[0].hand, tables[1].players[0].hand);
+
+        // replaced hands emit their log under the table id
+        retrieve_showdown(deps.as_mut(), 1);
+        let res = bulk_start_game(deps.as_mut(), vec![game(1, 2)]).unwrap();
+        assert!(res.attributes.iter().any(|attr| attr.key == "previous_hand_log_1"));
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hand_ref, 2);
+
+        assert_eq!(
+            bulk_start_game(deps.as_mut(), vec![game(4, 1), game(2, 2)]).unwrap_err(),
+            ContractError::HandInProgress { table_id: 2 }
+        );
+        assert_eq!(
+            bulk_start_game(deps.as_mut(), vec![]).unwrap_err(),
+            ContractError::InvalidBatchSize { size: 0, max: MAX_BULK_START_GAMES }
+        );
+        let games = (10..10 + MAX_BULK_START_GAMES as u32 + 1).map(|table_id| game(table_id, 1)).collect();
+        assert_eq!(
+            bulk_start_game(deps.as_mut(), games).unwrap_err(),
+            ContractError::InvalidBatchSize { size: MAX_BULK_START_GAMES + 1, max: MAX_BULK_START_GAMES }
+        );
+    }
+
+    fn retrieve_showdown(deps: DepsMut, table_id: u32) {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Showdown {
+                table_id,
+                game_state: GameState::River,
+                showdown_player_ids: vec![],
+                expected_hand_ref: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_start_game_hand_in_progress() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg::default();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let start_game = |deps: DepsMut, hand_ref: u32| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                ..start_game_args(1, players.clone())
+            })
+        };
+
+        start_game(deps.as_mut(), 1).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+
+        // Showdown not retrieved, the hand is kept as is
+        assert_eq!(start_game(deps.as_mut(), 2).unwrap_err(), ContractError::HandInProgress { table_id: 1 });
+        assert_eq!(load_table(&deps.storage, 1).unwrap(), table);
+
+        // Showdown retrieved, the table can be dealt again
+        retrieve_showdown(deps.as_mut(), 1);
+        start_game(deps.as_mut(), 2).unwrap();
+        assert_eq!(load_table(&deps.storage, 1).unwrap().hand_ref, 2);
+    }
+
+    #[test]
+    fn test_distinct_secrets_regeneration() {
+        let mut draws = vec![7u128, 3, 7, 3, 3, 11, 5].into_iter();
+        let mut calls = 0;
+        let secrets = helpers::generate_distinct_secrets(4, || {
+            calls += 1;
+            Ok(draws.next().unwrap())
+        })
+        .unwrap();
+
+        assert_eq!(secrets, vec![7, 3, 11, 5]);
+        assert_eq!(calls, 7);
+
+        let res = helpers::generate_distinct_secrets(2, || Err(StdError::generic_err("rng failure").into()));
+        assert!(res.is_err());
     }
-}
 
-#[cfg(test)]
-mod complete_tests {
-    use crate::contract::query_handlers::query_player_private_data;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, from_binary};
-    use super::*;
-    use std::time::Instant;
-    use std::collections::HashMap;
+    #[test]
+    fn test_counter_exhausted() {
+        let mut counter = u128::MAX - 1;
+        assert!(helpers::generate_random_number(&mock_env(), &mut counter).is_ok());
+        assert_eq!(counter, u128::MAX);
+        assert_eq!(
+            helpers::generate_random_number(&mock_env(), &mut counter).unwrap_err(),
+            ContractError::CounterExhausted {}
+        );
+        assert_eq!(counter, u128::MAX);
+
+        // a seed a few draws short of u128::MAX is kept as is, the first hand runs out of draws
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let mut env = mock_env();
+        env.block.random = Some(Binary::from((u128::MAX - 3).to_le_bytes().to_vec()));
+        instantiate(deps.as_mut(), env, info, InstantiateMsg::default()).unwrap();
+        assert_eq!(COUNTER_KEY.load(&deps.storage).unwrap(), u128::MAX - 3);
+
+        let players = start_game_players(2);
+        let res = execute_start_game(deps.as_mut(), start_game_args(1, players));
+        assert_eq!(res.unwrap_err(), ContractError::CounterExhausted {});
+        assert!(load_table(&deps.storage, 1).is_none());
+        assert_eq!(COUNTER_KEY.load(&deps.storage).unwrap(), u128::MAX - 3);
+    }
 
     #[test]
-    fn test_instantiate() {
+    fn test_random_number_uses_128_bits() {
+        let mut counter = 0u128;
+        let draws: Vec<u128> = (0..8)
+            .map(|_| helpers::generate_random_number(&mock_env(), &mut counter).unwrap())
+            .collect();
+
+        assert_eq!(counter, 8);
+        assert!(draws.iter().any(|draw| *draw > u64::MAX as u128));
+    }
+
+    #[test]
+    fn test_table_state() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let table_state = |deps: Deps, table_id: u32| {
+            query(deps, mock_env(), QueryMsg::TableState { table_id })
+                .and_then(|res| from_binary::<TableStateResponse>(&res))
+        };
+        assert_eq!(
+            table_state(deps.as_ref(), 1).unwrap_err(),
+            StdError::generic_err(ContractError::TableNotFound { table_id: 1 }.to_string())
+        );
+
+        let players = start_game_players(2);
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            hand_ref: 7,
+            ..start_game_args(1, players)
+        })
+        .unwrap();
+
+        let state = table_state(deps.as_ref(), 1).unwrap();
+        assert_eq!(state.hand_ref, 7);
+        assert_eq!(
+            state.players,
+            (1..=2)
+                .map(|i| TableStatePlayer { username: format!("player{}", i), player_id: Uuid::from_u128(i) })
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(state.current_phase, GameState::PreFlop);
+        assert_eq!(state.flop_retrieved_at, None);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::CommunityCards { table_id: 1, game_state: GameState::Flop, expected_hand_ref: None },
+        )
+        .unwrap();
+        let state = table_state(deps.as_ref(), 1).unwrap();
+        assert_eq!(state.started_at, mock_env().block.time);
+        assert_eq!(state.flop_retrieved_at, Some(mock_env().block.time));
+        assert_eq!(state.turn_retrieved_at, None);
+        assert_eq!(state.showdown_retrieved_at, None);
+
+        // nothing in the response can reveal a card or a secret
+        let raw = query(deps.as_ref(), mock_env(), QueryMsg::TableState { table_id: 1 }).unwrap();
+        let raw = String::from_utf8(raw.to_vec()).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert!(!raw.contains("secret") && !raw.contains("hand\""));
+        assert!(!raw.contains(&table.players[0].hand_secret.to_string()));
+    }
+
+    #[test]
+    fn test_all_community_cards() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        start_game(deps.as_mut(), 1, start_game_players(2));
+        let community_cards = load_table(&deps.storage, 1).unwrap().community_cards;
+        let (turn, river) = (community_cards.turn.unwrap(), community_cards.river.unwrap());
+
+        let all_community_cards = |deps: Deps, flop_secret, turn_secret, river_secret| {
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::AllCommunityCards { table_id: 1, flop_secret, turn_secret, river_secret },
+            )
+            .and_then(|res| from_binary::<AllCommunityCardsResponse>(&res))
+        };
+
+        let response =
+            all_community_cards(deps.as_ref(), Some(community_cards.flop.secret), Some(turn.secret), Some(river.secret))
+                .unwrap();
+        assert_eq!(response.flop, Some(community_cards.flop.cards.clone()));
+        assert_eq!(response.turn, Some(turn.card.clone()));
+        assert_eq!(response.river, Some(river.card.clone()));
+
+        // streets without a secret are skipped
+        let response = all_community_cards(deps.as_ref(), Some(community_cards.flop.secret), None, None).unwrap();
+        assert_eq!(response.flop, Some(community_cards.flop.cards));
+        assert_eq!((response.turn, response.river), (None, None));
+
+        // a mismatched secret names its street
+        assert_eq!(
+            all_community_cards(deps.as_ref(), None, Some(river.secret), Some(river.secret)).unwrap_err(),
+            StdError::generic_err(ContractError::SecretMismatch { street: "turn".to_string() }.to_string())
+        );
+
+        // missing secrets can be omitted from the message
+        let response: AllCommunityCardsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                from_binary(&Binary::from(br#"{"all_community_cards":{"table_id":1}}"#.to_vec())).unwrap(),
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!((response.flop, response.turn, response.river), (None, None, None));
+    }
+
+    #[test]
+    fn test_tables() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let tables = |deps: Deps, start_after: Option<u32>, limit: Option<u32>| -> Vec<(u32, u32, u32)> {
+            let res = query(deps, mock_env(), QueryMsg::Tables { start_after, limit }).unwrap();
+            from_binary::<TablesResponse>(&res)
+                .unwrap()
+                .tables
+                .into_iter()
+                .map(|table| (table.table_id, table.hand_ref, table.player_count))
+                .collect()
+        };
+        assert!(tables(deps.as_ref(), None, None).is_empty());
+
+        for (table_id, player_count) in [(7u32, 2u128), (3, 3), (5, 2)] {
+            let players = start_game_players(player_count);
+            execute_start_game(deps.as_mut(), StartGameArgs {
+                hand_ref: table_id * 10,
+                ..start_game_args(table_id, players)
+            })
+            .unwrap();
+        }
+
+        assert_eq!(tables(deps.as_ref(), None, None), vec![(3, 30, 3), (5, 50, 2), (7, 70, 2)]);
+        assert_eq!(tables(deps.as_ref(), None, Some(2)), vec![(3, 30, 3), (5, 50, 2)]);
+        assert_eq!(tables(deps.as_ref(), Some(5), Some(2)), vec![(7, 70, 2)]);
+        assert_eq!(tables(deps.as_ref(), Some(4), None), vec![(5, 50, 2), (7, 70, 2)]);
+        assert!(tables(deps.as_ref(), Some(7), None).is_empty());
+
+        // updating a table doesn't index it twice, ending a hand removes it
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AdvancePhase { table_id: 5 }).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::EndHand { table_id: 5, showdown_player_ids: vec![] },
+        )
+        .unwrap();
+        assert_eq!(TABLE_IDS.load(&deps.storage).unwrap(), vec![3, 7]);
+        assert_eq!(tables(deps.as_ref(), None, None), vec![(3, 30, 3), (7, 70, 2)]);
+
+        // a table missing from the index, as saved before it existed, is only indexed by its next StartGame
+        TABLE_IDS.save(&mut deps.storage, &vec![3]).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), ExecuteMsg::AdvancePhase { table_id: 7 }).unwrap();
+        retrieve_showdown(deps.as_mut(), 7);
+        assert_eq!(TABLE_IDS.load(&deps.storage).unwrap(), vec![3]);
+        start_game(deps.as_mut(), 7, start_game_players(2));
+        assert_eq!(TABLE_IDS.load(&deps.storage).unwrap(), vec![3, 7]);
+    }
+
+    #[test]
+    fn test_next_table_id() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let next_table_id = |deps: Deps| -> u32 {
+            from_binary::<NextTableIdResponse>(&query(deps, mock_env(), QueryMsg::NextTableId {}).unwrap())
+                .unwrap()
+                .table_id
+        };
+        let start_game = |deps: DepsMut, table_id: u32, table_intent: TableIntent| {
+            execute_start_game(deps, StartGameArgs {
+                table_intent,
+                ..start_game_args(table_id, start_game_players(2))
+            })
+        };
+        assert_eq!(next_table_id(deps.as_ref()), 0);
+
+        // querying doesn't reserve the id
+        assert_eq!(next_table_id(deps.as_ref()), 0);
+        start_game(deps.as_mut(), 0, TableIntent::New).unwrap();
+        assert_eq!(next_table_id(deps.as_ref()), 1);
+
+        // explicit ids move the counter past them, lower ones don't move it back
+        start_game(deps.as_mut(), 8, TableIntent::Any).unwrap();
+        assert_eq!(next_table_id(deps.as_ref()), 9);
+        start_game(deps.as_mut(), 4, TableIntent::Any).unwrap();
+        assert_eq!(next_table_id(deps.as_ref()), 9);
+
+        // an id allocated to two backends is only started once
+        let table_id = next_table_id(deps.as_ref());
+        start_game(deps.as_mut(), table_id, TableIntent::New).unwrap();
+        assert_eq!(
+            start_game(deps.as_mut(), table_id, TableIntent::New).unwrap_err(),
+            ContractError::TableAlreadyExists { table_id }
+        );
+
+        // deleting a table doesn't free its id
+        delete_table(&mut deps.storage, table_id).unwrap();
+        assert_eq!(next_table_id(deps.as_ref()), table_id + 1);
+        assert_eq!(
+            start_game(deps.as_mut(), table_id, TableIntent::New).unwrap_err(),
+            ContractError::TableIdReserved { table_id }
+        );
+        delete_table(&mut deps.storage, 4).unwrap();
+        assert_eq!(
+            start_game(deps.as_mut(), 4, TableIntent::New).unwrap_err(),
+            ContractError::TableIdReserved { table_id: 4 }
+        );
+        start_game(deps.as_mut(), table_id + 1, TableIntent::New).unwrap();
+
+        // ids skipped by a higher explicit id were never used and stay new
+        start_game(deps.as_mut(), 2, TableIntent::New).unwrap();
+        assert_eq!(next_table_id(deps.as_ref()), table_id + 2);
+
+        // contracts without the counter resume after the highest stored table
+        NEXT_TABLE_ID.remove(&mut deps.storage);
+        assert_eq!(next_table_id(deps.as_ref()), table_id + 2);
+
+        // the last id can be started, after which no id is left to allocate
+        start_game(deps.as_mut(), u32::MAX - 1, TableIntent::New).unwrap();
+        assert_eq!(next_table_id(deps.as_ref()), u32::MAX);
+        start_game(deps.as_mut(), u32::MAX, TableIntent::New).unwrap();
+        assert_eq!(
+            query(deps.as_ref(), mock_env(), QueryMsg::NextTableId {}).unwrap_err(),
+            StdError::generic_err(ContractError::TableIdsExhausted {}.to_string())
+        );
+    }
+
+    #[test]
+    fn test_community_cards_attributes() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            hand_ref: 9,
+            ..start_game_args(4, start_game_players(2))
+        })
+        .unwrap();
+        // the turn pairs the flop
+        let table = PokerTableBuilder::new()
+            .hand_ref(9)
+            .board(vec![Card::new(0, 2), Card::new(0, 3), Card::new(0, 4), Card::new(1, 4), Card::new(2, 9)])
+            .build();
+        save_table(&mut deps.storage, 4, &table).unwrap();
+
+        let attributes = |res: Response| -> Vec<(String, String)> {
+            res.attributes
+                .into_iter()
+                .filter(|attribute| attribute.key != RESPONSE_KEY)
+                .map(|attribute| (attribute.key, attribute.value))
+                .collect()
+        };
+        let expected = |game_state: &str, card_count: &str, board_paired: &str| -> Vec<(String, String)> {
+            [
+                ("table_id", "4"),
+                ("hand_ref", "9"),
+                ("game_state", game_state),
+                ("card_count", card_count),
+                ("board_paired", board_paired),
+            ]
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect()
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CommunityCards { table_id: 4, game_state: GameState::Flop, expected_hand_ref: None },
+        )
+        .unwrap();
+        // the JSON response is kept for the current clients
+        assert!(res.attributes.iter().any(|attribute| attribute.key == RESPONSE_KEY));
+        assert_eq!(attributes(res), expected("flop", "3", "false"));
+
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AdvancePhase { table_id: 4 }).unwrap();
+        assert_eq!(attributes(res), expected("turn", "1", "true"));
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+        assert_eq!(CONTRACT_VERSION_KEY.load(&deps.storage).unwrap(), CONTRACT_VERSION);
+        let version = |deps: Deps| -> VersionResponse {
+            from_binary(&query(deps, mock_env(), QueryMsg::Version {}).unwrap()).unwrap()
+        };
+        assert_eq!(
+            version(deps.as_ref()),
+            VersionResponse { contract: CONTRACT_NAME.to_string(), version: Some(CONTRACT_VERSION.to_string()) }
+        );
+
+        // a contract instantiated by an older version
+        let (major, _, _) = parse_version(CONTRACT_VERSION).unwrap();
+        let previous = "0.1.0".to_string();
+        assert!(parse_version(&previous).unwrap() < parse_version(CONTRACT_VERSION).unwrap());
+        CONTRACT_VERSION_KEY.save(&mut deps.storage, &previous).unwrap();
+        assert_eq!(version(deps.as_ref()).version, Some(previous.clone()));
+        let config = CONFIG_KEY.load(&deps.storage).unwrap();
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1].value, previous);
+        assert_eq!(res.attributes[2].value, CONTRACT_VERSION);
+        assert_eq!(CONTRACT_VERSION_KEY.load(&deps.storage).unwrap(), CONTRACT_VERSION);
+        assert_eq!(CONFIG_KEY.load(&deps.storage).unwrap(), config);
+
+        // migrating to the same version is allowed
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        // contracts instantiated before versioning
+        CONTRACT_VERSION_KEY.remove(&mut deps.storage);
+        assert_eq!(version(deps.as_ref()).version, None);
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(CONTRACT_VERSION_KEY.load(&deps.storage).unwrap(), CONTRACT_VERSION);
+
+        // downgrades are refused
+        let next = format!("{}.0.0", major + 1);
+        CONTRACT_VERSION_KEY.save(&mut deps.storage, &next).unwrap();
+        assert_eq!(
+            migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err(),
+            ContractError::InvalidMigration { stored: next.clone(), current: CONTRACT_VERSION.to_string() }
+        );
+        assert_eq!(CONTRACT_VERSION_KEY.load(&deps.storage).unwrap(), next);
+
+        assert_eq!(parse_version("1.2.3"), Some((1, 2, 3)));
+        assert_eq!(parse_version("1.2"), None);
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("1.2.x"), None);
+    }
+
+    #[test]
+    fn test_migrate_legacy_config() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &[]);
+
+        // the storage of a contract deployed before Config grew, metrics and versioning
+        let legacy = LegacyConfig { owner: info.sender.clone(), contract_address: mock_env().contract.address };
+        LEGACY_CONFIG_KEY.save(&mut deps.storage, &legacy).unwrap();
+        COUNTER_KEY.save(&mut deps.storage, &0).unwrap();
+
+        let start_game = |deps: DepsMut| {
+            execute_start_game(deps, start_game_args(1, start_game_players(2)))
+        };
+        assert!(start_game(deps.as_mut()).is_err());
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).is_err());
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let config = CONFIG_KEY.load(&deps.storage).unwrap();
+        assert_eq!(config, Config::from(legacy));
+        assert_eq!(config.dealers, vec![info.sender.clone()]);
+        assert_eq!(METRICS_KEY.load(&deps.storage).unwrap(), ExecuteMetrics::default());
+
+        start_game(deps.as_mut()).unwrap();
+        assert_eq!(METRICS_KEY.load(&deps.storage).unwrap().start_games, 1);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config.owner, info.sender);
+        assert_eq!(config.instantiated_at, None);
+
+        // a current Config is left as is
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(METRICS_KEY.load(&deps.storage).unwrap().start_games, 1);
+    }
+
+    #[test]
+    fn test_button_seat() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let start_game = |deps: DepsMut,
+                          hand_ref: u32,
+                          player_count: u128,
+                          button_seat: Option<u32>|
+         -> Result<u32, ContractError> {
+            let players = start_game_players(player_count);
+            let res = execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                button_seat,
+                ..start_game_args(1, players)
+            })?;
+            let response_attr = res.attributes.iter().find(|attr| attr.key == RESPONSE_KEY).unwrap();
+            match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+                ResponsePayload::StartGame(start_game_response) => Ok(start_game_response.button_seat),
+                _ => panic!("Expected StartGame response"),
+            }
+        };
+        let table_state = |deps: Deps| {
+            from_binary::<TableStateResponse>(&query(deps, mock_env(), QueryMsg::TableState { table_id: 1 }).unwrap())
+                .unwrap()
+                .button_seat
+        };
+
+        assert_eq!(
+            start_game(deps.as_mut(), 1, 3, Some(3)).unwrap_err(),
+            ContractError::InvalidButtonSeat { seat: 3, player_count: 3 }
+        );
+
+        // a new table starts on the first seat, then the button rotates
+        assert_eq!(start_game(deps.as_mut(), 1, 3, None), Ok(0));
+        for (hand_ref, expected) in [(2, 1), (3, 2), (4, 0)] {
+            retrieve_showdown(deps.as_mut(), 1);
+            assert_eq!(start_game(deps.as_mut(), hand_ref, 3, None), Ok(expected));
+            assert_eq!(table_state(deps.as_ref()), expected);
+        }
+
+        // an explicit seat overrides the rotation, which then goes on from it
+        retrieve_showdown(deps.as_mut(), 1);
+        assert_eq!(start_game(deps.as_mut(), 5, 3, Some(2)), Ok(2));
+        retrieve_showdown(deps.as_mut(), 1);
+        assert_eq!(start_game(deps.as_mut(), 6, 2, None), Ok(1));
+        assert_eq!(load_table(&deps.storage, 1).unwrap().button_seat, 1);
+    }
+
+    #[test]
+    fn test_short_deck() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let players = start_game_players(MAX_PLAYERS as u128);
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            deck_variant: DeckVariant::Short,
+            ..start_game_args(1, players)
+        })
+        .unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!(table.deck_variant, DeckVariant::Short);
+
+        let dealt = table.all_dealt_cards();
+        let board = dealt.board();
+        let dealt_cards: Vec<&Card> = dealt
+            .players
+            .iter()
+            .flat_map(|(_, hand)| hand.iter())
+            .chain(board.iter())
+            .chain(dealt.burned.iter())
+            .collect();
+        assert_eq!(dealt_cards.len(), required_cards(MAX_PLAYERS, COMMUNITY_CARD_PHASES));
+        assert!(dealt_cards.iter().all(|card| Deck::short().cards.contains(card)));
+
+        let unknown = query_handlers::query_unknown_cards(deps.as_ref(), 1, "key1".to_string()).unwrap();
+        assert_eq!(unknown.cards.len(), 36 - 2);
+
+        retrieve_showdown(deps.as_mut(), 1);
+        let response: VerifyShuffleResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::VerifyShuffle { table_id: 1 }).unwrap()).unwrap();
+        let mut replayed = Deck::short();
+        helpers::shuffle_deck(&mut replayed, table.shuffle_seed, table.shuffle_max_retries);
+        assert_eq!(Deck::try_from_bytes(&response.deck, &table.deck_variant).unwrap(), replayed);
+        assert_eq!(replayed.cards.len(), 36);
+
+        // a full short deck table leaves cards to spare, a larger one couldn't be dealt
+        assert!(execute_handlers::validate_deck_size(&DeckVariant::Short, MAX_PLAYERS, COMMUNITY_CARD_PHASES).is_ok());
+        assert_eq!(required_cards(14, COMMUNITY_CARD_PHASES), 36);
+        assert!(execute_handlers::validate_deck_size(&DeckVariant::Short, 14, COMMUNITY_CARD_PHASES).is_ok());
+        assert_eq!(
+            execute_handlers::validate_deck_size(&DeckVariant::Short, 15, COMMUNITY_CARD_PHASES).unwrap_err(),
+            ContractError::InsufficientDeck { required: 38, available: 36 }
+        );
+    }
+
+    #[test]
+    fn test_retrieved_community_cards() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        start_game(deps.as_mut(), 1, start_game_players(2));
+        let flop = load_table(&deps.storage, 1).unwrap().community_cards.flop;
+        let retrieved = |deps: Deps, secret_key: u128| {
+            query(deps, mock_env(), QueryMsg::RetrievedCommunityCards { table_id: 1, game_state: GameState::Flop, secret_key })
+        };
+
+        assert_eq!(
+            retrieved(deps.as_ref(), flop.secret).unwrap_err(),
+            StdError::generic_err(
+                ContractError::CardsNotRetrieved { table_id: 1, street: "flop".to_string() }.to_string()
+            )
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::CommunityCards { table_id: 1, game_state: GameState::Flop, expected_hand_ref: None },
+        )
+        .unwrap();
+        for _ in 0..2 {
+            let response: CommunityCardsResponse = from_binary(&retrieved(deps.as_ref(), flop.secret).unwrap()).unwrap();
+            assert_eq!(response.community_cards, flop.cards);
+            assert_eq!(response.retrieved_at, Some(mock_env().block.time));
+        }
+        assert_eq!(
+            load_table(&deps.storage, 1).unwrap().community_cards.flop.retrieved_at,
+            Some(mock_env().block.time)
+        );
+
+        assert_eq!(
+            retrieved(deps.as_ref(), flop.secret + 1).unwrap_err(),
+            StdError::generic_err(ContractError::SecretMismatch { street: "flop".to_string() }.to_string())
+        );
+
+        // with strict_one_time_reveal the reveal consumed the secret, for the re-read as for the regular query
+        let mut config = CONFIG_KEY.load(&deps.storage).unwrap();
+        config.strict_one_time_reveal = true;
+        CONFIG_KEY.save(&mut deps.storage, &config).unwrap();
+        let community_cards_query =
+            QueryMsg::CommunityCards { table_id: 1, game_state: GameState::Flop, secret_key: flop.secret };
+        assert!(query(deps.as_ref(), mock_env(), community_cards_query).is_err());
+        assert_eq!(retrieved(deps.as_ref(), flop.secret).unwrap_err(), StdError::generic_err("Phase secret already consumed"));
+    }
+
+    #[test]
+    fn test_out_of_order_reveal() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        start_game(deps.as_mut(), 1, start_game_players(2));
+        let reveal = |deps: DepsMut, game_state: GameState| {
+            execute(
+                deps,
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::CommunityCards { table_id: 1, game_state, expected_hand_ref: None },
+            )
+        };
+
+        assert_eq!(
+            reveal(deps.as_mut(), GameState::River).unwrap_err(),
+            ContractError::OutOfOrderReveal { expected: GameState::Flop, got: GameState::River }
+        );
+        reveal(deps.as_mut(), GameState::Flop).unwrap();
+        assert_eq!(
+            reveal(deps.as_mut(), GameState::River).unwrap_err(),
+            ContractError::OutOfOrderReveal { expected: GameState::Turn, got: GameState::River }
+        );
+        // a refused reveal leaves the street unrevealed
+        assert!(load_table(&deps.storage, 1).unwrap().community_cards.river.unwrap().retrieved_at.is_none());
+        assert_eq!(reveal(deps.as_mut(), GameState::Flop).unwrap_err(), ContractError::CardsAlreadyRetrieved {});
+
+        // an all-in showdown completes the hand, the streets left can't be revealed anymore
+        retrieve_showdown(deps.as_mut(), 1);
+        assert_eq!(load_table(&deps.storage, 1).unwrap().current_phase, GameState::Showdown);
+        assert_eq!(
+            reveal(deps.as_mut(), GameState::Turn).unwrap_err(),
+            ContractError::OutOfOrderReveal { expected: GameState::Showdown, got: GameState::Turn }
+        );
+        assert!(matches!(
+            execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AdvancePhase { table_id: 1 }).unwrap_err(),
+            ContractError::GameStateError { game_state: Some(GameState::Showdown), .. }
+        ));
+    }
+
+    #[test]
+    fn test_rebuild_table() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let players = start_game_players(2);
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            deck_variant: DeckVariant::Short,
+            max_hands: Some(2),
+            ..start_game_args(1, players.clone())
+        })
+        .unwrap();
+        // a hand left mid-way, its river lost
+        let mut table = load_table(&deps.storage, 1).unwrap();
+        table.community_cards.river = None;
+        save_table(&mut deps.storage, 1, &table).unwrap();
+
+        let rebuild = |table_id: u32| ExecuteMsg::RebuildTable { table_id, players: players.clone(), hand_ref: 2 };
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::AddDealer { dealer: Addr::unchecked("dealer") })
+            .unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("dealer", &[]), rebuild(1));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), rebuild(2));
+        assert_eq!(res.unwrap_err(), ContractError::TableNotFound { table_id: 2 });
+
+        // the hand in progress doesn't block the rebuild, nor does a pause
+        execute(deps.as_mut(), mock_env(), info.clone(), ExecuteMsg::SetPaused { paused: true }).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), rebuild(1)).unwrap();
+        let previous_hand_log = res.attributes.iter().find(|attr| attr.key == "previous_hand_log").unwrap();
+        assert!(previous_hand_log.value.contains("\"corrupted\":true"));
+
+        let history: HandHistoryResponse = from_binary(
+            &query(deps.as_ref(), mock_env(), QueryMsg::HandHistory { table_id: 1, limit: None, start_after: None, compress: false })
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(history.hands.len(), 1);
+        assert_eq!(history.hands[0].hand_ref, 1);
+        assert!(history.hands[0].corrupted);
+        assert!(load_game_result(&deps.storage, 1, 1).is_none());
+
+        // a fresh hand with the default settings, in place of the broken one under the table's hand limit
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!((table.hand_ref, table.current_phase, table.deck_variant), (2, GameState::PreFlop, DeckVariant::Standard));
+        assert!(table.community_cards.river.is_some());
+        assert_eq!((table.hands_dealt, table.max_hands), (1, Some(2)));
+
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::SetPaused { paused: false }).unwrap();
+        let next_hand = |hand_ref: u32| StartGameArgs { hand_ref, ..start_game_args(1, players.clone()) };
+        retrieve_showdown(deps.as_mut(), 1);
+        execute_start_game(deps.as_mut(), next_hand(3)).unwrap();
+        retrieve_showdown(deps.as_mut(), 1);
+        assert_eq!(
+            execute_start_game(deps.as_mut(), next_hand(4)).unwrap_err(),
+            ContractError::TableHandLimitReached { table_id: 1, max_hands: 2 }
+        );
+    }
+
+    #[test]
+    fn test_showdown_status() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let status = |deps: Deps| query(deps, mock_env(), QueryMsg::ShowdownStatus { table_id: 1 });
+        assert_eq!(
+            status(deps.as_ref()).unwrap_err(),
+            StdError::generic_err(ContractError::TableNotFound { table_id: 1 }.to_string())
+        );
+
+        start_game(deps.as_mut(), 1, start_game_players(2));
+        let response: ShowdownStatusResponse = from_binary(&status(deps.as_ref()).unwrap()).unwrap();
+        assert_eq!(
+            response,
+            ShowdownStatusResponse {
+                table_id: 1,
+                hand_ref: 1,
+                showdown_retrieved_at: None,
+                flop_retrieved_at: None,
+                turn_retrieved_at: None,
+                river_retrieved_at: None,
+            }
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::CommunityCards { table_id: 1, game_state: GameState::Flop, expected_hand_ref: None },
+        )
+        .unwrap();
+        let response: ShowdownStatusResponse = from_binary(&status(deps.as_ref()).unwrap()).unwrap();
+        assert_eq!(response.flop_retrieved_at, Some(mock_env().block.time));
+        assert_eq!((response.turn_retrieved_at, response.showdown_retrieved_at), (None, None));
+
+        // querying the status doesn't retrieve the showdown
+        retrieve_showdown(deps.as_mut(), 1);
+        let response: ShowdownStatusResponse = from_binary(&status(deps.as_ref()).unwrap()).unwrap();
+        assert_eq!(response.showdown_retrieved_at, Some(mock_env().block.time));
+    }
+
+    #[test]
+    fn test_deck_commitment() {
         let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let commitment = |deps: Deps| query(deps, mock_env(), QueryMsg::DeckCommitment { table_id: 1 });
+        assert_eq!(
+            commitment(deps.as_ref()).unwrap_err(),
+            StdError::generic_err(ContractError::TableNotFound { table_id: 1 }.to_string())
+        );
+
+        start_game(deps.as_mut(), 1, start_game_players(3));
+
+        // committed right away, the deck itself only after the showdown
+        let committed: DeckCommitmentResponse = from_binary(&commitment(deps.as_ref()).unwrap()).unwrap();
+        assert_eq!((committed.table_id, committed.hand_ref), (1, 1));
+        assert!(query(deps.as_ref(), mock_env(), QueryMsg::VerifyShuffle { table_id: 1 }).is_err());
+
+        retrieve_showdown(deps.as_mut(), 1);
+        let verify: VerifyShuffleResponse =
+            from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::VerifyShuffle { table_id: 1 }).unwrap()).unwrap();
+        assert_eq!(committed.commitment.as_slice(), Sha256::digest(verify.deck.as_slice()).as_slice());
+
+        // the revealed deck is the one the hand was dealt from: hole cards first, from the end
+        let table = load_table(&deps.storage, 1).unwrap();
+        let deck = Deck::try_from_bytes(&verify.deck, &table.deck_variant).unwrap();
+        assert_eq!(deck.cards[deck.cards.len() - 2..], [table.players[0].hand[1].clone(), table.players[0].hand[0].clone()]);
+    }
 
-        let msg = InstantiateMsg {};
+    #[test]
+    fn test_deck_variant_query() {
+        let mut deps = mock_dependencies();
         let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let deck_variant_query = |deps: Deps, table_id: u32| query(deps, mock_env(), QueryMsg::DeckVariant { table_id });
+        assert_eq!(
+            deck_variant_query(deps.as_ref(), 1).unwrap_err(),
+            StdError::generic_err(ContractError::TableNotFound { table_id: 1 }.to_string())
+        );
+
+        for (table_id, deck_variant) in [(1, DeckVariant::Standard), (2, DeckVariant::Short)] {
+            execute_start_game(deps.as_mut(), StartGameArgs {
+                deck_variant: deck_variant.clone(),
+                ..start_game_args(table_id, start_game_players(2))
+            })
+            .unwrap();
+
+            let response: DeckVariantResponse = from_binary(&deck_variant_query(deps.as_ref(), table_id).unwrap()).unwrap();
+            assert_eq!((response.table_id, response.hand_ref), (table_id, 1));
+            assert_eq!(response.cards, deck_variant.deck().cards);
+            assert_eq!(response.deck_variant, deck_variant);
 
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+            // the shuffle replayed from the queried deck is the dealt one
+            retrieve_showdown(deps.as_mut(), table_id);
+            let table = load_table(&deps.storage, table_id).unwrap();
+            let verify: VerifyShuffleResponse =
+                from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::VerifyShuffle { table_id }).unwrap()).unwrap();
+            let mut replayed = Deck { cards: response.cards };
+            helpers::shuffle_deck(&mut replayed, table.shuffle_seed, table.shuffle_max_retries);
+            assert_eq!(Deck::try_from_bytes(&verify.deck, &table.deck_variant).unwrap(), replayed);
+        }
     }
 
     #[test]
-    fn test_start_game() {
+    fn test_fold() {
         let mut deps = mock_dependencies();
-
-        let msg = InstantiateMsg {};
         let info = mock_info("creator", &coins(1000, "earth"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
 
-        let players = vec![
-            StartGamePlayer {
-                username: "player1".to_string(),
-                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
-                public_key: "key1".to_string(),
-            },
-            StartGamePlayer {
-                username: "player2".to_string(),
-                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
-                public_key: "key2".to_string(),
-            },
-        ];
+        let players = start_game_players(3);
+        start_game(deps.as_mut(), 1, players);
+        let hand_hash = load_table(&deps.storage, 1).unwrap().hand_hash().unwrap();
 
-        let res = execute(
-            deps.as_mut(),
-            mock_env(),
-            info,
-            ExecuteMsg::StartGame {
+        let fold = |player_id: u128| ExecuteMsg::Fold { table_id: 1, player_id: Uuid::from_u128(player_id) };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("player1", &[]), fold(2));
+        assert_eq!(res.unwrap_err(), ContractError::Unauthorized {});
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), fold(4));
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::PlayerNotFound {
                 table_id: 1,
-                hand_ref: 1,
-                players,
-                prev_hand_showdown_players: vec![],
-            },
-        )
-        .unwrap();
-        
-        let attrs = &res.attributes;
-        let response_attr = attrs.iter().find(|attr| attr.key == "response").unwrap();
-        assert!(response_attr.value.contains("\"players\":[\"player1\",\"player2\"]"));
-        assert!(response_attr.value.contains("\"table_id\":1"));
-        assert!(response_attr.value.contains("\"hand_ref\":1"));
+                player: Uuid::from_u128(4).to_string(),
+            }
+        );
 
-        let player_info1 = query_player_private_data(deps.as_ref(), 1, "key1".to_string()).unwrap();
-        let player_info2 = query_player_private_data(deps.as_ref(), 1, "key2".to_string()).unwrap();
-        
-        
-        assert_eq!(player_info1.table_id, 1);
-        assert_eq!(player_info1.hand_ref, 1);
-        assert_eq!(player_info1.hand.len(), 2);
-        assert!(player_info1.flop_secret_share.parse::<u64>().is_ok());
-        
-        assert_eq!(player_info2.table_id, 1);
-        assert_eq!(player_info2.hand_ref, 1);
-        assert_eq!(player_info2.hand.len(), 2);
-        assert!(player_info2.flop_secret_share.parse::<u64>().is_ok());
-        
-        let flop_secret = addition_shares(vec![
-            player_info1.flop_secret_share.parse::<u64>().unwrap(),
-            player_info2.flop_secret_share.parse::<u64>().unwrap(),
-        ]);
-        let turn_secret = addition_shares(vec![
-            player_info1.turn_secret_share.parse::<u64>().unwrap(),
-            player_info2.turn_secret_share.parse::<u64>().unwrap(),
-        ]);
-        let river_secret = addition_shares(vec![
-            player_info1.river_secret_share.parse::<u64>().unwrap(),
-            player_info2.river_secret_share.parse::<u64>().unwrap(),
-        ]);
+        execute(deps.as_mut(), mock_env(), info.clone(), fold(2)).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!(table.players.iter().map(|player| player.folded).collect::<Vec<_>>(), vec![false, true, false]);
+        // folding doesn't change the dealt hand
+        assert_eq!(table.hand_hash().unwrap(), hand_hash);
 
-        
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::CommunityCards {
+        let showdown = |deps: DepsMut, player_ids: Vec<u128>| {
+            execute(
+                deps,
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::Showdown {
+                    table_id: 1,
+                    game_state: GameState::River,
+                    showdown_player_ids: player_ids.into_iter().map(Uuid::from_u128).collect(),
+                    expected_hand_ref: None,
+                },
+            )
+        };
+        assert_eq!(
+            showdown(deps.as_mut(), vec![1, 2, 3]).unwrap_err(),
+            ContractError::PlayerFolded {
                 table_id: 1,
-                game_state: GameState::Flop,
-                secret_key: flop_secret,
-            },
+                player: Uuid::from_u128(2).to_string(),
+            }
         );
-        let flop_response: CommunityCardsResponse = from_binary(res.as_ref().unwrap()).unwrap();
-        assert_eq!(flop_response.table_id, 1);
-        assert_eq!(flop_response.hand_ref, 1);
-        assert_eq!(flop_response.game_state, GameState::Flop);
-        assert_eq!(flop_response.community_cards.len(), 3);
+        assert!(load_table(&deps.storage, 1).unwrap().showdown_retrieved_at.is_none());
 
-        
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::CommunityCards {
-                table_id: 1,
-                game_state: GameState::Turn,
-                secret_key: turn_secret,
-            },
+        let res = showdown(deps.as_mut(), vec![1, 3]).unwrap();
+        let response_attr = res.attributes.iter().find(|attr| attr.key == RESPONSE_KEY).unwrap();
+        let players_cards = match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::Showdown(showdown) => showdown.players_cards,
+            _ => panic!("Expected a showdown response"),
+        };
+        assert_eq!(
+            players_cards.into_iter().map(|(player_id, _)| player_id).collect::<Vec<_>>(),
+            vec![Uuid::from_u128(1), Uuid::from_u128(3)]
         );
-        let turn_response: CommunityCardsResponse = from_binary(res.as_ref().unwrap()).unwrap();
-        assert_eq!(turn_response.table_id, 1);
-        assert_eq!(turn_response.hand_ref, 1);
-        assert_eq!(turn_response.game_state, GameState::Turn);
-        assert_eq!(turn_response.community_cards.len(), 1);
 
-        
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::CommunityCards {
-                table_id: 1,
-                game_state: GameState::River,
-                secret_key: river_secret,
-            },
-        );
-        let river_response: CommunityCardsResponse = from_binary(res.as_ref().unwrap()).unwrap();
-        assert_eq!(river_response.table_id, 1);
-        assert_eq!(river_response.hand_ref, 1);
-        assert_eq!(river_response.game_state, GameState::River);
-        assert_eq!(river_response.community_cards.len(), 1);
+        // the hand is over
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), fold(1));
+        assert_eq!(res.unwrap_err(), ContractError::CardsAlreadyRetrieved {});
 
-        
-        let res = query(
-            deps.as_ref(),
-            mock_env(),
-            QueryMsg::CommunityCards {
-                table_id: 1,
-                game_state: GameState::Flop,
-                secret_key: flop_secret + 1, 
-            },
+        // nor can the hand logs reveal the folded hand, or rank it
+        let end_hand = |deps: DepsMut, player_ids: Vec<u128>| {
+            execute(
+                deps,
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::EndHand {
+                    table_id: 1,
+                    showdown_player_ids: player_ids.into_iter().map(Uuid::from_u128).collect(),
+                },
+            )
+        };
+        assert_eq!(
+            end_hand(deps.as_mut(), vec![1, 2]).unwrap_err(),
+            ContractError::PlayerFolded { table_id: 1, player: Uuid::from_u128(2).to_string() }
         );
-        assert!(res.is_err());
+        assert_eq!(end_hand(deps.as_mut(), vec![1, 3, 1]).unwrap_err(), ContractError::DuplicatePlayerIds {});
+        let res = execute_start_game(deps.as_mut(), StartGameArgs {
+            hand_ref: 2,
+            prev_hand_showdown_players: vec![Uuid::from_u128(2)],
+            ..start_game_args(1, start_game_players(3))
+        });
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::PlayerFolded { table_id: 1, player: Uuid::from_u128(2).to_string() }
+        );
+        assert_eq!(hand_history(1).get_len(&deps.storage).unwrap(), 0);
+
+        end_hand(deps.as_mut(), vec![1, 3]).unwrap();
+        assert_eq!(hand_history(1).get_len(&deps.storage).unwrap(), 1);
     }
-    
+
     #[test]
-    fn test_community_cards() {
+    fn test_reveal_order() {
         let mut deps = mock_dependencies();
-        
-        
-        let msg = InstantiateMsg {};
         let info = mock_info("creator", &coins(1000, "earth"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        
-        
-        let players = vec![
-            StartGamePlayer {
-                username: "player1".to_string(),
-                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
-                public_key: "key1".to_string(),
-            },
-            StartGamePlayer {
-                username: "player2".to_string(),
-                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
-                public_key: "key2".to_string(),
-            },
-        ];
-        
-        execute(
-            deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            ExecuteMsg::StartGame {
-                table_id: 1,
-                hand_ref: 1,
-                players,
-                prev_hand_showdown_players: vec![],
-            },
-        )
-        .unwrap();
-        
-        
-        let res = execute(
-            deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            ExecuteMsg::CommunityCards {
-                table_id: 1,
-                game_state: GameState::Flop,
-            },
-        )
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            button_seat: Some(2),
+            ..start_game_args(1, start_game_players(4))
+        })
         .unwrap();
-        
-        
-        let attrs = &res.attributes;
-        println!("{:?}", attrs);
-        let response_attr = attrs.iter().find(|attr| attr.key == "response").unwrap();
-        assert!(response_attr.value.contains("\"game_state\":\"flop\""));
-        
-        
+
+        let showdown_player_ids: Vec<Uuid> = [1, 4, 3].into_iter().map(Uuid::from_u128).collect();
         let res = execute(
             deps.as_mut(),
             mock_env(),
-            info.clone(),
-            ExecuteMsg::CommunityCards {
+            info,
+            ExecuteMsg::Showdown {
                 table_id: 1,
-                game_state: GameState::Turn,
+                game_state: GameState::River,
+                showdown_player_ids: showdown_player_ids.clone(),
+                expected_hand_ref: None,
             },
         )
         .unwrap();
-        
-        
-        let attrs = &res.attributes;
-        let response_attr = attrs.iter().find(|attr| attr.key == "response").unwrap();
-        assert!(response_attr.value.contains("\"game_state\":\"turn\""));
-        let response_payload: ResponsePayload = serde_json_wasm::from_str(&response_attr.value).unwrap();
-        match response_payload {
-            ResponsePayload::CommunityCards(cards_response) => {
-            assert_eq!(cards_response.community_cards.len(), 1);
-            assert_eq!(cards_response.game_state, GameState::Turn);
-            },
-            _ => panic!("Expected CommunityCards response"),
-        }
+        let response_attr = res.attributes.iter().find(|attr| attr.key == RESPONSE_KEY).unwrap();
+        let showdown = match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::Showdown(showdown) => showdown,
+            _ => panic!("Expected a showdown response"),
+        };
+
+        // players_cards keeps the input order, the reveal starts left of the button on seat 2
+        assert_eq!(
+            showdown.players_cards.iter().map(|(player_id, _)| *player_id).collect::<Vec<_>>(),
+            showdown_player_ids
+        );
+        assert_eq!(showdown.reveal_order, [4, 1, 3].into_iter().map(Uuid::from_u128).collect::<Vec<_>>());
     }
-    
+
     #[test]
-    fn test_invalid_game_state() {
+    fn test_player_hand() {
         let mut deps = mock_dependencies();
-        
-        let msg = InstantiateMsg {};
         let info = mock_info("creator", &coins(1000, "earth"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        
-        let players = vec![
-            StartGamePlayer {
-                username: "player1".to_string(),
-                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
-                public_key: "key1".to_string(),
-            },
-            StartGamePlayer {
-                username: "player2".to_string(),
-                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
-                public_key: "key2".to_string(),
-            },
-        ];
-        
-        execute(
-            deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            ExecuteMsg::StartGame {
-                table_id: 1,
-                hand_ref: 1,
-                players,
-                prev_hand_showdown_players: vec![],
-            },
-        )
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let players = start_game_players(3);
+        execute_start_game(deps.as_mut(), StartGameArgs {
+            hand_ref: 5,
+            ..start_game_args(1, players)
+        })
         .unwrap();
-        
-        
-        let res = execute(
-            deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            ExecuteMsg::CommunityCards {
+        let table = load_table(&deps.storage, 1).unwrap();
+
+        let player_hand = |deps: Deps, hand_secret: u128| {
+            let raw = format!(r#"{{"player_hand":{{"table_id":1,"hand_secret":"{}"}}}}"#, hand_secret);
+            query(deps, mock_env(), from_binary(&Binary::from(raw.into_bytes())).unwrap())
+        };
+
+        let player = &table.players[1];
+        let response: PlayerHandResponse = from_binary(&player_hand(deps.as_ref(), player.hand_secret).unwrap()).unwrap();
+        assert_eq!(
+            response,
+            PlayerHandResponse {
                 table_id: 1,
-                game_state: GameState::PreFlop,
-            },
+                hand_ref: 5,
+                player_id: player.player_id,
+                hand: player.hand.clone(),
+            }
+        );
+
+        let unknown_secret = table.players.iter().map(|player| player.hand_secret).max().unwrap().wrapping_add(1);
+        assert_eq!(
+            player_hand(deps.as_ref(), unknown_secret).unwrap_err(),
+            StdError::generic_err(ContractError::UnknownHandSecret { table_id: 1 }.to_string())
+        );
+    }
+
+    #[test]
+    fn test_showdown_from_state() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        for (table_id, game_state, board_len) in [(1, GameState::PreFlop, 5), (2, GameState::Flop, 2), (3, GameState::Turn, 1)] {
+            execute_start_game(deps.as_mut(), start_game_args(table_id, start_game_players(2)))
+            .unwrap();
+
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::Showdown {
+                    table_id,
+                    game_state: game_state.clone(),
+                    showdown_player_ids: vec![Uuid::from_u128(1), Uuid::from_u128(2)],
+                    expected_hand_ref: None,
+                },
+            )
+            .unwrap();
+            let response_attr = res.attributes.iter().find(|attr| attr.key == RESPONSE_KEY).unwrap();
+            let showdown = match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+                ResponsePayload::Showdown(showdown) => showdown,
+                _ => panic!("Expected a showdown response"),
+            };
+
+            let board = load_table(&deps.storage, table_id).unwrap().all_dealt_cards().board();
+            assert_eq!(showdown.from_state, game_state);
+            assert_eq!(showdown.board_len, board_len);
+            // the streets dealt after the all-in, the end of the board
+            assert_eq!(showdown.community_cards.unwrap(), board[5 - board_len..].to_vec());
+        }
+    }
+
+    #[test]
+    fn test_showdown_duplicate_player_ids() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        start_game(deps.as_mut(), 1, start_game_players(3));
+
+        let showdown = |deps: DepsMut, player_ids: Vec<u128>| {
+            execute(
+                deps,
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::Showdown {
+                    table_id: 1,
+                    game_state: GameState::River,
+                    showdown_player_ids: player_ids.into_iter().map(Uuid::from_u128).collect(),
+                    expected_hand_ref: None,
+                },
+            )
+        };
+        assert_eq!(showdown(deps.as_mut(), vec![1, 2, 1]).unwrap_err(), ContractError::DuplicatePlayerIds {});
+        // the hands weren't retrieved, the showdown can still happen once
+        assert!(load_table(&deps.storage, 1).unwrap().showdown_retrieved_at.is_none());
+        showdown(deps.as_mut(), vec![1, 2]).unwrap();
+    }
+
+    #[test]
+    fn test_expected_hand_ref() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let start_game = |deps: DepsMut, hand_ref: u32| {
+            execute_start_game(deps, StartGameArgs {
+                hand_ref,
+                ..start_game_args(1, start_game_players(2))
+            })
+        };
+        let community_cards = |deps: DepsMut, game_state: GameState, expected_hand_ref: Option<u32>| {
+            execute(
+                deps,
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::CommunityCards { table_id: 1, game_state, expected_hand_ref },
+            )
+        };
+        let showdown = |deps: DepsMut, expected_hand_ref: Option<u32>| {
+            execute(
+                deps,
+                mock_env(),
+                info.clone(),
+                ExecuteMsg::Showdown {
+                    table_id: 1,
+                    game_state: GameState::Turn,
+                    showdown_player_ids: vec![Uuid::from_u128(1), Uuid::from_u128(2)],
+                    expected_hand_ref,
+                },
+            )
+        };
+
+        start_game(deps.as_mut(), 1).unwrap();
+        community_cards(deps.as_mut(), GameState::Flop, Some(1)).unwrap();
+        showdown(deps.as_mut(), Some(1)).unwrap();
+
+        // a client still on hand 1 once hand 2 started
+        start_game(deps.as_mut(), 2).unwrap();
+        assert_eq!(
+            community_cards(deps.as_mut(), GameState::Flop, Some(1)).unwrap_err(),
+            ContractError::HandRefMismatch { expected: 1, actual: 2 }
         );
-        
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::GameStateError { method, table_id, game_state } => {
-                assert_eq!(method, "distribute_community_cards");
-                assert_eq!(table_id, 1);
-                assert_eq!(game_state, Some(GameState::PreFlop));
-            },
-            _ => panic!("Expected GameStateError"),
-        }
+        assert!(load_table(&deps.storage, 1).unwrap().community_cards.flop.retrieved_at.is_none());
+        assert_eq!(
+            showdown(deps.as_mut(), Some(1)).unwrap_err(),
+            ContractError::HandRefMismatch { expected: 1, actual: 2 }
+        );
+        assert!(load_table(&deps.storage, 1).unwrap().showdown_retrieved_at.is_none());
+
+        community_cards(deps.as_mut(), GameState::Flop, Some(2)).unwrap();
+        community_cards(deps.as_mut(), GameState::Turn, None).unwrap();
+        showdown(deps.as_mut(), None).unwrap();
     }
-    
+
     #[test]
-    fn test_showdown() {
+    fn test_showdown_query_without_secrets() {
         let mut deps = mock_dependencies();
-        
-        
-        let msg = InstantiateMsg {};
         let info = mock_info("creator", &coins(1000, "earth"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        
-        
-        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
-        let player2_id = Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap();
-        
-        let players = vec![
-            StartGamePlayer {
-                username: "player1".to_string(),
-                player_id: player1_id,
-                public_key: "key1".to_string(),
-            },
-            StartGamePlayer {
-                username: "player2".to_string(),
-                player_id: player2_id,
-                public_key: "key2".to_string(),
-            },
-        ];
-        
-        execute(
-            deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            ExecuteMsg::StartGame {
-                table_id: 1,
-                hand_ref: 1,
-                players,
-                prev_hand_showdown_players: vec![],
-            },
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        start_game(deps.as_mut(), 1, start_game_players(2));
+        let table = load_table(&deps.storage, 1).unwrap();
+
+        assert_eq!(
+            query_handlers::query_showdown(deps.as_ref(), 1, None, None, None, vec![]).unwrap_err(),
+            StdError::generic_err(ContractError::NoSecretsProvided {}.to_string())
+        );
+        assert_eq!(
+            query_handlers::query_winners(deps.as_ref(), 1, None, None, None, vec![]).unwrap_err(),
+            StdError::generic_err(ContractError::NoSecretsProvided {}.to_string())
+        );
+
+        // a community only reveal still works
+        let river = table.community_cards.river.unwrap();
+        let showdown = query_handlers::query_showdown(deps.as_ref(), 1, None, None, Some(river.secret), vec![]).unwrap();
+        assert!(showdown.players_cards.is_empty());
+        assert_eq!(showdown.community_cards, Some(vec![river.card]));
+
+        // a mismatched secret names its street
+        let secret_mismatch =
+            |street: &str| StdError::generic_err(ContractError::SecretMismatch { street: street.to_string() }.to_string());
+        let flop_secret = table.community_cards.flop.secret;
+        assert_eq!(
+            query_handlers::query_showdown(deps.as_ref(), 1, Some(river.secret), None, None, vec![]).unwrap_err(),
+            secret_mismatch("flop")
+        );
+        assert_eq!(
+            query_handlers::query_showdown(deps.as_ref(), 1, Some(flop_secret), None, Some(flop_secret), vec![])
+                .unwrap_err(),
+            secret_mismatch("river")
+        );
+    }
+
+    #[test]
+    fn test_showdown_deck_accounting() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let players = start_game_players(3);
+        start_game(deps.as_mut(), 1, players);
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!(table.undealt.len(), 52 - required_cards(3, COMMUNITY_CARD_PHASES));
+
+        // the secret based Showdown query never discloses the rest of the deck
+        let players_secrets = table.players.iter().map(|player| player.hand_secret).collect();
+        let showdown = query_handlers::query_showdown(
+            deps.as_ref(),
+            1,
+            Some(table.community_cards.flop.secret),
+            table.community_cards.turn.as_ref().map(|turn| turn.secret),
+            table.community_cards.river.as_ref().map(|river| river.secret),
+            players_secrets,
         )
         .unwrap();
-        
-        
+        assert_eq!((showdown.burned, showdown.undealt), (None, None));
+
         let res = execute(
             deps.as_mut(),
             mock_env(),
-            info.clone(),
+            info,
             ExecuteMsg::Showdown {
                 table_id: 1,
                 game_state: GameState::River,
-                showdown_player_ids: vec![player1_id, player2_id],
+                showdown_player_ids: (1..=3).map(Uuid::from_u128).collect(),
+                expected_hand_ref: None,
             },
         )
         .unwrap();
-        
-        
-        let attrs = &res.attributes;
-        let response_attr = attrs.iter().find(|attr| attr.key == "response").unwrap();
-        assert!(response_attr.value.contains("\"players_cards\""));
+        let response_attr = res.attributes.iter().find(|attr| attr.key == RESPONSE_KEY).unwrap();
+        let showdown = match serde_json_wasm::from_str(&response_attr.value).unwrap() {
+            ResponsePayload::Showdown(showdown) => showdown,
+            _ => panic!("Expected Showdown response"),
+        };
+
+        let burned = showdown.burned.unwrap();
+        assert_eq!(burned, table.community_cards.burned);
+        let mut accounted: Vec<Card> = showdown
+            .players_cards
+            .into_iter()
+            .flat_map(|(_, hand)| hand)
+            .chain(showdown.community_cards.unwrap())
+            .chain(burned)
+            .chain(showdown.undealt.unwrap())
+            .collect();
+        assert_eq!(accounted.len(), 52);
+        accounted.sort_by_key(|card| card.to_bytes());
+        let mut deck = Deck::new().cards;
+        deck.sort_by_key(|card| card.to_bytes());
+        assert_eq!(accounted, deck);
     }
-    
+
     #[test]
-    fn test_player_not_found() {
+    fn test_showdown_by_ids() {
         let mut deps = mock_dependencies();
-        
-        
-        let msg = InstantiateMsg {};
         let info = mock_info("creator", &coins(1000, "earth"));
-        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        
-        
-        let player1_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
-        let player2_id = Uuid::parse_str("e6799ecf-f202-418a-a535-0b42509f69f7").unwrap();
-
-        let players = vec![
-            StartGamePlayer {
-                username: "player1".to_string(),
-                player_id: player1_id,
-                public_key: "key1".to_string(),
-            },
-            StartGamePlayer {
-                username: "player2".to_string(),
-                player_id: player2_id,
-                public_key: "key2".to_string(),
-            },
-        ];
-        
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
         execute(
             deps.as_mut(),
             mock_env(),
             info.clone(),
-            ExecuteMsg::StartGame {
-                table_id: 1,
-                hand_ref: 1,
-                players,
-                prev_hand_showdown_players: vec![],
-            },
+            ExecuteMsg::AddDealer { dealer: Addr::unchecked("dealer") },
         )
         .unwrap();
-        
-        
-        let non_existent_player = Uuid::parse_str("aaaaaaaa-bbbb-cccc-dddd-eeeeeeeeeeee").unwrap();
+
+        let players = start_game_players(2);
+        start_game(deps.as_mut(), 1, players);
+        let table = load_table(&deps.storage, 1).unwrap();
+
+        let showdown = |deps: Deps, player_ids: Vec<Uuid>, viewer: &str| {
+            query_handlers::query_showdown_by_ids(deps, 1, player_ids, viewer.to_string())
+        };
+        let both = vec![Uuid::from_u128(2), Uuid::from_u128(1)];
+
+        // the hands stay hidden until the showdown is retrieved
+        assert_eq!(
+            showdown(deps.as_ref(), both.clone(), "creator").unwrap_err(),
+            StdError::generic_err("Showdown not retrieved yet")
+        );
+        retrieve_showdown(deps.as_mut(), 1);
+
+        for viewer in ["creator", "dealer"] {
+            let response = showdown(deps.as_ref(), both.clone(), viewer).unwrap();
+            assert_eq!(
+                response.players_cards,
+                vec![
+                    (Uuid::from_u128(2), table.players[1].hand.clone()),
+                    (Uuid::from_u128(1), table.players[0].hand.clone()),
+                ]
+            );
+            assert_eq!(response.community_cards, Some(table.all_dealt_cards().board()));
+        }
+
+        // a seated player can't read the other hands
+        assert_eq!(
+            showdown(deps.as_ref(), both, "key1").unwrap_err(),
+            StdError::generic_err(ContractError::Unauthorized {}.to_string())
+        );
+        assert_eq!(
+            showdown(deps.as_ref(), vec![Uuid::from_u128(3)], "creator").unwrap_err(),
+            StdError::generic_err(
+                ContractError::PlayerNotFound { table_id: 1, player: Uuid::from_u128(3).to_string() }.to_string()
+            )
+        );
+
+        // the same players as the Showdown: neither repeated nor folded
+        assert_eq!(
+            showdown(deps.as_ref(), vec![Uuid::from_u128(1), Uuid::from_u128(1)], "creator").unwrap_err(),
+            StdError::generic_err(ContractError::DuplicatePlayerIds {}.to_string())
+        );
+        let mut folded_table = load_table(&deps.storage, 1).unwrap();
+        folded_table.players[1].folded = true;
+        save_table(&mut deps.storage, 1, &folded_table).unwrap();
+        assert_eq!(
+            showdown(deps.as_ref(), vec![Uuid::from_u128(2)], "dealer").unwrap_err(),
+            StdError::generic_err(
+                ContractError::PlayerFolded { table_id: 1, player: Uuid::from_u128(2).to_string() }.to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_start_game_missing_randomness() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), InstantiateMsg::default()).unwrap();
+
+        let mut env = mock_env();
+        env.block.random = None;
+        let mut counter = 0u128;
+        assert_eq!(
+            helpers::generate_random_number(&env, &mut counter).unwrap_err(),
+            ContractError::MissingRandomness {}
+        );
+        assert_eq!(counter, 0);
         let res = execute(
             deps.as_mut(),
-            mock_env(),
-            info.clone(),
-            ExecuteMsg::Showdown {
-                table_id: 1,
-                game_state: GameState::River,
-                showdown_player_ids: vec![non_existent_player],
-            },
+            env,
+            info,
+            ExecuteMsg::StartGame(start_game_args(1, start_game_players(2))),
         );
-        
-        assert!(res.is_err());
-        match res.unwrap_err() {
-            ContractError::PlayerNotFound { table_id, player } => {
-                assert_eq!(table_id, 1);
-                assert_eq!(player, non_existent_player.to_string());
-            },
-            _ => panic!("Expected PlayerNotFound error"),
+        assert_eq!(res.unwrap_err(), ContractError::MissingRandomness {});
+        assert!(load_table(&deps.storage, 1).is_none());
+    }
+
+    #[test]
+    fn test_random_number_salted_by_contract() {
+        let env = mock_env();
+        let mut other_env = mock_env();
+        other_env.contract.address = Addr::unchecked("other_contract");
+
+        let first_draw = |env: &Env| helpers::generate_random_number(env, &mut 0u128).unwrap();
+        assert_eq!(env.block.height, other_env.block.height);
+        assert_eq!(env.block.random, other_env.block.random);
+        assert_ne!(first_draw(&env), first_draw(&other_env));
+        assert_eq!(first_draw(&env), first_draw(&mock_env()));
+
+        // same contract, next block
+        let mut next_block = mock_env();
+        next_block.block.height += 1;
+        assert_ne!(first_draw(&env), first_draw(&next_block));
+    }
+
+    // draws a fixed sequence, the deal of a known seed can then be asserted card by card
+    struct SeededRandomness {
+        draws: std::vec::IntoIter<u128>,
+    }
+
+    impl SeededRandomness {
+        fn new(draws: Vec<u128>) -> Self {
+            SeededRandomness { draws: draws.into_iter() }
         }
     }
 
-    pub fn addition_shares(shares: Vec<u64>) -> u64 {
-        shares.iter().copied().fold(0u64, u64::wrapping_add)
+    impl Randomness for SeededRandomness {
+        fn next(&mut self) -> Result<u128, ContractError> {
+            self.draws.next().ok_or(ContractError::CustomError { val: "seed exhausted".to_string() })
+        }
+    }
+
+    #[test]
+    fn test_block_randomness() {
+        let env = mock_env();
+        let mut counter = 7u128;
+        let expected: Vec<u128> =
+            (0..3).map(|_| helpers::generate_random_number(&env, &mut counter).unwrap()).collect();
+
+        let mut rng = helpers::BlockRandomness::new(&env, 7);
+        assert_eq!((0..3).map(|_| rng.next().unwrap()).collect::<Vec<_>>(), expected);
+        assert_eq!(rng.counter, counter);
+    }
+
+    #[test]
+    fn test_seeded_deal() {
+        let players = start_game_players(2);
+        // shuffle seed, then one share per phase for the first player
+        let mut rng = SeededRandomness::new(vec![42, 11, 22, 33]);
+
+        let (mut deck, seed, fallback_used) =
+            execute_handlers::initialize_deck(&mut rng, SHUFFLE_MAX_RETRIES, &DeckVariant::Standard).unwrap();
+        assert_eq!((seed, fallback_used), (42, false));
+        let player_cards = execute_handlers::distribute_player_cards(&mut deck, &players).unwrap();
+        let mut secrets = Vec::new();
+        let community_cards = execute_handlers::generate_community_cards(
+            &mut rng,
+            &[1000, 2000, 3000],
+            &mut secrets,
+            &mut deck,
+            players.len(),
+            &SharingScheme::Additive,
+        )
+        .unwrap();
+        assert!(rng.next().is_err());
+
+        let dealt = |cards: &[Card]| cards.iter().map(Card::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            player_cards.iter().map(|(_, cards)| dealt(cards)).collect::<Vec<_>>(),
+            vec![vec!["♥10", "♦9"], vec!["♦J", "♥2"]]
+        );
+        assert_eq!(dealt(&community_cards.burned), vec!["♣K", "♠6", "♠A"]);
+        assert_eq!(dealt(&community_cards.flop.cards), vec!["♠5", "♠K", "♦8"]);
+        assert_eq!(community_cards.turn.unwrap().card.to_string(), "♦A");
+        assert_eq!(community_cards.river.unwrap().card.to_string(), "♥6");
+        assert_eq!(
+            secrets,
+            vec![(1000, vec![(1, 11), (2, 989)]), (2000, vec![(1, 22), (2, 1978)]), (3000, vec![(1, 33), (2, 2967)])]
+        );
+    }
+
+    #[test]
+    fn test_shamir_reconstruct() {
+        let env = mock_env();
+        let mut rng = helpers::BlockRandomness::new(&env, 0);
+        let secret = rng.next().unwrap() % helpers::shamir::PRIME;
+        let shares = helpers::shamir::split(&mut rng, secret, 3, 5).unwrap();
+        let points: Vec<(u128, u128)> = shares.iter().enumerate().map(|(i, &share)| (i as u128 + 1, share)).collect();
+
+        // any 3 of the 5 shares reconstruct the secret
+        for subset in [[0, 1, 2], [0, 2, 4], [1, 3, 4], [2, 3, 4]] {
+            let subset: Vec<(u128, u128)> = subset.iter().map(|&i| points[i]).collect();
+            assert_eq!(helpers::shamir::reconstruct(&subset), secret);
+        }
+        assert_eq!(helpers::shamir::reconstruct(&points), secret);
+
+        // 2 shares don't
+        for subset in [[0, 1], [2, 4], [3, 4]] {
+            let subset: Vec<(u128, u128)> = subset.iter().map(|&i| points[i]).collect();
+            assert_ne!(helpers::shamir::reconstruct(&subset), secret);
+        }
+    }
+
+    #[test]
+    fn test_threshold_sharing() {
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let start_game = |deps: DepsMut, sharing_scheme: SharingScheme| {
+            let players = start_game_players(3);
+            execute_start_game(deps, StartGameArgs {
+                sharing_scheme,
+                ..start_game_args(1, players)
+            })
+        };
+
+        for threshold in [0, 1, 4] {
+            assert_eq!(
+                start_game(deps.as_mut(), SharingScheme::Threshold { threshold }).unwrap_err(),
+                ContractError::InvalidThreshold { threshold, player_count: 3 }
+            );
+        }
+
+        start_game(deps.as_mut(), SharingScheme::Threshold { threshold: 2 }).unwrap();
+        let table = load_table(&deps.storage, 1).unwrap();
+        assert_eq!(table.sharing_scheme, SharingScheme::Threshold { threshold: 2 });
+
+        let from_shares = |deps: Deps, players: &[Player]| {
+            let shares = players
+                .iter()
+                .map(|player| PlayerShare {
+                    player_id: player.player_id,
+                    game_state: GameState::Flop,
+                    share: player.flop_secret_share,
+                })
+                .collect();
+            query(
+                deps,
+                mock_env(),
+                QueryMsg::CommunityCardsFromShares { table_id: 1, game_state: GameState::Flop, shares },
+            )
+        };
+
+        // exactly the threshold of shares, from any players
+        for players in [&table.players[..2], &table.players[1..]] {
+            let response: CommunityCardsResponse = from_binary(&from_shares(deps.as_ref(), players).unwrap()).unwrap();
+            assert_eq!(response.community_cards, table.community_cards.flop.cards);
+        }
+
+        // one share short of the threshold
+        assert_eq!(
+            from_shares(deps.as_ref(), &table.players[..1]).unwrap_err(),
+            StdError::generic_err("Not enough shares to reach the threshold")
+        );
     }
 
     #[test]
     fn test_additive_sharing() {
-        let secret = 14151497078262209000u64;
-    let mut counter = 0;
-    let _shares = helpers::additive_secret_sharing(&mock_env(), 2, secret, &mut counter).unwrap();
+        let secret = 14151497078262209000u128;
+    let env = mock_env();
+    let _shares = helpers::additive_secret_sharing(&mut helpers::BlockRandomness::new(&env, 0), 2, secret).unwrap();
     let shares = [8676118583430535000, 5475378494831674000, ];
-         let sum = shares.iter().copied().fold(0u64, u64::wrapping_add);
+         let sum = shares.iter().copied().fold(0u128, u128::wrapping_add);
          println!("{:?}", sum);
         assert_eq!(sum, secret);
     }
 
+    #[test]
+    fn test_reconstruct_additive_round_trip() {
+        let env = mock_env();
+        let mut rng = helpers::BlockRandomness::new(&env, 0);
+
+        for players in MIN_PLAYERS..=MAX_PLAYERS {
+            for _ in 0..20 {
+                let secret = rng.next().unwrap();
+                let shares = helpers::additive_secret_sharing(&mut rng, players, secret).unwrap();
+
+                assert_eq!(shares.len(), players);
+                assert_eq!(reconstruct_additive(&shares), secret);
+                // every share is needed
+                assert_ne!(reconstruct_additive(&shares[1..]), secret);
+            }
+        }
+    }
+
     #[test]
     fn test_shuffle_performance_comparison() {
         const ITERATIONS: usize = 10000;
@@ -1183,7 +6861,7 @@ mod complete_tests {
         for _ in 0..ITERATIONS {
             let mut deck = Deck::new();
             let start = Instant::now();
-            helpers::shuffle_deck(&mut deck, seed);
+            helpers::shuffle_deck(&mut deck, seed, SHUFFLE_MAX_RETRIES);
             total_duration_unbiased += start.elapsed();
         }
         let mean_unbiased = total_duration_unbiased / ITERATIONS as u32;
@@ -1201,7 +6879,7 @@ mod complete_tests {
         let original_deck = Deck::new();
         
         shuffle_deck_modulo_bias(&mut deck1, seed);
-        helpers::shuffle_deck(&mut deck2, seed);
+        helpers::shuffle_deck(&mut deck2, seed, SHUFFLE_MAX_RETRIES);
         
         assert_ne!(deck1.cards, original_deck.cards, "shuffle_deck should shuffle the deck");
         assert_ne!(deck2.cards, original_deck.cards, "shuffle_deck_unbiased should shuffle the deck");
@@ -1214,8 +6892,8 @@ mod complete_tests {
 
         while deck_len > 1 {
             deck_len -= 1;
-            rng.update(&seed.to_le_bytes());
-            rng.update(&(deck_len as u64).to_le_bytes());
+            rng.update(seed.to_le_bytes());
+            rng.update((deck_len as u64).to_le_bytes());
 
             let hash = rng.finalize_reset();
             let random_value = u64::from_le_bytes(hash[..8].try_into().unwrap());
@@ -1225,6 +6903,109 @@ mod complete_tests {
         }
     }
 
+    #[test]
+    fn test_shuffled_deck_integrity() {
+        for variant in [DeckVariant::Standard, DeckVariant::Short] {
+            for max_retries in [SHUFFLE_MAX_RETRIES, 1, 0] {
+                for seed in 0..200u64 {
+                    let mut deck = variant.deck();
+                    helpers::shuffle_deck(&mut deck, seed.wrapping_mul(0x9E37_79B9_7F4A_7C15), max_retries);
+                    assert_eq!(deck.verify_integrity(&variant), Ok(()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_shuffle_retry_cap() {
+        let mut sorted_bytes = Deck::new().to_bytes();
+        sorted_bytes.sort();
+
+        for seed in 0..1000u64 {
+            let mut deck = Deck::new();
+            assert!(!helpers::shuffle_deck(&mut deck, seed, SHUFFLE_MAX_RETRIES));
+        }
+
+        for seed in 0..100u64 {
+
+            // With no retry allowed, every index is drawn by the fallback
+            let mut capped_deck = Deck::new();
+            assert!(helpers::shuffle_deck(&mut capped_deck, seed, 0));
+            assert_ne!(capped_deck.cards, Deck::new().cards);
+
+            let mut bytes = capped_deck.to_bytes();
+            bytes.sort();
+            assert_eq!(bytes, sorted_bytes);
+        }
+
+        let mut deps = mock_dependencies();
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg::default()).unwrap();
+
+        let players = vec![
+            StartGamePlayer {
+                username: "player1".to_string(),
+                player_id: Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap(),
+                public_key: "key1".to_string(),
+            },
+            StartGamePlayer {
+                username: "player2".to_string(),
+                player_id: Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap(),
+                public_key: "key2".to_string(),
+            },
+        ];
+
+        let start_game = |deps: DepsMut, table_id: u32, shuffle_max_retries: Option<u64>| {
+            execute_start_game(deps, StartGameArgs {
+                shuffle_max_retries,
+                ..start_game_args(table_id, players.clone())
+            })
+        };
+        start_game(deps.as_mut(), 1, None).unwrap();
+        start_game(deps.as_mut(), 2, Some(0)).unwrap();
+        assert!(!load_table(&deps.storage, 1).unwrap().shuffle_fallback_used);
+        assert!(load_table(&deps.storage, 2).unwrap().shuffle_fallback_used);
+
+        start_game(deps.as_mut(), 3, Some(MAX_SHUFFLE_RETRIES)).unwrap();
+        assert_eq!(
+            start_game(deps.as_mut(), 4, Some(MAX_SHUFFLE_RETRIES + 1)).unwrap_err(),
+            ContractError::InvalidShuffleRetries { max_retries: MAX_SHUFFLE_RETRIES + 1, max: MAX_SHUFFLE_RETRIES }
+        );
+    }
+
+    #[test]
+    fn test_swap_hasher_stream() {
+        // a swap's attempts hash the same bytes as hashing seed, deck length and attempt from scratch
+        for (seed, deck_len, attempt) in [(0u64, 51usize, 0u64), (12345, 20, 3), (u64::MAX, 1, u64::MAX)] {
+            let mut from_scratch = Sha256::new();
+            from_scratch.update(seed.to_le_bytes());
+            from_scratch.update((deck_len as u64).to_le_bytes());
+            from_scratch.update(attempt.to_le_bytes());
+
+            let swap_rng = helpers::swap_hasher(seed, deck_len);
+            assert_eq!(swap_rng.clone().chain_update(attempt.to_le_bytes()).finalize(), from_scratch.finalize());
+        }
+    }
+
+    #[test]
+    fn test_shuffle_fallback_distribution() {
+        const ITERATIONS: usize = 52_000;
+        const UPPER_BOUND: usize = 52;
+
+        let mut distribution: HashMap<String, usize> = HashMap::new();
+        for seed in 0..ITERATIONS {
+            let index = helpers::fallback_index(&helpers::swap_hasher(seed as u64, UPPER_BOUND - 1), UPPER_BOUND);
+            assert!(index < UPPER_BOUND);
+            *distribution.entry(index.to_string()).or_insert(0) += 1;
+        }
+
+        let expected_frequency = ITERATIONS as f64 / UPPER_BOUND as f64;
+        let chi_squared = calculate_chi_squared(&distribution, expected_frequency, UPPER_BOUND);
+
+        // Chi-squared critical value for 51 degrees of freedom at 99% confidence
+        assert!(chi_squared < 77.38, "Fallback index failed randomness test: χ² = {:.2}", chi_squared);
+    }
+
     #[test]
     fn test_threshold_calculation(){
         let deck = Deck::new();
@@ -1256,20 +7037,20 @@ mod complete_tests {
             let mut deck = Deck::new();
             shuffle_deck_modulo_bias(&mut deck, i as u64);
             
-            for pos in 0..POSITIONS_TO_TEST {
+            for (pos, distribution) in biased_distribution.iter_mut().enumerate() {
                 let card_str = deck.cards[pos].to_string();
-                *biased_distribution[pos].entry(card_str).or_insert(0) += 1;
+                *distribution.entry(card_str).or_insert(0) += 1;
             }
         }
         
         // Run shuffle_deck_unbiased iterations
         for i in 0..SHUFFLE_ITERATIONS {
             let mut deck = Deck::new();
-            helpers::shuffle_deck(&mut deck, i as u64);
+            helpers::shuffle_deck(&mut deck, i as u64, SHUFFLE_MAX_RETRIES);
             
-            for pos in 0..POSITIONS_TO_TEST {
+            for (pos, distribution) in unbiased_distribution.iter_mut().enumerate() {
                 let card_str = deck.cards[pos].to_string();
-                *unbiased_distribution[pos].entry(card_str).or_insert(0) += 1;
+                *distribution.entry(card_str).or_insert(0) += 1;
             }
         }
         
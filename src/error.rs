@@ -29,6 +29,10 @@ pub enum ContractError {
     // issued when player is not found
     PlayerNotFound { table_id: u32, player: String },
 
+    #[error("No player of table {table_id} holds this hand secret")]
+    // issued when a hand is looked up by a secret no player of the table was dealt
+    UnknownHandSecret { table_id: u32 },
+
     #[error("Table {table_id} not found")]
     // issued when table is not found
     TableNotFound { table_id: u32 },
@@ -49,4 +53,136 @@ pub enum ContractError {
     #[error("Players invalide count: {count}")]
     // issued when player count is invalid
     InvalidPlayerCount { count: usize },
-}
\ No newline at end of file
+
+    #[error("No block randomness available")]
+    // issued when the chain does not provide env.block.random
+    MissingRandomness {},
+
+    #[error("Insufficient block randomness: got {length} bytes, expected at least {required}")]
+    // issued when env.block.random is too short to seed the contract
+    InsufficientRandomness { length: usize, required: usize },
+
+    #[error("Duplicate hole card dealt to player {player}")]
+    // issued when a hole card was dealt twice, this should never arise from a single deck
+    DuplicateHoleCard { player: String },
+
+    #[error("Invalid card string: {card}")]
+    // issued when a card can't be parsed back from its string representation
+    InvalidCardString { card: String },
+
+    #[error("Invalid card: suit {suit}, rank {rank}")]
+    // issued when a card is built from out of range suit or rank
+    InvalidCard { suit: u8, rank: u8 },
+
+    #[error("Deck exhausted")]
+    // issued when more cards are dealt than the deck holds
+    DeckExhausted {},
+
+    #[error("Table {table_id} already exists")]
+    // issued when a new table is requested on an existing table id
+    TableAlreadyExists { table_id: u32 },
+
+    #[error("Invalid phase count {phases} for {player_count} players")]
+    // issued when the number of community card phases is out of range or would exhaust the deck
+    InvalidPhaseCount { phases: u8, player_count: usize },
+
+    #[error("Hand in progress on table {table_id}")]
+    // issued when a hand is started on a table whose previous hand didn't reach the showdown
+    HandInProgress { table_id: u32 },
+
+    #[error("Duplicate player ids")]
+    // issued when two players of a hand share the same player_id
+    DuplicatePlayerIds {},
+
+    #[error("Randomness counter exhausted")]
+    // issued when the randomness counter would wrap around and repeat previous draws
+    CounterExhausted {},
+
+    #[error("No share at index {share_index}")]
+    // issued when a player's share_index has no matching share, shares are looked up by their x-coordinate
+    MissingShare { share_index: u32 },
+
+    #[error("Invalid threshold {threshold} for {player_count} players")]
+    // issued when a threshold sharing scheme can't be reconstructed by the players or gives them the secrets
+    InvalidThreshold { threshold: u8, player_count: usize },
+
+    #[error("Cannot migrate from version {stored} to {current}")]
+    // issued when a migration would downgrade the contract or the stored version can't be parsed
+    InvalidMigration { stored: String, current: String },
+
+    #[error("Invalid button seat {seat} for {player_count} players")]
+    // issued when the dealer button is given a seat outside of the table
+    InvalidButtonSeat { seat: u32, player_count: usize },
+
+    #[error("Corrupt deck")]
+    // issued when a shuffled deck doesn't hold exactly the cards of its variant, this should never arise
+    CorruptDeck {},
+
+    #[error("Table id {table_id} was already used")]
+    // issued when a new table is requested on an id a previous hand was started on
+    TableIdReserved { table_id: u32 },
+
+    #[error("Every table id was already used")]
+    // issued when NextTableId has no unused id left, the last one u32::MAX included
+    TableIdsExhausted {},
+
+    #[error("Player {player} folded in table {table_id}")]
+    // issued when the showdown reveals the cards of a player who folded
+    PlayerFolded { table_id: u32, player: String },
+
+    #[error("Contract paused")]
+    // issued when a hand is started while the owner paused the contract
+    ContractPaused {},
+
+    #[error("Table {table_id} reached its limit of {max_hands} hands")]
+    // issued when a hand is started on a table that already dealt its max_hands, until an EndHand
+    TableHandLimitReached { table_id: u32, max_hands: u32 },
+
+    #[error("Hand {hand_ref} of table {table_id} already has a game result")]
+    // issued when a hand_ref is reused on a table, its game result would overwrite the earlier one
+    HandRefAlreadyUsed { table_id: u32, hand_ref: u32 },
+
+    #[error("Expected hand {expected}, table is on hand {actual}")]
+    // issued when a message targets a hand_ref that is no longer the table's current hand
+    HandRefMismatch { expected: u32, actual: u32 },
+
+    #[error("Invalid stakes: {reason}")]
+    // issued when the recorded stakes of a hand are inconsistent
+    InvalidStakes { reason: String },
+
+    #[error("No secrets provided")]
+    // issued when a showdown query holds neither a player secret nor a community secret, it would reveal nothing
+    NoSecretsProvided {},
+
+    #[error("Invalid batch of {size} games, expected 1 to {max}")]
+    // issued when a BulkStartGame is empty or exceeds the batch size cap
+    InvalidBatchSize { size: usize, max: usize },
+
+    #[error("Secret mismatch for street {street}")]
+    // issued when a query's phase secret doesn't match the secret of that street, street is GameState::as_str
+    SecretMismatch { street: String },
+
+    #[error("Deck of {available} cards can't deal the {required} cards of the hand")]
+    // issued when the hole cards, burns and board of a hand exceed the cards of its deck variant
+    InsufficientDeck { required: usize, available: usize },
+
+    #[error("Street {street} of table {table_id} not retrieved yet")]
+    // issued when re-reading a street the owner didn't reveal yet, street is GameState::as_str
+    CardsNotRetrieved { table_id: u32, street: String },
+
+    #[error("Invalid player limits {min} to {max}")]
+    // issued when the instantiated player bounds are inverted or outside of MIN_PLAYERS to MAX_PLAYERS
+    InvalidPlayerLimits { min: usize, max: usize },
+
+    #[error("Out of order reveal: expected {expected:?}, got {got:?}")]
+    // issued when a street is revealed before the previous one, or after the showdown
+    OutOfOrderReveal { expected: GameState, got: GameState },
+
+    #[error("Invalid address {address}")]
+    // issued when an address given to the contract isn't a valid, normalized address of the chain
+    InvalidAddress { address: String },
+
+    #[error("Shuffle retries {max_retries} above the maximum of {max}")]
+    // issued when a StartGame asks for a rejection sampling cap above MAX_SHUFFLE_RETRIES
+    InvalidShuffleRetries { max_retries: u64, max: u64 },
+}
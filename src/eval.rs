@@ -0,0 +1,447 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::state::{Card, DeckVariant, Rank};
+
+const ACE_HIGH: u8 = 14;
+const HAND_SIZE: usize = 5;
+
+/*
+ * Value of a five cards poker hand, ranks are ace-high (2..=14) so that two HandRank can be compared directly.
+ * Categories are declared from weakest to strongest and the derived Ord compares the category first,
+ * then the tiebreak ranks in declaration order, kickers being sorted from highest to lowest.
+ * Missing kickers (hands of less than five cards) are 0.
+ * The derived Ord is the standard ranking, use HandRank::compare to rank hands of a given deck variant.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum HandRank {
+    HighCard { kickers: [u8; 5] },
+    Pair { pair: u8, kickers: [u8; 3] },
+    TwoPair { high_pair: u8, low_pair: u8, kicker: u8 },
+    ThreeOfAKind { trips: u8, kickers: [u8; 2] },
+    Straight { high: u8 },
+    Flush { kickers: [u8; 5] },
+    FullHouse { trips: u8, pair: u8 },
+    FourOfAKind { quads: u8, kicker: u8 },
+    StraightFlush { high: u8 },
+}
+
+impl HandRank {
+    // the ranks compared once the categories are equal, in the order the derived Ord compares them
+    fn tiebreaks(&self) -> Vec<u8> {
+        match self {
+            HandRank::HighCard { kickers } | HandRank::Flush { kickers } => kickers.to_vec(),
+            HandRank::Pair { pair, kickers } => [*pair].into_iter().chain(*kickers).collect(),
+            HandRank::TwoPair { high_pair, low_pair, kicker } => vec![*high_pair, *low_pair, *kicker],
+            HandRank::ThreeOfAKind { trips, kickers } => [*trips].into_iter().chain(*kickers).collect(),
+            HandRank::Straight { high } | HandRank::StraightFlush { high } => vec![*high],
+            HandRank::FullHouse { trips, pair } => vec![*trips, *pair],
+            HandRank::FourOfAKind { quads, kicker } => vec![*quads, *kicker],
+        }
+    }
+
+    /*
+     * Orders two hands dealt from the given deck. With the short deck a flush is rarer than a full house
+     * and beats it, every other category keeps its standard order.
+     */
+    pub fn compare(&self, other: &HandRank, deck_variant: &DeckVariant) -> Ordering {
+        match deck_variant {
+            DeckVariant::Standard => self.cmp(other),
+            DeckVariant::Short => match (self, other) {
+                (HandRank::Flush { .. }, HandRank::FullHouse { .. }) => Ordering::Greater,
+                (HandRank::FullHouse { .. }, HandRank::Flush { .. }) => Ordering::Less,
+                _ => self.cmp(other),
+            },
+        }
+    }
+}
+
+/*
+ * Why a hand beat another hand of the same category: the winner's ranks compared until the first one that differs,
+ * the last rank being the deciding one. None when the categories differ, as the category alone decides, or on a tie.
+ */
+pub fn tie_broken_by(winner: &HandRank, other: &HandRank) -> Option<Vec<Rank>> {
+    if std::mem::discriminant(winner) != std::mem::discriminant(other) {
+        return None;
+    }
+
+    let winner_ranks = winner.tiebreaks();
+    let deciding = winner_ranks.iter().zip(other.tiebreaks()).position(|(winner, other)| *winner != other)?;
+    Some(winner_ranks[..=deciding].iter().map(|&value| Rank::from_value(value)).collect())
+}
+
+/*
+ * Best five cards hand out of the hole cards and the community cards (up to seven cards), with the rules of the deck
+ * they were dealt from. Every five cards combination is evaluated, which is at most 21 evaluations for seven cards.
+ */
+pub fn best_hand(hole: &[Card], community: &[Card], deck_variant: &DeckVariant) -> HandRank {
+    let cards: Vec<Card> = hole.iter().chain(community.iter()).cloned().collect();
+    let hand_size = cards.len().min(HAND_SIZE);
+
+    combinations(cards.len(), hand_size)
+        .into_iter()
+        .map(|indexes| {
+            let hand: Vec<Card> = indexes.iter().map(|&i| cards[i].clone()).collect();
+            evaluate(&hand, deck_variant)
+        })
+        .max_by(|a, b| a.compare(b, deck_variant))
+        .unwrap_or(HandRank::HighCard { kickers: [0; 5] })
+}
+
+/*
+ * Ranks the players' hands against the same board, from the best hand to the worst.
+ * Players with exactly tied hands are grouped together, so the first group holds every co-winner.
+ */
+pub fn rank_hands(
+    players_cards: &[(Uuid, Vec<Card>)],
+    community: &[Card],
+    deck_variant: &DeckVariant,
+) -> Vec<Vec<(Uuid, HandRank)>> {
+    let mut ranked: Vec<(Uuid, HandRank)> = players_cards
+        .iter()
+        .map(|(player_id, hole)| (*player_id, best_hand(hole, community, deck_variant)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.compare(&a.1, deck_variant));
+
+    let mut groups: Vec<Vec<(Uuid, HandRank)>> = Vec::new();
+    for (player_id, hand_rank) in ranked {
+        match groups.last_mut() {
+            Some(group) if group[0].1 == hand_rank => group.push((player_id, hand_rank)),
+            _ => groups.push(vec![(player_id, hand_rank)]),
+        }
+    }
+    groups
+}
+
+fn evaluate(hand: &[Card], deck_variant: &DeckVariant) -> HandRank {
+    let mut ranks: Vec<u8> = hand.iter().map(Card::rank_value).collect();
+    ranks.sort_unstable_by(|a, b| b.cmp(a));
+
+    let is_flush = hand.len() == HAND_SIZE && hand.iter().all(|card| card.same_suit(&hand[0]));
+    let straight_high = straight_high(&ranks, deck_variant);
+
+    // (count, rank) sorted so that the biggest groups come first, then the highest ranks
+    let mut groups: Vec<(usize, u8)> = Vec::new();
+    for &rank in ranks.iter() {
+        match groups.iter_mut().find(|(_, r)| *r == rank) {
+            Some(group) => group.0 += 1,
+            None => groups.push((1, rank)),
+        }
+    }
+    groups.sort_unstable_by(|a, b| b.cmp(a));
+
+    let kickers: Vec<u8> = groups.iter().filter(|(count, _)| *count == 1).map(|(_, rank)| *rank).collect();
+
+    match (straight_high, is_flush, groups.as_slice()) {
+        (Some(high), true, _) => HandRank::StraightFlush { high },
+        (_, _, [(4, quads), ..]) => HandRank::FourOfAKind {
+            quads: *quads,
+            kicker: padded::<1>(&kickers)[0],
+        },
+        (_, _, [(3, trips), (2, pair), ..]) => HandRank::FullHouse {
+            trips: *trips,
+            pair: *pair,
+        },
+        (_, true, _) => HandRank::Flush {
+            kickers: padded(&ranks),
+        },
+        (Some(high), _, _) => HandRank::Straight { high },
+        (_, _, [(3, trips), ..]) => HandRank::ThreeOfAKind {
+            trips: *trips,
+            kickers: padded(&kickers),
+        },
+        (_, _, [(2, high_pair), (2, low_pair), ..]) => HandRank::TwoPair {
+            high_pair: *high_pair,
+            low_pair: *low_pair,
+            kicker: padded::<1>(&kickers)[0],
+        },
+        (_, _, [(2, pair), ..]) => HandRank::Pair {
+            pair: *pair,
+            kickers: padded(&kickers),
+        },
+        _ => HandRank::HighCard {
+            kickers: padded(&ranks),
+        },
+    }
+}
+
+/*
+ * Highest card of the straight formed by the (descending) ranks, if any.
+ * The wheel (A-2-3-4-5) is the lowest straight, its high card is the 5.
+ * The short deck has no 2 to 5, the ace plays low in A-6-7-8-9 instead, its high card is the 9.
+ */
+fn straight_high(ranks: &[u8], deck_variant: &DeckVariant) -> Option<u8> {
+    if ranks.len() != HAND_SIZE || ranks.windows(2).any(|pair| pair[0] == pair[1]) {
+        return None;
+    }
+
+    if ranks[0] - ranks[HAND_SIZE - 1] == 4 {
+        Some(ranks[0])
+    } else if ranks == [ACE_HIGH, 5, 4, 3, 2] && *deck_variant == DeckVariant::Standard {
+        Some(5)
+    } else if ranks == [ACE_HIGH, 9, 8, 7, 6] && *deck_variant == DeckVariant::Short {
+        Some(9)
+    } else {
+        None
+    }
+}
+
+fn padded<const N: usize>(ranks: &[u8]) -> [u8; N] {
+    let mut padded = [0u8; N];
+    for (slot, rank) in padded.iter_mut().zip(ranks.iter()) {
+        *slot = *rank;
+    }
+    padded
+}
+
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    if k == 0 {
+        return vec![vec![]];
+    }
+    if n < k {
+        return vec![];
+    }
+
+    // Combinations including the last index, then the ones without it
+    let mut result: Vec<Vec<usize>> = combinations(n - 1, k - 1)
+        .into_iter()
+        .map(|mut combination| {
+            combination.push(n - 1);
+            combination
+        })
+        .collect();
+    result.extend(combinations(n - 1, k));
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cards(cards: &str) -> Vec<Card> {
+        cards.split_whitespace().map(|card| Card::from_string(card).unwrap()).collect()
+    }
+
+    fn rank(hole: &str, community: &str) -> HandRank {
+        best_hand(&cards(hole), &cards(community), &DeckVariant::Standard)
+    }
+
+    fn short_deck_rank(hole: &str, community: &str) -> HandRank {
+        best_hand(&cards(hole), &cards(community), &DeckVariant::Short)
+    }
+
+    #[test]
+    fn categories() {
+        assert_eq!(
+            rank("♠A ♥K", "♦9 ♣7 ♠4 ♥3 ♦2"),
+            HandRank::HighCard { kickers: [14, 13, 9, 7, 4] }
+        );
+        assert_eq!(
+            rank("♠A ♥A", "♦9 ♣7 ♠4 ♥3 ♦2"),
+            HandRank::Pair { pair: 14, kickers: [9, 7, 4] }
+        );
+        assert_eq!(
+            rank("♠A ♥A", "♦9 ♣9 ♠4 ♥4 ♦2"),
+            HandRank::TwoPair { high_pair: 14, low_pair: 9, kicker: 4 }
+        );
+        assert_eq!(
+            rank("♠7 ♥7", "♦7 ♣K ♠4 ♥3 ♦Q"),
+            HandRank::ThreeOfAKind { trips: 7, kickers: [13, 12] }
+        );
+        assert_eq!(
+            rank("♠10 ♥J", "♦Q ♣K ♠A ♥3 ♦2"),
+            HandRank::Straight { high: 14 }
+        );
+        assert_eq!(
+            rank("♥2 ♥9", "♥J ♥4 ♥K ♠K ♦Q"),
+            HandRank::Flush { kickers: [13, 11, 9, 4, 2] }
+        );
+        assert_eq!(
+            rank("♠8 ♥8", "♦8 ♣K ♠K ♥3 ♦2"),
+            HandRank::FullHouse { trips: 8, pair: 13 }
+        );
+        assert_eq!(
+            rank("♠8 ♥8", "♦8 ♣8 ♠K ♥3 ♦2"),
+            HandRank::FourOfAKind { quads: 8, kicker: 13 }
+        );
+        assert_eq!(
+            rank("♣9 ♣10", "♣J ♣Q ♣K ♥3 ♦2"),
+            HandRank::StraightFlush { high: 13 }
+        );
+    }
+
+    #[test]
+    fn categories_order() {
+        let ordered = [
+            rank("♠A ♥K", "♦9 ♣7 ♠4 ♥3 ♦2"),
+            rank("♠2 ♥2", "♦9 ♣7 ♠4 ♥3 ♦K"),
+            rank("♠2 ♥2", "♦3 ♣3 ♠4 ♥6 ♦8"),
+            rank("♠2 ♥2", "♦2 ♣3 ♠4 ♥6 ♦8"),
+            rank("♠A ♥2", "♦3 ♣4 ♠5 ♥J ♦J"),
+            rank("♥2 ♥3", "♥4 ♥5 ♥7 ♠K ♦K"),
+            rank("♠2 ♥2", "♦2 ♣3 ♠3 ♥6 ♦8"),
+            rank("♠2 ♥2", "♦2 ♣2 ♠3 ♥6 ♦8"),
+            rank("♥A ♥2", "♥3 ♥4 ♥5 ♠K ♦K"),
+        ];
+
+        for pair in ordered.windows(2) {
+            assert!(pair[0] < pair[1], "{:?} should be lower than {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn wheel() {
+        assert_eq!(rank("♠A ♥2", "♦3 ♣4 ♠5 ♥J ♦Q"), HandRank::Straight { high: 5 });
+        assert_eq!(rank("♥A ♥2", "♥3 ♥4 ♥5 ♠J ♦Q"), HandRank::StraightFlush { high: 5 });
+
+        // The wheel is the lowest straight, and a six-high straight beats it
+        assert!(rank("♠A ♥2", "♦3 ♣4 ♠5 ♥J ♦Q") < rank("♠6 ♥2", "♦3 ♣4 ♠5 ♥J ♦Q"));
+        assert!(rank("♠A ♥2", "♦3 ♣4 ♠5 ♥J ♦Q") > rank("♠K ♥K", "♦K ♣4 ♠5 ♥J ♦Q"));
+
+        // A-K-Q-J-10 is the highest straight, K-A-2-3-4 is not a straight
+        assert_eq!(rank("♠A ♥K", "♦Q ♣J ♠10"), HandRank::Straight { high: 14 });
+        assert_eq!(
+            rank("♠K ♥A", "♦2 ♣3 ♠4"),
+            HandRank::HighCard { kickers: [14, 13, 4, 3, 2] }
+        );
+    }
+
+    #[test]
+    fn best_five_of_seven() {
+        // The 6-high straight uses the board 2-3-4-5 rather than the ace
+        assert_eq!(rank("♠A ♥6", "♦2 ♣3 ♠4 ♥5 ♦K"), HandRank::Straight { high: 6 });
+        // Three pairs, the lowest one is dropped and the kicker is the best remaining card
+        assert_eq!(
+            rank("♠Q ♥Q", "♦9 ♣9 ♠4 ♥4 ♦8"),
+            HandRank::TwoPair { high_pair: 12, low_pair: 9, kicker: 8 }
+        );
+        // Two trips make a full house with the highest trips
+        assert_eq!(
+            rank("♠Q ♥Q", "♦Q ♣9 ♠9 ♥9 ♦8"),
+            HandRank::FullHouse { trips: 12, pair: 9 }
+        );
+        // Six cards of the same suit, only the five highest count
+        assert_eq!(
+            rank("♥2 ♥A", "♥3 ♥10 ♥J ♥7 ♠K"),
+            HandRank::Flush { kickers: [14, 11, 10, 7, 3] }
+        );
+    }
+
+    #[test]
+    fn ties_and_kickers() {
+        // Same hand with different suits is a tie
+        assert_eq!(
+            rank("♠A ♥K", "♦Q ♣Q ♠7 ♥5 ♦2"),
+            rank("♣A ♦K", "♦Q ♣Q ♠7 ♥5 ♦2")
+        );
+        // Board plays for both players
+        assert_eq!(
+            rank("♠2 ♥3", "♦A ♣K ♠Q ♥J ♦10"),
+            rank("♣4 ♦5", "♦A ♣K ♠Q ♥J ♦10")
+        );
+
+        assert!(rank("♠A ♥K", "♦Q ♣Q ♠7 ♥5 ♦2") > rank("♣A ♦J", "♦Q ♣Q ♠7 ♥5 ♦2"));
+        assert!(rank("♠A ♥A", "♦K ♣K ♠7 ♥5 ♦2") > rank("♣K ♦7", "♥K ♣A ♠7 ♥5 ♦2"));
+        assert!(rank("♠9 ♥9", "♦9 ♣K ♠K ♥5 ♦2") > rank("♣8 ♦8", "♥8 ♣A ♠A ♥5 ♦2"));
+        assert!(rank("♠9 ♥9", "♦9 ♣9 ♠K ♥5 ♦2") > rank("♣9 ♦9", "♥9 ♠9 ♠Q ♥5 ♦2"));
+        assert!(rank("♥A ♥3", "♥4 ♥5 ♥7 ♠K ♦K") > rank("♥K ♥Q", "♥4 ♥5 ♥7 ♠K ♦K"));
+    }
+
+    #[test]
+    fn tie_breaks() {
+        // Both players hold a flush with the same four highest cards, the fifth decides
+        let board = "♥A ♥K ♥9 ♥7 ♠2";
+        let winner = rank("♥5 ♣Q", board);
+        let other = rank("♥4 ♣J", board);
+        assert!(winner > other);
+        assert_eq!(
+            tie_broken_by(&winner, &other),
+            Some(vec![Rank::Ace, Rank::King, Rank::Nine, Rank::Seven, Rank::Five])
+        );
+
+        // Same pair, decided by the second kicker
+        let board = "♦Q ♣Q ♠7 ♥5 ♦2";
+        assert_eq!(
+            tie_broken_by(&rank("♠A ♥K", board), &rank("♣A ♦J", board)),
+            Some(vec![Rank::Queen, Rank::Ace, Rank::King])
+        );
+        assert_eq!(
+            tie_broken_by(&rank("♠A ♥A", "♦9 ♣7 ♠4 ♥3 ♦2"), &rank("♠K ♥K", "♦9 ♣7 ♠4 ♥3 ♦2")),
+            Some(vec![Rank::Ace])
+        );
+
+        // The category alone decides, and tied hands have nothing to break
+        assert_eq!(tie_broken_by(&rank("♠A ♥A", board), &rank("♠A ♥K", board)), None);
+        assert_eq!(tie_broken_by(&rank("♠A ♥K", board), &rank("♣A ♦K", board)), None);
+    }
+
+    #[test]
+    fn ranked_hands_with_ties() {
+        let board = cards("♦A ♣K ♠7 ♥5 ♦2");
+        let players = vec![
+            (Uuid::from_u128(1), cards("♠3 ♥4")),
+            (Uuid::from_u128(2), cards("♠A ♥Q")),
+            (Uuid::from_u128(3), cards("♣A ♦Q")),
+            (Uuid::from_u128(4), cards("♠K ♥K")),
+        ];
+
+        let groups = rank_hands(&players, &board, &DeckVariant::Standard);
+        let ids: Vec<Vec<u128>> = groups
+            .iter()
+            .map(|group| group.iter().map(|(player_id, _)| player_id.as_u128()).collect())
+            .collect();
+        assert_eq!(ids, vec![vec![1], vec![4], vec![2, 3]]);
+        assert_eq!(groups[0][0].1, HandRank::Straight { high: 5 });
+        assert_eq!(groups[1][0].1, HandRank::ThreeOfAKind { trips: 13, kickers: [14, 7] });
+        assert_eq!(groups[2][0].1, groups[2][1].1);
+
+        // Board plays for everyone, all players split
+        let board = cards("♦A ♣K ♠Q ♥J ♦10");
+        let groups = rank_hands(&players[..3], &board, &DeckVariant::Standard);
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 3);
+
+        assert!(rank_hands(&[], &board, &DeckVariant::Standard).is_empty());
+    }
+
+    #[test]
+    fn short_deck() {
+        // The ace plays low in A-6-7-8-9, the lowest short deck straight
+        assert_eq!(short_deck_rank("♠A ♥6", "♦7 ♣8 ♠9 ♥K ♦Q"), HandRank::Straight { high: 9 });
+        assert_eq!(short_deck_rank("♥A ♥6", "♥7 ♥8 ♥9 ♠K ♦Q"), HandRank::StraightFlush { high: 9 });
+        assert!(short_deck_rank("♠A ♥6", "♦7 ♣8 ♠9 ♥K ♦Q") < short_deck_rank("♠10 ♥6", "♦7 ♣8 ♠9 ♥K ♦Q"));
+        assert_eq!(
+            rank("♠A ♥6", "♦7 ♣8 ♠9 ♥K ♦Q"),
+            HandRank::HighCard { kickers: [14, 13, 12, 9, 8] }
+        );
+
+        // A flush beats a full house, a full house still beats a straight
+        let flush = short_deck_rank("♥6 ♥9", "♥J ♥7 ♥K ♠K ♦Q");
+        let full_house = short_deck_rank("♠8 ♥8", "♦8 ♣K ♠K ♥7 ♦6");
+        let straight = short_deck_rank("♠10 ♥J", "♦Q ♣K ♠A ♥7 ♦6");
+        assert_eq!(flush.compare(&full_house, &DeckVariant::Short), Ordering::Greater);
+        assert_eq!(full_house.compare(&straight, &DeckVariant::Short), Ordering::Greater);
+        assert_eq!(flush.compare(&full_house, &DeckVariant::Standard), Ordering::Less);
+
+        let board = cards("♥J ♥7 ♥K ♦K ♣8");
+        let players = vec![
+            (Uuid::from_u128(1), cards("♠8 ♥8")),
+            (Uuid::from_u128(2), cards("♥6 ♥9")),
+        ];
+        let groups = rank_hands(&players, &board, &DeckVariant::Short);
+        assert_eq!(groups[0][0].0, Uuid::from_u128(2));
+        let groups = rank_hands(&players, &board, &DeckVariant::Standard);
+        assert_eq!(groups[0][0].0, Uuid::from_u128(1));
+    }
+
+    #[test]
+    fn fewer_than_five_cards() {
+        assert_eq!(rank("♠A ♥A", ""), HandRank::Pair { pair: 14, kickers: [0, 0, 0] });
+        assert_eq!(rank("♠A ♥K", ""), HandRank::HighCard { kickers: [14, 13, 0, 0, 0] });
+        assert_eq!(rank("", ""), HandRank::HighCard { kickers: [0; 5] });
+    }
+}
@@ -1,5 +1,6 @@
 pub mod contract;
 mod error;
+pub mod eval;
 pub mod msg;
 pub mod state;
 
@@ -1,15 +1,27 @@
-use cosmwasm_std::Timestamp;
+use std::io::{Read, Write};
+
+use cosmwasm_std::{Addr, Binary, StdError, StdResult, Timestamp};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use secret_toolkit_permit::Permit;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::state::{Card, GameState};
+use crate::eval::HandRank;
+use crate::state::{Card, DeckVariant, GameState, HandLink, Rank, SharingScheme, Stakes};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
 pub struct InstantiateMsg {
-
+    #[serde(default)]
+    pub strict_one_time_reveal: bool, // reject phase secrets in queries, RetrievedCommunityCards included, once the phase was revealed by the owner
+    #[serde(default)]
+    pub min_players: Option<usize>, // players a hand needs at least, defaults to MIN_PLAYERS
+    #[serde(default)]
+    pub max_players: Option<usize>, // players a hand seats at most, e.g. 6 for 6-max tables, defaults to MAX_PLAYERS
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct StartGamePlayer {
     pub username: String,
@@ -20,22 +32,128 @@ pub struct StartGamePlayer {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    StartGame {
-        table_id: u32,
-        hand_ref: u32,
-        players: Vec<StartGamePlayer>,
-        prev_hand_showdown_players: Vec<Uuid>, // player_ids of players who showed their cards in the last hand
+    StartGame(StartGameArgs),
+    /*
+     * Starts a hand on each table in a single transaction, in order. The batch fails as a whole
+     * on the first game that would fail alone, and holds at most MAX_BULK_START_GAMES games.
+     */
+    BulkStartGame {
+        games: Vec<StartGameArgs>,
     },
     CommunityCards {
         table_id: u32,
         game_state: GameState,
+        #[serde(default)]
+        expected_hand_ref: Option<u32>, // rejects the message if another hand started on the table in between
     },
     Showdown {
         table_id: u32,
         game_state: GameState,
         showdown_player_ids: Vec<Uuid>, // player_ids of players whos cards are shown
+        #[serde(default)]
+        expected_hand_ref: Option<u32>, // see CommunityCards
+    },
+    AdvancePhase {
+        table_id: u32, // reveals the street following the table's current phase
+    },
+    EndHand {
+        table_id: u32,
+        #[serde(default)]
+        showdown_player_ids: Vec<Uuid>, // player_ids of players who showed their cards, included in the final hand log
+    },
+    /*
+     * Two-step ownership transfer: the owner proposes a new owner, who becomes owner once it accepts,
+     * so a mistyped address can't lock the contract.
+     */
+    TransferOwnership {
+        new_owner: Addr,
+    },
+    AcceptOwnership {},
+    AddDealer {
+        dealer: Addr,
+    },
+    RemoveDealer {
+        dealer: Addr,
+    },
+    /*
+     * Owner only, for maintenance: while paused no new hand can start,
+     * the hands in progress still reveal their streets and showdown.
+     */
+    SetPaused {
+        paused: bool,
+    },
+    // open to anyone, revokes the sender's permit with that name
+    RevokePermit {
+        permit_name: String,
     },
+    /*
+     * For a disconnected player, the contract completes CommunityCardsFromShares with the player's stored shares
+     * so the remaining players can still reconstruct the secrets.
+     */
+    MarkPlayerInactive {
+        table_id: u32,
+        player_id: Uuid,
+    },
+    // a folded player's cards can't be revealed by the Showdown of the hand
+    Fold {
+        table_id: u32,
+        player_id: Uuid,
+    },
+    /*
+     * Owner only, to recover a table left inconsistent: its hand is archived in the hand history marked as corrupted,
+     * without a game result, and a fresh hand is dealt at the same id with the default StartGame settings,
+     * even while the contract is paused. The table keeps its max_hands and the new hand replaces the broken one in its count.
+     */
+    RebuildTable {
+        table_id: u32,
+        players: Vec<StartGamePlayer>,
+        hand_ref: u32,
+    },
+}
+// a hand to start, by StartGame or as one of the games of a BulkStartGame
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct StartGameArgs {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub players: Vec<StartGamePlayer>,
+    pub prev_hand_showdown_players: Vec<Uuid>, // player_ids of players who showed their cards in the last hand
+    #[serde(default)]
+    pub compress_hand_log: bool, // gzip + base64 the previous hand log, see CompressedResponse
+    #[serde(default)]
+    pub shuffle_max_retries: Option<u64>, // rejection sampling cap of the shuffle, defaults to SHUFFLE_MAX_RETRIES, at most MAX_SHUFFLE_RETRIES
+    #[serde(default)]
+    pub table_intent: TableIntent,
+    #[serde(default)]
+    pub phases: Option<u8>, // community card phases dealt (1 = flop only), defaults to COMMUNITY_CARD_PHASES
+    #[serde(default)]
+    pub sharing_scheme: SharingScheme, // how the community card secrets are split, see SharingScheme
+    #[serde(default)]
+    pub button_seat: Option<u32>, // index in players of the dealer button, rotated from the previous hand by default
+    #[serde(default)]
+    pub deck_variant: DeckVariant,
+    /*
+     * Hands the table id can deal before StartGame is refused until an EndHand deletes it,
+     * counted by the contract since hand_ref is the caller's. Defaults to the table's previous limit.
+     */
+    #[serde(default)]
+    pub max_hands: Option<u32>,
+    #[serde(default)]
+    pub stakes: Option<Stakes>, // recorded in the hand log, blinds and ante must be nonzero
+}
+
+/*
+ * Whether a StartGame expects to open a new table or to deal the next hand of an existing one.
+ * Any keeps the historical behavior: the table is created if absent and overwritten otherwise.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TableIntent {
+    #[default]
+    Any,
+    New,
+    Continue,
 }
+
 /*
 * The secrets are sent as strings because javascript is using 53-bit integers. 
 * Note that they are also sent by the contract as strings, so they can be parsed to BigInt in javascript easily.
@@ -50,75 +168,481 @@ pub enum QueryMsg {
     CommunityCards { 
         table_id: u32, 
         game_state: GameState, 
-        #[serde(deserialize_with = "string_to_u64")]
-        secret_key: u64 
+        #[serde(deserialize_with = "string_to_u128")]
+        secret_key: u128 
+    },
+    /*
+     * Re-reads a street the owner already revealed with CommunityCards, e.g. after losing the execute response.
+     * retrieved_at is left untouched. With strict_one_time_reveal the reveal consumed the phase secret,
+     * so the re-read is refused like every other query holding it.
+     */
+    RetrievedCommunityCards {
+        table_id: u32,
+        game_state: GameState,
+        #[serde(deserialize_with = "string_to_u128")]
+        secret_key: u128,
+    },
+    // every street whose secret is given, streets without a secret are skipped
+    AllCommunityCards {
+        table_id: u32,
+        #[serde(default, deserialize_with = "string_to_option_u128")]
+        flop_secret: Option<u128>,
+        #[serde(default, deserialize_with = "string_to_option_u128")]
+        turn_secret: Option<u128>,
+        #[serde(default, deserialize_with = "string_to_option_u128")]
+        river_secret: Option<u128>,
     },
     Showdown { 
         table_id: u32, 
-        #[serde(deserialize_with = "string_to_option_u64")]
-        flop_secret: Option<u64>,
-        #[serde(deserialize_with = "string_to_option_u64")]
-        turn_secret: Option<u64>,
-        #[serde(deserialize_with = "string_to_option_u64")]
-        river_secret: Option<u64>,
-        #[serde(deserialize_with = "vec_string_to_vec_u64")]
-        players_secrets: Vec<u64>,
-    }
+        #[serde(deserialize_with = "string_to_option_u128")]
+        flop_secret: Option<u128>,
+        #[serde(deserialize_with = "string_to_option_u128")]
+        turn_secret: Option<u128>,
+        #[serde(deserialize_with = "string_to_option_u128")]
+        river_secret: Option<u128>,
+        #[serde(deserialize_with = "vec_string_to_vec_u128")]
+        players_secrets: Vec<u128>,
+    },
+    // a single player's hand, from the hand_secret the player revealed
+    PlayerHand {
+        table_id: u32,
+        #[serde(deserialize_with = "string_to_u128")]
+        hand_secret: u128,
+    },
+    Metrics {},
+    // the crate name and the version stored by the last instantiate or migrate, e.g. for a client to check compatibility
+    Version {},
+    // public, non-sensitive: the owner, contract address and admin settings, e.g. to check a TransferOwnership
+    Config {},
+    // public metadata of the table, never any card or secret
+    TableState {
+        table_id: u32,
+    },
+    /*
+     * Whether the owner already retrieved the showdown and each street of the hand, without retrieving anything:
+     * a recovering client re-sends only what is still missing and reads the rest from the hand log.
+     */
+    ShowdownStatus {
+        table_id: u32,
+    },
+    // the deck the table's hand was shuffled from, in its pre-shuffle order, to replay VerifyShuffle against
+    DeckVariant {
+        table_id: u32,
+    },
+    /*
+     * Public as soon as the hand is dealt: the hash commits the contract to the shuffled deck,
+     * whose preimage is only revealed after the showdown by VerifyShuffle.
+     */
+    DeckCommitment {
+        table_id: u32,
+    },
+    /*
+     * Table id no hand was ever started on, querying doesn't reserve it.
+     * Start the hand with TableIntent::New: if another backend took the id in between,
+     * StartGame fails with TableAlreadyExists (or TableIdReserved once that table is deleted)
+     * instead of HandInProgress or overwriting its table, query again and retry.
+     */
+    NextTableId {},
+    // stored tables by ascending table_id, start_after is the last table_id of the previous page
+    Tables {
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    HandChain {
+        table_id: u32,
+    },
+    /*
+     * Logs of the completed hands of a table, oldest first.
+     * start_after is the next_start_after of the previous page, the history index of its last hand.
+     * With compress the page is returned as a ResponsePayload of type "compressed", see CompressedResponse.
+     */
+    HandHistory {
+        table_id: u32,
+        limit: Option<u32>,
+        start_after: Option<u32>,
+        #[serde(default)]
+        compress: bool,
+    },
+    GameResult {
+        table_id: u32,
+        hand_ref: u32,
+    },
+    /*
+     * Seed and shuffled deck of the hand, so an auditor can re-run shuffle_deck offline and compare the deal.
+     * Only available once the showdown is retrieved, the seed reveals every card.
+     */
+    VerifyShuffle {
+        table_id: u32,
+    },
+    /*
+     * Evaluates the hands revealed by the given secrets, with the same secret checks as the Showdown query.
+     * If some streets are not revealed (all-in before the river), the hands are evaluated with the revealed board only.
+     */
+    Winners {
+        table_id: u32,
+        #[serde(deserialize_with = "string_to_option_u128")]
+        flop_secret: Option<u128>,
+        #[serde(deserialize_with = "string_to_option_u128")]
+        turn_secret: Option<u128>,
+        #[serde(deserialize_with = "string_to_option_u128")]
+        river_secret: Option<u128>,
+        #[serde(deserialize_with = "vec_string_to_vec_u128")]
+        players_secrets: Vec<u128>,
+    },
+    // the Showdown query and the winners of the revealed hands in one call, with the same secret checks
+    ShowdownWithWinners {
+        table_id: u32,
+        #[serde(deserialize_with = "string_to_option_u128")]
+        flop_secret: Option<u128>,
+        #[serde(deserialize_with = "string_to_option_u128")]
+        turn_secret: Option<u128>,
+        #[serde(deserialize_with = "string_to_option_u128")]
+        river_secret: Option<u128>,
+        #[serde(deserialize_with = "vec_string_to_vec_u128")]
+        players_secrets: Vec<u128>,
+    },
+    /*
+     * Same as CommunityCards, but the contract reconstructs the secret from the players' shares.
+     * Shares are deduplicated by (player_id, game_state), so a share submitted twice is only counted once.
+     * The shares of players marked inactive by the owner are completed from storage.
+     */
+    CommunityCardsFromShares {
+        table_id: u32,
+        game_state: GameState,
+        shares: Vec<PlayerShare>,
+    },
+    /*
+     * Wrapping sum of the given players' shares for a phase, to cross-check shares collected off-chain.
+     * Only available once the showdown is retrieved, since individual shares must stay private during the hand.
+     */
+    PartialSum {
+        table_id: u32,
+        game_state: GameState,
+        player_ids: Vec<Uuid>,
+    },
 }
 
-fn string_to_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerShare {
+    pub player_id: Uuid,
+    pub game_state: GameState,
+    #[serde(deserialize_with = "string_to_u128")]
+    pub share: u128,
+}
+
+fn string_to_u128<'de, D>(deserializer: D) -> Result<u128, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    s.parse::<u64>().map_err(serde::de::Error::custom)
+    s.parse::<u128>().map_err(serde::de::Error::custom)
 }
 
-fn string_to_option_u64<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+fn string_to_option_u128<'de, D>(deserializer: D) -> Result<Option<u128>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
     let s = Option::<String>::deserialize(deserializer)?;
     match s {
-        Some(s) => s.parse::<u64>().map(Some).map_err(serde::de::Error::custom),
+        Some(s) => s.parse::<u128>().map(Some).map_err(serde::de::Error::custom),
         None => Ok(None),
     }
 }
 
-fn vec_string_to_vec_u64<'de, D>(deserializer: D) -> Result<Vec<u64>, D::Error>
+fn vec_string_to_vec_u128<'de, D>(deserializer: D) -> Result<Vec<u128>, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
     let vec = Vec::<String>::deserialize(deserializer)?;
     vec.into_iter()
-        .map(|s| s.parse::<u64>().map_err(serde::de::Error::custom))
+        .map(|s| s.parse::<u128>().map_err(serde::de::Error::custom))
         .collect()
 }
 
+/*
+ * Human form of a card in the responses, "♠A" instead of the packed byte kept in storage.
+ * Response fields holding cards keep the Card type and go through card_string.
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CardView(pub Card);
+
+impl From<Card> for CardView {
+    fn from(card: Card) -> Self {
+        CardView(card)
+    }
+}
+
+impl Serialize for CardView {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for CardView {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Card::from_string(&s).map(CardView).map_err(serde::de::Error::custom)
+    }
+}
+
+// shapes of the response fields holding cards, mapped to the same shape of CardView
+pub trait CardStrings: Sized {
+    type View: Serialize + for<'de> Deserialize<'de>;
+
+    fn to_view(&self) -> Self::View;
+    fn from_view(view: Self::View) -> Self;
+}
+
+impl CardStrings for Card {
+    type View = CardView;
+
+    fn to_view(&self) -> CardView {
+        CardView(self.clone())
+    }
+
+    fn from_view(view: CardView) -> Self {
+        view.0
+    }
+}
+
+impl<T: CardStrings> CardStrings for Vec<T> {
+    type View = Vec<T::View>;
+
+    fn to_view(&self) -> Self::View {
+        self.iter().map(CardStrings::to_view).collect()
+    }
+
+    fn from_view(view: Self::View) -> Self {
+        view.into_iter().map(T::from_view).collect()
+    }
+}
+
+impl<T: CardStrings> CardStrings for Option<T> {
+    type View = Option<T::View>;
+
+    fn to_view(&self) -> Self::View {
+        self.as_ref().map(CardStrings::to_view)
+    }
+
+    fn from_view(view: Self::View) -> Self {
+        view.map(T::from_view)
+    }
+}
+
+impl<T: CardStrings> CardStrings for (Uuid, T) {
+    type View = (Uuid, T::View);
+
+    fn to_view(&self) -> Self::View {
+        (self.0, self.1.to_view())
+    }
+
+    fn from_view(view: Self::View) -> Self {
+        (view.0, T::from_view(view.1))
+    }
+}
+
+// serde(with) module writing the cards of a response field as CardView strings
+pub mod card_string {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::CardStrings;
+
+    pub fn serialize<T: CardStrings, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        value.to_view().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: CardStrings, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        T::View::deserialize(deserializer).map(T::from_view)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryWithPermit {
     PlayerPrivateData { table_id: u32 },
+    // cards the permit holder can't see: neither in their hand nor on the revealed board
+    UnknownCards { table_id: u32 },
+    // an authenticated alternative to the CommunityCards secret, for seated players once the street is revealed
+    CommunityCards { table_id: u32, game_state: GameState },
+    // the log of the previous hand, as emitted by the StartGame that replaced it
+    LastHandLog { table_id: u32 },
+    /*
+     * Showdown hands looked up by player_id instead of hand_secret, for the owner or a dealer
+     * once the showdown was retrieved. The secret based Showdown query stays the trustless path.
+     */
+    Showdown { table_id: u32, player_ids: Vec<Uuid> },
+    // every player's secrets and shares, only for the owner's permit, to reconcile a backend after a crash
+    AllPlayerShares { table_id: u32 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub contract_address: Addr,
+    pub instantiated_at: Option<Timestamp>, // None for contracts instantiated before it was recorded
+    pub instantiated_height: Option<u64>,
+    pub strict_one_time_reveal: bool,
+    pub pending_owner: Option<Addr>,
+    pub dealers: Vec<Addr>,
+    pub paused: bool,
+    pub min_players: usize,
+    pub max_players: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AllCommunityCardsResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    #[serde(with = "card_string")]
+    pub flop: Option<Vec<Card>>,
+    #[serde(with = "card_string")]
+    pub turn: Option<Card>,
+    #[serde(with = "card_string")]
+    pub river: Option<Card>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TableSummary {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub player_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VersionResponse {
+    pub contract: String,
+    // None for a contract instantiated before versioning and not migrated since
+    pub version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NextTableIdResponse {
+    pub table_id: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TablesResponse {
+    pub tables: Vec<TableSummary>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TableStatePlayer {
+    pub username: String,
+    pub player_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DeckCommitmentResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub commitment: Binary, // Sha256 of VerifyShuffleResponse.deck
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DeckVariantResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub deck_variant: DeckVariant,
+    #[serde(with = "card_string")]
+    pub cards: Vec<Card>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TableStateResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub players: Vec<TableStatePlayer>,
+    pub current_phase: GameState,
+    pub button_seat: u32,
+    pub deck_variant: DeckVariant,
+    pub flop_retrieved_at: Option<Timestamp>,
+    pub turn_retrieved_at: Option<Timestamp>,
+    pub river_retrieved_at: Option<Timestamp>,
+    pub showdown_retrieved_at: Option<Timestamp>,
+    pub started_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ShowdownStatusResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub showdown_retrieved_at: Option<Timestamp>, // a second Showdown fails with CardsAlreadyRetrieved once set
+    pub flop_retrieved_at: Option<Timestamp>,
+    pub turn_retrieved_at: Option<Timestamp>,
+    pub river_retrieved_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PartialSumResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub game_state: GameState,
+    pub partial_sum: String,
+    pub complete: bool, // true when every player's share is included, partial_sum is then the phase secret
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct PlayerDataResponse {
     pub table_id: u32,
     pub hand_ref: u32,
+    #[serde(with = "card_string")]
     pub hand: Vec<Card>,
     pub hand_secret: String,
     pub flop_secret_share: String,
     pub turn_secret_share: String,
     pub river_secret_share: String,
+    pub share_index: u32,
+    pub sharing_scheme: SharingScheme,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]  // Helps with JSON representation
 pub enum ResponsePayload {
     StartGame(StartGameResponse),
+    BulkStartGame(BulkStartGameResponse),
     LastHand(LastHandLogResponse),
     CommunityCards(CommunityCardsResponse),
     Showdown(ShowdownResponse),
+    HandHistory(HandHistoryResponse),
+    Compressed(CompressedResponse),
+}
+
+/*
+ * A ResponsePayload serialized to JSON, gzip-compressed and base64-encoded.
+ * Clients receiving a payload of type "compressed" must decode it to get the original payload.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CompressedResponse {
+    pub payload: Binary,
+}
+
+impl CompressedResponse {
+    pub fn encode(response: &ResponsePayload) -> StdResult<Self> {
+        let json = serde_json_wasm::to_vec(response)
+            .map_err(|e| StdError::serialize_err("ResponsePayload", e.to_string()))?;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder
+            .write_all(&json)
+            .map_err(|e| StdError::generic_err(format!("Compression failed: {}", e)))?;
+        let compressed = encoder
+            .finish()
+            .map_err(|e| StdError::generic_err(format!("Compression failed: {}", e)))?;
+
+        Ok(CompressedResponse {
+            payload: Binary::from(compressed),
+        })
+    }
+
+    pub fn decode(&self) -> StdResult<ResponsePayload> {
+        let mut json = Vec::new();
+        GzDecoder::new(self.payload.as_slice())
+            .read_to_end(&mut json)
+            .map_err(|e| StdError::generic_err(format!("Decompression failed: {}", e)))?;
+
+        serde_json_wasm::from_slice(&json)
+            .map_err(|e| StdError::parse_err("ResponsePayload", e.to_string()))
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -126,6 +650,48 @@ pub struct StartGameResponse {
     pub table_id: u32,
     pub hand_ref: u32,
     pub players: Vec<String>,
+    pub button_seat: u32,
+    #[serde(default)]
+    pub cards_dealt: usize, // hole cards, two per player
+    #[serde(default)]
+    pub deck_remaining: usize, // cards left in the deck once the hole cards, burns and board are dealt
+}
+
+/*
+ * The StartGameResponse of each game of a BulkStartGame, in order.
+ * The log of a replaced hand is emitted in a previous_hand_log_<table_id> attribute.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BulkStartGameResponse {
+    pub games: Vec<StartGameResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VerifyShuffleResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub seed: String,
+    pub max_retries: u64,
+    pub deck: Binary, // Deck::to_bytes of the shuffled deck, cards are dealt from the end
+    pub counter_start: String, // randomness counter range drawn by the hand, end excluded
+    pub counter_end: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PlayerHandResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub player_id: Uuid,
+    #[serde(with = "card_string")]
+    pub hand: Vec<Card>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnknownCardsResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    #[serde(with = "card_string")]
+    pub cards: Vec<Card>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -133,15 +699,90 @@ pub struct CommunityCardsResponse {
     pub table_id: u32,
     pub hand_ref: u32,
     pub game_state: GameState,
+    #[serde(with = "card_string")]
     pub community_cards: Vec<Card>,
+    // block time of the owner's reveal, the reveal itself in the CommunityCards execute response
+    #[serde(default)]
+    pub retrieved_at: Option<Timestamp>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct ShowdownResponse {
     pub table_id: u32,
     pub hand_ref: u32,
+    #[serde(with = "card_string")]
     pub players_cards: Vec<(Uuid, Vec<Card>)>,
+    #[serde(with = "card_string")]
     pub community_cards: Option<Vec<Card>>,
+    /*
+     * Only set by the Showdown execute, for the audit of the whole deck: with every hand and the board,
+     * the burned and never dealt cards account for each card of the deck.
+     */
+    #[serde(default, with = "card_string")]
+    pub burned: Option<Vec<Card>>,
+    #[serde(default, with = "card_string")]
+    pub undealt: Option<Vec<Card>>,
+    #[serde(default)]
+    pub reveal_order: Vec<Uuid>, // player_ids of players_cards by seat, clockwise from the left of the button
+    /*
+     * The game_state of the Showdown execute, the all-in street: community_cards then only holds the streets
+     * dealt after it (the whole board on PreFlop and River). Queries returning the whole board use PreFlop.
+     */
+    #[serde(default)]
+    pub from_state: GameState,
+    #[serde(default)]
+    pub board_len: usize, // number of community_cards
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WinnersResponse {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    #[serde(with = "card_string")]
+    pub community_cards: Vec<Card>,
+    pub rankings: Vec<Vec<(Uuid, HandRank)>>, // best hands first, tied players share the same group
+    /*
+     * When the winners' hand and the next best hand share their category, the ranks that decided it
+     * as given by eval::tie_broken_by, so the players behind can see exactly why they lost.
+     */
+    #[serde(default)]
+    pub tie_broken_by: Option<Vec<Rank>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ShowdownWithWinnersResponse {
+    pub showdown: ShowdownResponse,
+    // best hand of the revealed ones, several players on a split pot, evaluated with the revealed board only
+    pub winners: Vec<(Uuid, HandRank)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HandChainResponse {
+    pub table_id: u32,
+    pub links: Vec<HandLink>,
+    pub current_hand_hash: Option<Binary>, // recomputed from the stored table, None once the hand ended
+}
+
+impl HandChainResponse {
+    /*
+     * Each hand must reference the hash of the hand dealt before it on the table,
+     * and the table still in storage must hash to the last link.
+     */
+    pub fn verify(&self) -> bool {
+        let linked = self.links.first().is_none_or(|first| first.prev_hand_hash == [0u8; 32])
+            && self
+                .links
+                .windows(2)
+                .all(|links| links[1].prev_hand_hash == links[0].hand_hash);
+
+        let current_intact = match (&self.current_hand_hash, self.links.last()) {
+            (Some(hash), Some(last)) => hash.as_slice() == last.hand_hash,
+            (Some(_), None) => false,
+            (None, _) => true,
+        };
+
+        linked && current_intact
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -150,12 +791,39 @@ pub struct ShowdownPlayer {
     pub hand: Vec<String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LastHandLogQueryResponse {
+    pub table_id: u32,
+    pub last_hand: Option<LastHandLogResponse>, // None until a hand of the table is finalized
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HandHistoryResponse {
+    pub table_id: u32,
+    pub hands: Vec<LastHandLogResponse>,
+    pub next_start_after: Option<u32>, // None on the last page
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct LastHandLogResponse {
+    pub hand_ref: u32,
     pub showdown_players: Vec<ShowdownPlayer>, 
     pub community_cards: Vec<String>,
+    pub burned_cards: Vec<String>,
     pub flop_retrieved_at: Option<Timestamp>,
     pub turn_retrieved_at: Option<Timestamp>,
     pub river_retrieved_at: Option<Timestamp>,
     pub showdown_retrieved_at: Option<Timestamp>,
+    #[serde(default)]
+    pub started_at: Timestamp, // zero for hands dealt before it was recorded
+    #[serde(default)]
+    pub stakes: Option<Stakes>,
+    #[serde(default)]
+    pub corrupted: bool, // archived by RebuildTable, the log may not describe a consistent hand
+    /*
+     * The best hands among showdown_players and their percentage of the pot, summing to 100 unless nobody showed.
+     * On an uneven split the leftover points go to the first winners, in the order of the table's players.
+     */
+    #[serde(default)]
+    pub winners: Vec<(Uuid, u8)>,
 }
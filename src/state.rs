@@ -1,30 +1,173 @@
 use secret_toolkit_serialization::Json;
-use secret_toolkit_storage::{Item, Keymap, KeymapBuilder, WithoutIter};
+use secret_toolkit_storage::{AppendStore, Item, Keymap, KeymapBuilder, WithoutIter};
 use serde::{Deserialize, Serialize};
-use cosmwasm_std::{Addr, StdError, StdResult, Storage, Timestamp};
+use cosmwasm_std::{Addr, StdError, StdResult, Storage, Timestamp, Uint128};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::fmt;
 use uuid::Uuid;
 
+use crate::eval::HandRank;
+use crate::msg::LastHandLogResponse;
+use crate::ContractError;
+
 pub const PREFIX_REVOKED_PERMITS: &str = "revoked_permits";
 
+// bounds of the players of a hand, each contract can narrow them at instantiation, see PlayerLimits
+pub const MIN_PLAYERS: usize = 2;
+pub const MAX_PLAYERS: usize = 9;
+
 pub static COUNTER_KEY: Item<u128> = Item::new(b"counter");
 
+/*
+ * Items are stored with Bincode2, which ignores serde defaults: a field added to Config makes the stored one
+ * unreadable until migrate rewrites it, see LegacyConfig.
+ */
 pub static CONFIG_KEY: Item<Config> = Item::new(b"config");
 
+// the same storage key, read with the layout of the contracts deployed before Config grew
+pub static LEGACY_CONFIG_KEY: Item<LegacyConfig> = Item::new(b"config");
+
+pub static METRICS_KEY: Item<ExecuteMetrics> = Item::new(b"metrics");
+
+// crate version of the code that last instantiated or migrated the contract, absent before versioning
+pub static CONTRACT_VERSION_KEY: Item<String> = Item::new(b"contract_version");
+
+// block of the instantiation, outside of Config so its layout is unchanged, absent for older contracts
+pub static INSTANTIATION_KEY: Item<Instantiation> = Item::new(b"instantiation");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Instantiation {
+    pub time: Timestamp,
+    pub height: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Config {
     pub owner: Addr,
     pub contract_address: Addr,
+    pub strict_one_time_reveal: bool, // see InstantiateMsg
+    pub pending_owner: Option<Addr>, // set by TransferOwnership until the new owner accepts
+    pub dealers: Vec<Addr>, // senders allowed to run the hands, managed by the owner
+    pub paused: bool, // set by SetPaused, blocks StartGame only
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LegacyConfig {
+    pub owner: Addr,
+    pub contract_address: Addr,
+}
+
+impl From<LegacyConfig> for Config {
+    fn from(legacy: LegacyConfig) -> Self {
+        Config {
+            dealers: vec![legacy.owner.clone()],
+            owner: legacy.owner,
+            contract_address: legacy.contract_address,
+            strict_one_time_reveal: false,
+            pending_owner: None,
+            paused: false,
+        }
+    }
+}
+
+// players of a hand allowed by this contract, contracts instantiated before the limits have none stored
+pub static PLAYER_LIMITS_KEY: Item<PlayerLimits> = Item::new(b"player_limits");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PlayerLimits {
+    pub min_players: usize,
+    pub max_players: usize,
+}
+
+impl Default for PlayerLimits {
+    fn default() -> Self {
+        PlayerLimits { min_players: MIN_PLAYERS, max_players: MAX_PLAYERS }
+    }
+}
+
+pub fn load_player_limits(storage: &dyn Storage) -> StdResult<PlayerLimits> {
+    Ok(PLAYER_LIMITS_KEY.may_load(storage)?.unwrap_or_default())
+}
+
+impl Config {
+    /*
+     * The owner can always deal, which also keeps configs saved before dealers existed authorized.
+     */
+    pub fn is_dealer(&self, sender: &Addr) -> bool {
+        *sender == self.owner || self.dealers.contains(sender)
+    }
+}
+
+/*
+ * Number of successful executions per message type, for operators' observability.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExecuteMetrics {
+    pub start_games: u64,
+    pub community_cards: u64,
+    pub showdowns: u64,
 }
 
 pub static TABLES_STORE: Keymap<u32, PokerTable, Json, WithoutIter> =
             KeymapBuilder::new(b"tables").without_iter().build();
 
+/*
+ * Sorted ids of the stored tables, TABLES_STORE can't be iterated.
+ * Only StartGame adds a table and delete_table removes it, tables saved before the index existed
+ * are indexed by their next StartGame.
+ */
+pub static TABLE_IDS: Item<Vec<u32>> = Item::new(b"table_ids");
+
 pub fn save_table(storage: &mut dyn Storage, key: u32, item: &PokerTable) -> StdResult<()> {
     TABLES_STORE.insert(storage, &key, item).map_err(|err| {
         StdError::generic_err(format!("Failed to save table: {}", err))
     })
 }
 
+// adds a table created by StartGame to TABLE_IDS, the index is only written when the id is missing
+pub fn index_table_id(storage: &mut dyn Storage, table_id: u32) -> StdResult<()> {
+    let mut table_ids = TABLE_IDS.may_load(storage)?.unwrap_or_default();
+    if let Err(position) = table_ids.binary_search(&table_id) {
+        table_ids.insert(position, table_id);
+        TABLE_IDS.save(storage, &table_ids)?;
+    }
+    Ok(())
+}
+
+/*
+ * Smallest table id above every id a hand was ever started on, deleting a table doesn't lower it.
+ * Contracts upgraded before the counter existed fall back on the highest indexed table.
+ */
+pub static NEXT_TABLE_ID: Item<u32> = Item::new(b"next_table_id");
+
+pub fn next_table_id(storage: &dyn Storage) -> StdResult<u32> {
+    let next_table_id = match NEXT_TABLE_ID.may_load(storage)? {
+        Some(next_table_id) => next_table_id,
+        None => TABLE_IDS
+            .may_load(storage)?
+            .and_then(|table_ids| table_ids.last().copied())
+            .map_or(0, |table_id| table_id.saturating_add(1)),
+    };
+
+    // the counter saturates at u32::MAX, which may have been used since
+    if table_id_used(storage, next_table_id)? {
+        return Err(StdError::generic_err(ContractError::TableIdsExhausted {}.to_string()));
+    }
+    Ok(next_table_id)
+}
+
+// whether a hand was ever started on table_id, each StartGame links its hand in the table's hand chain
+pub fn table_id_used(storage: &dyn Storage, table_id: u32) -> StdResult<bool> {
+    Ok(hand_chain(table_id).get_len(storage)? > 0)
+}
+
+// moves the counter past table_id, saturating at u32::MAX
+pub fn reserve_table_id(storage: &mut dyn Storage, table_id: u32) -> StdResult<()> {
+    let next_table_id = next_table_id(storage)?.max(table_id.saturating_add(1));
+    NEXT_TABLE_ID.save(storage, &next_table_id)
+}
+
 pub fn load_table(storage: &dyn Storage, key: u32) -> Option<PokerTable> {
     TABLES_STORE.get(storage, &key)
 }
@@ -32,35 +175,205 @@ pub fn load_table(storage: &dyn Storage, key: u32) -> Option<PokerTable> {
 pub fn delete_table(storage: &mut dyn Storage, key: u32) -> StdResult<()> {
     TABLES_STORE.remove(storage, &key).map_err(|err| {
         StdError::generic_err(format!("Failed to delete table: {}", err))
-    })
+    })?;
+
+    let mut table_ids = TABLE_IDS.may_load(storage)?.unwrap_or_default();
+    if let Ok(position) = table_ids.binary_search(&key) {
+        table_ids.remove(position);
+        TABLE_IDS.save(storage, &table_ids)?;
+    }
+    Ok(())
+}
+
+/*
+ * Append-only chain of the hands dealt on each table (suffixed by table id),
+ * a link is pushed by StartGame once the hand is dealt.
+ */
+pub static HAND_CHAIN_STORE: AppendStore<HandLink, Json> = AppendStore::new(b"hand_chain");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct HandLink {
+    pub hand_ref: u32,
+    pub prev_hand_hash: [u8; 32],
+    pub hand_hash: [u8; 32],
+}
+
+// bumped whenever the fields of HandDigest change, hashes of different versions never match
+pub const HAND_HASH_VERSION: u8 = 1;
+
+/*
+ * What a hand_hash commits to, a new PokerTable field only changes the hash once it's added here.
+ */
+#[derive(Serialize)]
+struct HandDigest<'a> {
+    version: u8,
+    hand_ref: u32,
+    prev_hand_hash: &'a [u8; 32],
+    players: Vec<(Uuid, &'a Vec<Card>)>,
+    board: Vec<Card>,
+    shuffle_seed: u64,
+}
+
+pub fn hand_chain(table_id: u32) -> AppendStore<'static, HandLink, Json> {
+    HAND_CHAIN_STORE.add_suffix(&table_id.to_be_bytes())
+}
+
+pub fn append_hand_link(storage: &mut dyn Storage, table_id: u32, table: &PokerTable) -> StdResult<()> {
+    hand_chain(table_id).push(storage, &table.hand_link()?)
+}
+
+pub fn last_hand_hash(storage: &dyn Storage, table_id: u32) -> StdResult<[u8; 32]> {
+    let chain = hand_chain(table_id);
+    match chain.get_len(storage)? {
+        0 => Ok([0u8; 32]),
+        len => Ok(chain.get_at(storage, len - 1)?.hand_hash),
+    }
+}
+
+/*
+ * Logs of the completed hands of each table (suffixed by table id), oldest first.
+ * A hand is archived when the next StartGame overwrites it or when it is ended with EndHand.
+ */
+pub static HAND_HISTORY_STORE: AppendStore<LastHandLogResponse, Json> = AppendStore::new(b"hand_history");
+
+pub fn hand_history(table_id: u32) -> AppendStore<'static, LastHandLogResponse, Json> {
+    HAND_HISTORY_STORE.add_suffix(&table_id.to_be_bytes())
+}
+
+/*
+ * Settlement record of each finalized hand, keyed by (table_id, hand_ref).
+ * Bets aren't handled by the contract, so pots, amounts and rake are left to the payment system,
+ * which settles them from the winners recorded here.
+ */
+pub static GAME_RESULTS_STORE: Keymap<(u32, u32), GameResult, Json, WithoutIter> =
+            KeymapBuilder::new(b"game_results").without_iter().build();
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct GameResult {
+    pub table_id: u32,
+    pub hand_ref: u32,
+    pub community_cards: Vec<Card>,
+    pub showdown_hands: Vec<(Uuid, Vec<Card>)>,
+    pub rankings: Vec<Vec<(Uuid, HandRank)>>, // best hands first, tied players share the same group
+    pub winners: Vec<Uuid>, // split pot when several players tie
+}
+
+// results are keyed by the caller's hand_ref, a saved result is never overwritten
+pub fn save_game_result(storage: &mut dyn Storage, result: &GameResult) -> Result<(), ContractError> {
+    let key = (result.table_id, result.hand_ref);
+    if GAME_RESULTS_STORE.contains(storage, &key) {
+        return Err(ContractError::HandRefAlreadyUsed { table_id: result.table_id, hand_ref: result.hand_ref });
+    }
+    Ok(GAME_RESULTS_STORE.insert(storage, &key, result)?)
+}
+
+pub fn load_game_result(storage: &dyn Storage, table_id: u32, hand_ref: u32) -> Option<GameResult> {
+    GAME_RESULTS_STORE.get(storage, &(table_id, hand_ref))
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct CommunityCards {
     pub flop: Flop,
-    pub turn: Turn, 
-    pub river: River, 
+    #[serde(default)]
+    pub turn: Option<Turn>, // None when the hand was started with fewer phases, see StartGame
+    #[serde(default)]
+    pub river: Option<River>,
+    #[serde(default)]
+    pub burned: Vec<Card>, // one card burned before each phase, only disclosed for audit
+}
+
+impl CommunityCards {
+    // when the street was revealed by the owner, None for PreFlop, Showdown and streets not dealt or not revealed
+    pub fn retrieved_at(&self, game_state: &GameState) -> Option<Timestamp> {
+        match game_state {
+            GameState::PreFlop | GameState::Showdown => None,
+            GameState::Flop => self.flop.retrieved_at,
+            GameState::Turn => self.turn.as_ref().and_then(|turn| turn.retrieved_at),
+            GameState::River => self.river.as_ref().and_then(|river| river.retrieved_at),
+        }
+    }
+
+    pub fn consume_all(&mut self) {
+        self.flop.consumed = true;
+        if let Some(turn) = self.turn.as_mut() {
+            turn.consumed = true;
+        }
+        if let Some(river) = self.river.as_mut() {
+            river.consumed = true;
+        }
+    }
+
+    /*
+     * Streets disclosed so far, by a reveal or by the showdown (see consumed).
+     */
+    pub fn revealed_board(&self) -> Vec<Card> {
+        let mut board = Vec::new();
+        if self.flop.consumed {
+            board.extend(self.flop.cards.iter().cloned());
+        }
+        board.extend(self.turn.iter().filter(|turn| turn.consumed).map(|turn| turn.card.clone()));
+        board.extend(self.river.iter().filter(|river| river.consumed).map(|river| river.card.clone()));
+        board
+    }
+
+    // number of cards of each rank on the revealed board, indexed by rank - 1 (ace first)
+    pub fn board_rank_counts(&self) -> [u8; 13] {
+        let mut counts = [0u8; 13];
+        for card in self.revealed_board() {
+            counts[card.rank() as usize - 1] += 1;
+        }
+        counts
+    }
+
+    // a paired board makes full houses and quads possible
+    pub fn is_paired(&self) -> bool {
+        self.board_rank_counts().iter().any(|&count| count >= 2)
+    }
+
+    pub fn is_consumed(&self, game_state: &GameState) -> bool {
+        match game_state {
+            GameState::PreFlop | GameState::Showdown => false,
+            GameState::Flop => self.flop.consumed,
+            GameState::Turn => self.turn.as_ref().is_some_and(|turn| turn.consumed),
+            GameState::River => self.river.as_ref().is_some_and(|river| river.consumed),
+        }
+    }
+
+    pub fn is_dealt(&self, game_state: &GameState) -> bool {
+        match game_state {
+            GameState::PreFlop | GameState::Showdown => false,
+            GameState::Flop => true,
+            GameState::Turn => self.turn.is_some(),
+            GameState::River => self.river.is_some(),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Flop {
     pub cards: Vec<Card>,
-    pub secret: u64,
+    pub secret: u128,
     pub retrieved_at: Option<Timestamp>,
+    #[serde(default)]
+    pub consumed: bool, // set once the owner revealed the phase, see strict_one_time_reveal
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Turn {
     pub card: Card,
-    pub secret: u64,
+    pub secret: u128,
     pub retrieved_at: Option<Timestamp>,
+    #[serde(default)]
+    pub consumed: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct River {
     pub card: Card,
-    pub secret: u64,
+    pub secret: u128,
     pub retrieved_at: Option<Timestamp>,
+    #[serde(default)]
+    pub consumed: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -69,6 +382,115 @@ pub struct PokerTable {
     pub players: Vec<Player>,
     pub community_cards: CommunityCards,
     pub showdown_retrieved_at: Option<Timestamp>,
+    #[serde(default)]
+    pub current_phase: GameState,
+    #[serde(default)]
+    pub shuffle_fallback_used: bool, // whether the shuffle reached its rejection sampling cap, see shuffle_deck
+    #[serde(default)]
+    pub prev_hand_hash: [u8; 32], // hand_hash of the previous hand on this table, zeroes for the first one
+    #[serde(default)]
+    pub shuffle_seed: u64, // with shuffle_max_retries, reproduces the dealt deck, see VerifyShuffle
+    #[serde(default)]
+    pub shuffle_max_retries: u64,
+    #[serde(default)]
+    pub sharing_scheme: SharingScheme,
+    #[serde(default)]
+    pub button_seat: u32, // index in players of the dealer button
+    #[serde(default)]
+    pub deck_variant: DeckVariant,
+    #[serde(default)]
+    pub undealt: Vec<Card>, // remainder of the deck once the hand is dealt, only disclosed by the showdown
+    #[serde(default)]
+    pub counter_start: u128, // randomness counter range drawn by the hand, end excluded
+    #[serde(default)]
+    pub counter_end: u128,
+    #[serde(default)]
+    pub max_hands: Option<u32>, // hands a table id can deal before it must be ended, see StartGame
+    #[serde(default)]
+    pub hands_dealt: u32, // on this table id since it was created, including this hand
+    #[serde(default)]
+    pub stakes: Option<Stakes>,
+    #[serde(default)]
+    pub deck_commitment: [u8; 32], // Sha256 of the shuffled deck bytes, zeroes for hands dealt before commitments
+    #[serde(default)]
+    pub started_at: Timestamp, // block time of the StartGame that dealt the hand, zero for hands dealt before it was recorded
+}
+
+/*
+ * Every card dealt for a hand, without any of the secrets protecting them.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DealtCards {
+    pub players: Vec<(Uuid, Vec<Card>)>,
+    pub flop: Vec<Card>,
+    pub turn: Option<Card>,
+    pub river: Option<Card>,
+    pub burned: Vec<Card>,
+}
+
+impl DealtCards {
+    pub fn board(&self) -> Vec<Card> {
+        let mut board = self.flop.clone();
+        board.extend(self.turn.clone());
+        board.extend(self.river.clone());
+        board
+    }
+}
+
+impl PokerTable {
+    /*
+     * Hash of the dealt hand, see HandDigest: hand_ref, players' hands, board, shuffle_seed and prev_hand_hash.
+     * The retrieval progress and players' status are left out since they change while the hand is played.
+     */
+    pub fn hand_hash(&self) -> StdResult<[u8; 32]> {
+        let digest = HandDigest {
+            version: HAND_HASH_VERSION,
+            hand_ref: self.hand_ref,
+            prev_hand_hash: &self.prev_hand_hash,
+            players: self.players.iter().map(|player| (player.player_id, &player.hand)).collect(),
+            board: self.all_dealt_cards().board(),
+            shuffle_seed: self.shuffle_seed,
+        };
+
+        let bytes = serde_json_wasm::to_vec(&digest)
+            .map_err(|e| StdError::serialize_err("HandDigest", e.to_string()))?;
+        Ok(Sha256::digest(bytes).into())
+    }
+
+    pub fn hand_link(&self) -> StdResult<HandLink> {
+        Ok(HandLink {
+            hand_ref: self.hand_ref,
+            prev_hand_hash: self.prev_hand_hash,
+            hand_hash: self.hand_hash()?,
+        })
+    }
+
+    pub fn all_dealt_cards(&self) -> DealtCards {
+        DealtCards {
+            players: self
+                .players
+                .iter()
+                .map(|player| (player.player_id, player.hand.clone()))
+                .collect(),
+            flop: self.community_cards.flop.cards.clone(),
+            turn: self.community_cards.turn.as_ref().map(|turn| turn.card.clone()),
+            river: self.community_cards.river.as_ref().map(|river| river.card.clone()),
+            burned: self.community_cards.burned.clone(),
+        }
+    }
+
+    /*
+     * Players of player_ids by seat, clockwise from the seat left of the button:
+     * the first player to act after the flop shows first.
+     */
+    pub fn reveal_order(&self, player_ids: &[Uuid]) -> Vec<Uuid> {
+        let seats = self.players.len();
+        (1..=seats)
+            .map(|offset| &self.players[(self.button_seat as usize + offset) % seats])
+            .filter(|player| player_ids.contains(&player.player_id))
+            .map(|player| player.player_id)
+            .collect()
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -77,32 +499,187 @@ pub struct Player {
     pub player_id: Uuid,
     pub public_key: String,
     pub hand: Vec<Card>,
-    pub hand_secret: u64,
-    pub flop_secret_share: u64,
-    pub turn_secret_share: u64,
-    pub river_secret_share: u64,
+    pub hand_secret: u128,
+    pub flop_secret_share: u128,
+    pub turn_secret_share: u128,
+    pub river_secret_share: u128,
+    #[serde(default)]
+    pub share_index: u32, // seat + 1, the x-coordinate of the player's shares
+    #[serde(default)]
+    pub inactive: bool, // marked by the owner, see MarkPlayerInactive
+    #[serde(default)]
+    pub folded: bool, // marked by the owner, see Fold
 }
 
 
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
 pub enum GameState {
+    #[default]
     PreFlop,
     Flop,
     Turn,
     River,
+    Showdown, // the hand is complete, set once the owner retrieved the showdown
 }
 
+impl GameState {
+    // the street revealed after this one, the showdown isn't a street
+    pub fn next(&self) -> Option<GameState> {
+        match self {
+            GameState::PreFlop => Some(GameState::Flop),
+            GameState::Flop => Some(GameState::Turn),
+            GameState::Turn => Some(GameState::River),
+            GameState::River | GameState::Showdown => None,
+        }
+    }
+
+    // same as its JSON representation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameState::PreFlop => "pre_flop",
+            GameState::Flop => "flop",
+            GameState::Turn => "turn",
+            GameState::River => "river",
+            GameState::Showdown => "showdown",
+        }
+    }
+}
 
+/*
+ * Stakes the hand was played for. The betting itself is off-chain, the stakes are only recorded
+ * so the archived hand log settles disputes about them.
+ */
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Stakes {
+    pub small_blind: Uint128,
+    pub big_blind: Uint128,
+    #[serde(default)]
+    pub ante: Option<Uint128>,
+    pub currency: String, // e.g. a denom such as uscrt, or an off-chain chip currency
+}
+
+/*
+ * The deck a hand is dealt from. Short deck hands are ranked with the short deck rules: A-6-7-8-9 is a straight
+ * and a flush beats a full house.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeckVariant {
+    #[default]
+    Standard,
+    Short,
+}
+
+impl DeckVariant {
+    // ordered deck of the variant, before shuffling
+    pub fn deck(&self) -> Deck {
+        match self {
+            DeckVariant::Standard => Deck::new(),
+            DeckVariant::Short => Deck::short(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            DeckVariant::Standard => 52,
+            DeckVariant::Short => 36,
+        }
+    }
+}
+
+/*
+ * How the community card secrets are split between the players, clients must reconstruct them the same way.
+ * Additive: the secret is the sum of every player's share, wrapping over u128.
+ * Threshold: each share is the point (share_index, share) of a random polynomial of degree threshold - 1
+ * over the prime field of order 2^127 - 1, the secret is its Lagrange interpolation at 0 from any threshold shares.
+ */
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SharingScheme {
+    #[default]
+    Additive,
+    Threshold { threshold: u8 },
+}
+
+
+/*
+ * Typed views of the suit and rank packed in a Card, their discriminants are the packed values
+ * so the wire format stays the single u8.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(u8)]
+pub enum Suit {
+    Clubs = 0,
+    Diamonds = 1,
+    Hearts = 2,
+    Spades = 3,
+}
+
+impl Suit {
+    pub const ALL: [Suit; 4] = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Rank {
+    Ace = 1,
+    Two = 2,
+    Three = 3,
+    Four = 4,
+    Five = 5,
+    Six = 6,
+    Seven = 7,
+    Eight = 8,
+    Nine = 9,
+    Ten = 10,
+    Jack = 11,
+    Queen = 12,
+    King = 13,
+}
+
+impl Rank {
+    pub const ALL: [Rank; 13] = [
+        Rank::Ace,
+        Rank::Two,
+        Rank::Three,
+        Rank::Four,
+        Rank::Five,
+        Rank::Six,
+        Rank::Seven,
+        Rank::Eight,
+        Rank::Nine,
+        Rank::Ten,
+        Rank::Jack,
+        Rank::Queen,
+        Rank::King,
+    ];
+
+    // inverse of Card::rank_value, the ace is 14
+    pub fn from_value(value: u8) -> Rank {
+        Rank::ALL[(value as usize - 1) % Rank::ALL.len()]
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Card(u8);
 
 impl Card {
     pub fn new(suit: u8, rank: u8) -> Self {
-        assert!(suit < 4, "Invalid suit");
-        assert!(rank >= 1 && rank <= 13, "Invalid rank");
-        Card((suit << 4) | rank)
+        Card::try_new(suit, rank).expect("Invalid card")
+    }
+
+    pub fn try_new(suit: u8, rank: u8) -> Result<Self, ContractError> {
+        if suit >= 4 || !(1..=13).contains(&rank) {
+            return Err(ContractError::InvalidCard { suit, rank });
+        }
+        Ok(Card((suit << 4) | rank))
+    }
+
+    pub fn from_parts(suit: Suit, rank: Rank) -> Self {
+        Card(((suit as u8) << 4) | rank as u8)
     }
 
     pub fn suit(&self) -> u8 {
@@ -113,6 +690,36 @@ impl Card {
         self.0 & 0b1111
     }
 
+    // like Display, only defined for valid cards
+    pub fn suit_enum(&self) -> Suit {
+        Suit::ALL[self.suit() as usize]
+    }
+
+    pub fn rank_enum(&self) -> Rank {
+        Rank::ALL[self.rank() as usize - 1]
+    }
+
+    pub fn same_rank(&self, other: &Card) -> bool {
+        self.rank() == other.rank()
+    }
+
+    pub fn same_suit(&self, other: &Card) -> bool {
+        self.suit() == other.suit()
+    }
+
+    // rank ordered for comparisons, the ace is high (14)
+    pub fn rank_value(&self) -> u8 {
+        match self.rank() {
+            1 => 14,
+            rank => rank,
+        }
+    }
+
+    // rank ordered with the ace low (1), as in the wheel straight A-2-3-4-5
+    pub fn rank_value_ace_low(&self) -> u8 {
+        self.rank()
+    }
+
     pub fn to_bytes(&self) -> u8 {
         self.0
     }
@@ -121,17 +728,58 @@ impl Card {
         Card(byte)
     }
 
-    pub fn to_string(&self) -> String {
-        /* Order of suits in this list is relatively important (as they are mostly continuous digits, ranks is pretty hard to f*** up...),
-         * this list of suits should be in the same order in the backend and frontend executing/querying the contract.
-         * This order is crucial because the contract logs the cards from the last game 
-         * (for audit purposes) in the transaction log (unencrypted plaintext) of each StartGameResponse. 
-         * Thus, by doing so, the last_hand_log will match what the player sees in his game, and what will be stored in the
-         * backend database. Anyways, for audit purposes it's not a big deal, we can always map the suits to the correct ones by permutation.
-         */ 
-        let suits = ["♣", "♦", "♥", "♠"]; 
-        let ranks = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K"];
-        format!("{}{}", suits[self.suit() as usize], ranks[self.rank() as usize - 1])
+    /*
+     * Inverse of the Display implementation, parses a card such as "♠A" or "♥10"
+     * as logged in the last_hand_log.
+     */
+    pub fn from_string(s: &str) -> Result<Card, ContractError> {
+        let invalid = || ContractError::InvalidCardString { card: s.to_string() };
+
+        let suit = SUITS
+            .iter()
+            .position(|suit| s.starts_with(suit))
+            .ok_or_else(invalid)?;
+        let rank = RANKS
+            .iter()
+            .position(|rank| &s[SUITS[suit].len()..] == *rank)
+            .ok_or_else(invalid)?;
+
+        Card::try_new(suit as u8, rank as u8 + 1)
+    }
+}
+
+/* Order of suits in this list is relatively important (as they are mostly continuous digits, ranks is pretty hard to f*** up...),
+ * this list of suits should be in the same order in the backend and frontend executing/querying the contract.
+ * This order is crucial because the contract logs the cards from the last game 
+ * (for audit purposes) in the transaction log (unencrypted plaintext) of each StartGameResponse. 
+ * Thus, by doing so, the last_hand_log will match what the player sees in his game, and what will be stored in the
+ * backend database. Anyways, for audit purposes it's not a big deal, we can always map the suits to the correct ones by permutation.
+ */ 
+const SUITS: [&str; 4] = ["♣", "♦", "♥", "♠"];
+const RANKS: [&str; 13] = ["A", "2", "3", "4", "5", "6", "7", "8", "9", "10", "J", "Q", "K"];
+
+/*
+ * Cards sort by rank with the ace high, then by suit in the SUITS order, so a sorted hand reads
+ * from its lowest to its highest card. The packed byte only breaks ties between invalid cards.
+ */
+impl Ord for Card {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank_value()
+            .cmp(&other.rank_value())
+            .then(self.suit().cmp(&other.suit()))
+            .then(self.0.cmp(&other.0))
+    }
+}
+
+impl PartialOrd for Card {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", SUITS[self.suit() as usize], RANKS[self.rank() as usize - 1])
     }
 }
 
@@ -141,6 +789,12 @@ pub struct Deck {
     pub cards: Vec<Card>,
 }
 
+impl Default for Deck {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Deck {
     pub fn new() -> Self {
         let mut cards = Vec::new();
@@ -152,16 +806,254 @@ impl Deck {
         Deck { cards }
     }
 
+    // short deck (6-plus) Hold'em: the 2 to 5 of every suit are removed, leaving 36 cards
+    pub fn short() -> Self {
+        let mut cards = Vec::new();
+        for suit in 0..4 {
+            for rank in std::iter::once(1).chain(6..=13) {
+                cards.push(Card::new(suit, rank));
+            }
+        }
+        Deck { cards }
+    }
+
+    /*
+     * A full deck, before any card is dealt, must hold exactly the cards of the variant it was built from.
+     */
+    pub fn verify_integrity(&self, deck_variant: &DeckVariant) -> Result<(), ContractError> {
+        let expected = deck_variant.deck();
+
+        let mut cards = self.to_bytes();
+        let mut expected_cards = expected.to_bytes();
+        cards.sort_unstable();
+        expected_cards.sort_unstable();
+
+        if cards != expected_cards {
+            return Err(ContractError::CorruptDeck {});
+        }
+        Ok(())
+    }
+
+    pub fn deal(&mut self) -> Result<Card, ContractError> {
+        self.cards.pop().ok_or(ContractError::DeckExhausted {})
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         self.cards.iter().map(|card| card.0).collect()
     }
 
+    // trusted bytes only, such as a deck the contract serialized itself, see try_from_bytes
     pub fn from_bytes(bytes: &[u8]) -> Self {
         let cards = bytes.iter().map(|&b| Card(b)).collect();
         Deck { cards }
     }
+
+    /*
+     * For bytes the contract can't vouch for, e.g. a deck carried over by a migration:
+     * every byte must decode to a legal card and the deck must hold exactly the cards of the table's variant.
+     */
+    pub fn try_from_bytes(bytes: &[u8], deck_variant: &DeckVariant) -> Result<Self, ContractError> {
+        let cards = bytes
+            .iter()
+            .map(|&b| Card::try_new(b >> 4, b & 0b1111))
+            .collect::<Result<Vec<_>, _>>()?;
+        let deck = Deck { cards };
+        deck.verify_integrity(deck_variant)?;
+        Ok(deck)
+    }
+}
+
+/*
+ * Test builders: every field has a default so a test only spells out what it checks.
+ */
+#[cfg(test)]
+pub struct PlayerBuilder {
+    player: Player,
+}
+
+#[cfg(test)]
+impl PlayerBuilder {
+    pub fn new(player_id: Uuid) -> Self {
+        PlayerBuilder {
+            player: Player {
+                username: player_id.to_string(),
+                player_id,
+                public_key: player_id.to_string(),
+                hand: Vec::new(),
+                hand_secret: 0,
+                flop_secret_share: 0,
+                turn_secret_share: 0,
+                river_secret_share: 0,
+                share_index: 0,
+                inactive: false,
+                folded: false,
+            },
+        }
+    }
+
+    pub fn username(mut self, username: &str) -> Self {
+        self.player.username = username.to_string();
+        self
+    }
+
+    pub fn public_key(mut self, public_key: &str) -> Self {
+        self.player.public_key = public_key.to_string();
+        self
+    }
+
+    pub fn hand(mut self, hand: Vec<Card>) -> Self {
+        self.player.hand = hand;
+        self
+    }
+
+    pub fn hand_secret(mut self, hand_secret: u128) -> Self {
+        self.player.hand_secret = hand_secret;
+        self
+    }
+
+    pub fn shares(mut self, flop: u128, turn: u128, river: u128) -> Self {
+        self.player.flop_secret_share = flop;
+        self.player.turn_secret_share = turn;
+        self.player.river_secret_share = river;
+        self
+    }
+
+    pub fn share_index(mut self, share_index: u32) -> Self {
+        self.player.share_index = share_index;
+        self
+    }
+
+    pub fn folded(mut self) -> Self {
+        self.player.folded = true;
+        self
+    }
+
+    pub fn build(self) -> Player {
+        self.player
+    }
+}
+
+#[cfg(test)]
+pub struct PokerTableBuilder {
+    table: PokerTable,
 }
 
+#[cfg(test)]
+impl Default for PokerTableBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+impl PokerTableBuilder {
+    // a three phases board of ♣2 to ♣6 with every secret set to 0
+    pub fn new() -> Self {
+        PokerTableBuilder {
+            table: PokerTable {
+                hand_ref: 1,
+                players: Vec::new(),
+                community_cards: CommunityCards {
+                    flop: Flop {
+                        cards: vec![Card::new(0, 2), Card::new(0, 3), Card::new(0, 4)],
+                        secret: 0,
+                        retrieved_at: None,
+                        consumed: false,
+                    },
+                    turn: Some(Turn {
+                        card: Card::new(0, 5),
+                        secret: 0,
+                        retrieved_at: None,
+                        consumed: false,
+                    }),
+                    river: Some(River {
+                        card: Card::new(0, 6),
+                        secret: 0,
+                        retrieved_at: None,
+                        consumed: false,
+                    }),
+                    burned: Vec::new(),
+                },
+                showdown_retrieved_at: None,
+                current_phase: GameState::PreFlop,
+                shuffle_fallback_used: false,
+                prev_hand_hash: [0u8; 32],
+                shuffle_seed: 0,
+                shuffle_max_retries: 0,
+                sharing_scheme: SharingScheme::Additive,
+                button_seat: 0,
+                deck_variant: DeckVariant::Standard,
+                undealt: Vec::new(),
+                counter_start: 0,
+                counter_end: 0,
+                max_hands: None,
+                hands_dealt: 1,
+                stakes: None,
+                deck_commitment: [0u8; 32],
+                started_at: Timestamp::default(),
+            },
+        }
+    }
+
+    pub fn hand_ref(mut self, hand_ref: u32) -> Self {
+        self.table.hand_ref = hand_ref;
+        self
+    }
+
+    // players without a share_index are given their seat + 1, as StartGame does
+    pub fn player(mut self, mut player: Player) -> Self {
+        if player.share_index == 0 {
+            player.share_index = self.table.players.len() as u32 + 1;
+        }
+        self.table.players.push(player);
+        self
+    }
+
+    // the first three cards are the flop, the turn and river are only dealt if present
+    pub fn board(mut self, board: Vec<Card>) -> Self {
+        let community_cards = &mut self.table.community_cards;
+        community_cards.flop.cards = board[..3].to_vec();
+        community_cards.turn = board.get(3).map(|card| Turn {
+            card: card.clone(),
+            secret: community_cards.turn.as_ref().map_or(0, |turn| turn.secret),
+            retrieved_at: None,
+            consumed: false,
+        });
+        community_cards.river = board.get(4).map(|card| River {
+            card: card.clone(),
+            secret: community_cards.river.as_ref().map_or(0, |river| river.secret),
+            retrieved_at: None,
+            consumed: false,
+        });
+        self
+    }
+
+    pub fn secrets(mut self, flop: u128, turn: u128, river: u128) -> Self {
+        let community_cards = &mut self.table.community_cards;
+        community_cards.flop.secret = flop;
+        if let Some(turn_cards) = community_cards.turn.as_mut() {
+            turn_cards.secret = turn;
+        }
+        if let Some(river_cards) = community_cards.river.as_mut() {
+            river_cards.secret = river;
+        }
+        self
+    }
+
+    pub fn burned(mut self, burned: Vec<Card>) -> Self {
+        self.table.community_cards.burned = burned;
+        self
+    }
+
+    pub fn current_phase(mut self, current_phase: GameState) -> Self {
+        self.table.current_phase = current_phase;
+        self
+    }
+
+    pub fn build(self) -> PokerTable {
+        self.table
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -172,7 +1064,259 @@ use super::*;
         let deck = Deck::new();
         for card in deck.cards.iter() {
             println!("{}", card.to_bytes());
-            println!("{}", card.to_string());
+            println!("{}", card);
+        }
+    }
+
+    #[test]
+    fn try_new_card() {
+        let card = Card::try_new(3, 13).unwrap();
+        assert_eq!(card, Card::new(3, 13));
+        assert_eq!(card.to_bytes(), (3 << 4) | 13);
+
+        for (suit, rank) in [(4, 1), (0, 0), (0, 14), (255, 255)] {
+            assert_eq!(Card::try_new(suit, rank).unwrap_err(), ContractError::InvalidCard { suit, rank });
+        }
+    }
+
+    #[test]
+    fn card_parts() {
+        for card in Deck::new().cards {
+            assert_eq!(card.suit_enum() as u8, card.suit());
+            assert_eq!(card.rank_enum() as u8, card.rank());
+            assert_eq!(Card::from_parts(card.suit_enum(), card.rank_enum()), card);
+        }
+
+        let card = Card::from_parts(Suit::Hearts, Rank::Ten);
+        assert_eq!(card, Card::new(2, 10));
+        assert_eq!(card.to_string(), "♥10");
+        assert_eq!(Card::from_parts(Suit::Spades, Rank::Ace).to_bytes(), (3 << 4) | 1);
+        assert_eq!(Card::from_parts(Suit::Clubs, Rank::King), Card::new(0, 13));
+    }
+
+    #[test]
+    fn card_comparisons() {
+        let ace = Card::from_parts(Suit::Spades, Rank::Ace);
+        let king = Card::from_parts(Suit::Spades, Rank::King);
+        let two = Card::from_parts(Suit::Hearts, Rank::Two);
+
+        assert!(king.rank_value() < ace.rank_value());
+        assert_eq!(ace.rank_value(), 14);
+        assert!(ace.rank_value_ace_low() < two.rank_value_ace_low());
+        assert_eq!(ace.rank_value_ace_low(), 1);
+        assert_eq!(two.rank_value(), two.rank_value_ace_low());
+
+        assert!(ace.same_suit(&king));
+        assert!(!ace.same_suit(&two));
+        assert!(ace.same_rank(&Card::from_parts(Suit::Diamonds, Rank::Ace)));
+        assert!(!ace.same_rank(&king));
+    }
+
+    #[test]
+    fn card_string_round_trip() {
+        for card in Deck::new().cards {
+            assert_eq!(Card::from_string(&card.to_string()).unwrap(), card);
+        }
+        assert_eq!(Card::from_string("♥10").unwrap(), Card::new(2, 10));
+
+        for invalid in ["", "♠", "A", "♠1", "♠11", "x10", "♠A♠", "10♥"] {
+            assert_eq!(
+                Card::from_string(invalid).unwrap_err(),
+                ContractError::InvalidCardString {
+                    card: invalid.to_string()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn short_deck() {
+        let deck = Deck::short();
+        assert_eq!(deck.cards.len(), DeckVariant::Short.size());
+        assert_eq!(deck.cards.iter().collect::<std::collections::HashSet<_>>().len(), 36);
+        assert!(deck.cards.iter().all(|card| !(2..=5).contains(&card.rank())));
+        assert!(deck.cards.iter().all(|card| Deck::new().cards.contains(card)));
+
+        assert_eq!(DeckVariant::Standard.deck(), Deck::new());
+        assert_eq!(DeckVariant::Standard.deck().cards.len(), DeckVariant::Standard.size());
+        assert_eq!(DeckVariant::Short.deck(), deck);
+    }
+
+    #[test]
+    fn verify_deck_integrity() {
+        assert_eq!(Deck::new().verify_integrity(&DeckVariant::Standard), Ok(()));
+        assert_eq!(Deck::short().verify_integrity(&DeckVariant::Short), Ok(()));
+
+        // a deck is checked against the table's variant, never against the variant its size suggests
+        assert_eq!(Deck::new().verify_integrity(&DeckVariant::Short), Err(ContractError::CorruptDeck {}));
+        assert_eq!(Deck::short().verify_integrity(&DeckVariant::Standard), Err(ContractError::CorruptDeck {}));
+
+        let mut reversed = Deck::new();
+        reversed.cards.reverse();
+        assert_eq!(reversed.verify_integrity(&DeckVariant::Standard), Ok(()));
+
+        let mut duplicated = Deck::new();
+        duplicated.cards[0] = duplicated.cards[1].clone();
+        assert_eq!(duplicated.verify_integrity(&DeckVariant::Standard), Err(ContractError::CorruptDeck {}));
+
+        // a 2 has no place in a short deck
+        let mut short = Deck::short();
+        short.cards[0] = Card::new(0, 2);
+        assert_eq!(short.verify_integrity(&DeckVariant::Short), Err(ContractError::CorruptDeck {}));
+
+        let mut dealt = Deck::new();
+        dealt.deal().unwrap();
+        assert_eq!(dealt.verify_integrity(&DeckVariant::Standard), Err(ContractError::CorruptDeck {}));
+    }
+
+    #[test]
+    fn deck_try_from_bytes() {
+        for variant in [DeckVariant::Standard, DeckVariant::Short] {
+            let deck = variant.deck();
+            assert_eq!(Deck::try_from_bytes(&deck.to_bytes(), &variant), Ok(deck));
+        }
+
+        // rank 0 and rank 15 don't exist, nor does a fifth suit
+        for (byte, suit, rank) in [(0x20, 2, 0), (0x1f, 1, 15), (0x41, 4, 1)] {
+            let mut bytes = Deck::new().to_bytes();
+            bytes[0] = byte;
+            assert_eq!(Deck::try_from_bytes(&bytes, &DeckVariant::Standard), Err(ContractError::InvalidCard { suit, rank }));
         }
+
+        let mut bytes = Deck::new().to_bytes();
+        bytes[0] = bytes[1];
+        assert_eq!(Deck::try_from_bytes(&bytes, &DeckVariant::Standard), Err(ContractError::CorruptDeck {}));
+        assert_eq!(Deck::try_from_bytes(&bytes[1..], &DeckVariant::Standard), Err(ContractError::CorruptDeck {}));
+    }
+
+    #[test]
+    fn deal_until_exhausted() {
+        let mut deck = Deck::new();
+        let mut dealt = Vec::new();
+        for _ in 0..52 {
+            dealt.push(deck.deal().unwrap());
+        }
+        assert!(deck.cards.is_empty());
+        assert_eq!(dealt.first(), Some(&Card::new(3, 13)));
+        assert_eq!(deck.deal().unwrap_err(), ContractError::DeckExhausted {});
+    }
+
+    #[test]
+    fn all_dealt_cards() {
+        let player_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let other_player_id = Uuid::parse_str("8f204fcc-54a5-4473-8ac3-4845bff291ab").unwrap();
+
+        let table = PokerTableBuilder::new()
+            .player(PlayerBuilder::new(player_id).hand(vec![Card::new(0, 1), Card::new(1, 1)]).build())
+            .player(PlayerBuilder::new(other_player_id).hand(vec![Card::new(2, 1), Card::new(3, 1)]).build())
+            .burned(vec![Card::new(1, 2), Card::new(1, 3), Card::new(1, 4)])
+            .build();
+
+        let dealt = table.all_dealt_cards();
+        assert_eq!(dealt.players.len(), 2);
+        assert_eq!(dealt.players[0], (player_id, vec![Card::new(0, 1), Card::new(1, 1)]));
+        assert_eq!(dealt.players[1], (other_player_id, vec![Card::new(2, 1), Card::new(3, 1)]));
+        assert_eq!(dealt.flop.len(), 3);
+        assert_eq!(dealt.turn, Some(Card::new(0, 5)));
+        assert_eq!(dealt.river, Some(Card::new(0, 6)));
+        assert_eq!(dealt.burned.len(), 3);
+        assert_eq!(dealt.board(), (2..=6).map(|rank| Card::new(0, rank)).collect::<Vec<_>>());
+
+        let flop_only = PokerTableBuilder::new().board((2..=4).map(|rank| Card::new(0, rank)).collect()).build();
+        assert_eq!(flop_only.all_dealt_cards().board(), (2..=4).map(|rank| Card::new(0, rank)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn board_pairing() {
+        let mut unpaired = PokerTableBuilder::new().build().community_cards;
+        assert!(unpaired.revealed_board().is_empty());
+        unpaired.consume_all();
+        assert_eq!(unpaired.revealed_board().len(), 5);
+        assert!(!unpaired.is_paired());
+        assert_eq!(unpaired.board_rank_counts(), [0, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0]);
+
+        let board = vec![Card::new(0, 9), Card::new(1, 9), Card::new(2, 4), Card::new(3, 9), Card::new(0, 1)];
+        let mut paired = PokerTableBuilder::new().board(board).build().community_cards;
+        paired.flop.consumed = true;
+        assert!(paired.is_paired());
+        assert_eq!(paired.board_rank_counts()[8], 2);
+
+        // only the revealed streets count
+        let mut turn_pairs = PokerTableBuilder::new()
+            .board(vec![Card::new(0, 2), Card::new(0, 3), Card::new(0, 4), Card::new(1, 4), Card::new(2, 4)])
+            .build()
+            .community_cards;
+        turn_pairs.flop.consumed = true;
+        assert!(!turn_pairs.is_paired());
+        turn_pairs.consume_all();
+        assert!(turn_pairs.is_paired());
+        assert_eq!(turn_pairs.board_rank_counts()[3], 3);
+    }
+
+    #[test]
+    fn hand_hash() {
+        let player_id = Uuid::parse_str("2928c53b-5d14-4a7c-b56e-83ef56a0644e").unwrap();
+        let table = PokerTableBuilder::new()
+            .player(PlayerBuilder::new(player_id).hand(vec![Card::new(0, 1), Card::new(1, 1)]).build())
+            .build();
+        let hand_hash = table.hand_hash().unwrap();
+
+        // only the digest fields are committed to
+        let mut played = table.clone();
+        played.players[0].folded = true;
+        played.community_cards.flop.consumed = true;
+        played.current_phase = GameState::River;
+        played.button_seat = 1;
+        played.started_at = Timestamp::from_seconds(1);
+        assert_eq!(played.hand_hash().unwrap(), hand_hash);
+
+        let mut reseeded = table.clone();
+        reseeded.shuffle_seed = 1;
+        assert_ne!(reseeded.hand_hash().unwrap(), hand_hash);
+        let mut redealt = table;
+        redealt.players[0].hand[1] = Card::new(2, 1);
+        assert_ne!(redealt.hand_hash().unwrap(), hand_hash);
+    }
+
+    #[test]
+    fn table_builder() {
+        let board = vec![Card::new(1, 10), Card::new(1, 11), Card::new(1, 12), Card::new(1, 13), Card::new(1, 1)];
+        let table = PokerTableBuilder::new()
+            .hand_ref(7)
+            .player(PlayerBuilder::new(Uuid::from_u128(1)).username("alice").hand(vec![Card::new(0, 1), Card::new(0, 13)]).build())
+            .player(PlayerBuilder::new(Uuid::from_u128(2)).username("bob").hand_secret(42).build())
+            .player(PlayerBuilder::new(Uuid::from_u128(3)).public_key("key3").shares(1, 2, 3).build())
+            .board(board.clone())
+            .secrets(10, 20, 30)
+            .current_phase(GameState::Turn)
+            .build();
+
+        assert_eq!(table.hand_ref, 7);
+        assert_eq!(table.players.len(), 3);
+        assert_eq!(table.players[0].username, "alice");
+        assert_eq!(table.players[0].hand.len(), 2);
+        assert_eq!(table.players[1].hand_secret, 42);
+        assert_eq!(table.players[2].public_key, "key3");
+        assert_eq!(table.players[2].river_secret_share, 3);
+        assert_eq!(table.players.iter().map(|player| player.share_index).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(table.all_dealt_cards().board(), board);
+        assert_eq!(table.community_cards.turn.as_ref().unwrap().secret, 20);
+        assert_eq!(table.community_cards.river.as_ref().unwrap().secret, 30);
+        assert_eq!(table.current_phase, GameState::Turn);
+        assert!(!table.community_cards.is_dealt(&GameState::PreFlop));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn card_ordering() {
+        let mut hand = [Card::new(3, 1), Card::new(0, 13), Card::new(2, 2), Card::new(0, 1), Card::new(1, 10)];
+        hand.sort();
+        let sorted: Vec<String> = hand.iter().map(|card| card.to_string()).collect();
+        assert_eq!(sorted, vec!["♥2", "♦10", "♣K", "♣A", "♠A"]);
+
+        // same rank, the suit decides
+        assert!(Card::new(0, 5) < Card::new(1, 5));
+        assert!(Card::new(2, 5) < Card::new(3, 5));
+        assert!(Card::new(3, 13) < Card::new(0, 1));
+        assert_eq!(Card::new(2, 7).cmp(&Card::new(2, 7)), Ordering::Equal);
+    }
+}